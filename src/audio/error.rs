@@ -0,0 +1,101 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type AudioResult<T> = Result<T, AudioError>;
+
+/// Audio file I/O failure with the file and byte offset it happened at.
+#[derive(Debug)]
+pub enum AudioError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    InvalidFormat {
+        path: PathBuf,
+        offset: u64,
+        reason: String,
+    },
+    UnsupportedFormat {
+        path: PathBuf,
+        reason: String,
+    },
+    // data ends before the size declared by the header, the frames before `offset` are readable
+    Truncated {
+        path: PathBuf,
+        offset: u64,
+        recovered_frames: usize,
+    },
+    ChannelMismatch {
+        path: PathBuf,
+        expected: u16,
+        actual: u16,
+    },
+}
+
+impl AudioError {
+    /// Attaches file context to an error from the decoding layer.
+    pub fn decode(path: &Path, offset: u64, error: hound::Error) -> Self {
+        let path = path.to_path_buf();
+        match error {
+            hound::Error::IoError(source) if source.kind() == io::ErrorKind::UnexpectedEof => AudioError::Truncated {
+                path,
+                offset,
+                recovered_frames: 0,
+            },
+            hound::Error::IoError(source) => AudioError::Io { path, source },
+            hound::Error::FormatError(reason) => AudioError::InvalidFormat {
+                path,
+                offset,
+                reason: reason.to_string(),
+            },
+            error => AudioError::UnsupportedFormat {
+                path,
+                reason: error.to_string(),
+            },
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            AudioError::Io { path, .. }
+            | AudioError::InvalidFormat { path, .. }
+            | AudioError::UnsupportedFormat { path, .. }
+            | AudioError::Truncated { path, .. }
+            | AudioError::ChannelMismatch { path, .. } => path,
+        }
+    }
+}
+
+impl Display for AudioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let path = self.path().display();
+        match self {
+            AudioError::Io { source, .. } => write!(f, "{path}: {source}"),
+            AudioError::InvalidFormat { offset, reason, .. } => {
+                write!(f, "{path}: invalid wave file at byte {offset}: {reason}")
+            }
+            AudioError::UnsupportedFormat { reason, .. } => write!(f, "{path}: unsupported format: {reason}"),
+            AudioError::Truncated {
+                offset,
+                recovered_frames,
+                ..
+            } => write!(
+                f,
+                "{path}: data truncated at byte {offset}, {recovered_frames} frames recovered"
+            ),
+            AudioError::ChannelMismatch { expected, actual, .. } => {
+                write!(f, "{path}: expected {expected} channels, found {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AudioError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AudioError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,13 +1,25 @@
 mod audio_unit;
 mod device;
 mod driver;
+mod error;
 mod latency;
+mod npy;
+mod playback;
+mod resampler;
 mod session;
+mod wav64;
 mod wav_file;
+mod wav_stream;
 
 pub use audio_unit::*;
 pub use device::*;
 pub use driver::*;
+pub use error::*;
 pub use latency::*;
+pub use npy::*;
+pub use playback::*;
+pub use resampler::*;
 pub use session::*;
+pub use wav64::*;
 pub use wav_file::*;
+pub use wav_stream::*;
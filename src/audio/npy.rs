@@ -0,0 +1,328 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+// magic, version and header length take 10 bytes, the whole header is padded to this
+const NPY_HEADER_ALIGN: usize = 64;
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+const ZIP_VERSION: u16 = 20;
+const ZIP64_END_OF_CENTRAL_DIR: u32 = 0x0606_4b50;
+const ZIP64_END_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+const ZIP64_VERSION: u16 = 45;
+const ZIP64_LIMIT: u64 = u32::MAX as u64;
+// 1980-01-01, the earliest date a zip entry can carry
+const ZIP_DOS_DATE: u16 = 0x21;
+
+/// Encodes little-endian f32 data as a NumPy `.npy` v1.0 array in C order.
+pub fn npy_bytes(shape: &[usize], data: &[f32]) -> Vec<u8> {
+    debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+    let shape = match shape {
+        [len] => format!("({len},)"),
+        _ => format!(
+            "({})",
+            shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+    let unpadded = NPY_MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(NPY_HEADER_ALIGN) - unpadded));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(NPY_MAGIC.len() + 4 + header.len() + data.len() * 4);
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    data.iter()
+        .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+    bytes
+}
+
+pub fn write_npy<P: AsRef<Path>>(path: P, shape: &[usize], data: &[f32]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&npy_bytes(shape, data))?;
+    writer.flush()
+}
+
+/// Writes raw little-endian f32 samples without any header.
+pub fn write_raw_f32<P: AsRef<Path>>(path: P, data: &[f32]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for value in data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+struct NpzEntry {
+    name: String,
+    crc: u32,
+    size: u64,
+    offset: u64,
+}
+
+/// NumPy `.npz` archive, an uncompressed zip of `.npy` arrays. Arrays and archives past 4 GB are
+/// written with zip64 records.
+pub struct NpzWriter {
+    writer: BufWriter<File>,
+    entries: Vec<NpzEntry>,
+    offset: u64,
+    // sizes and offsets from here on move to zip64 fields, lowered by the tests
+    zip64_limit: u64,
+}
+
+impl NpzWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            entries: Vec::new(),
+            offset: 0,
+            zip64_limit: ZIP64_LIMIT,
+        })
+    }
+
+    /// Adds an array, `np.load` exposes it under `name`.
+    pub fn add(&mut self, name: &str, shape: &[usize], data: &[f32]) -> Result<()> {
+        let name = format!("{name}.npy");
+        let bytes = npy_bytes(shape, data);
+        let size = bytes.len() as u64;
+        let crc = crc32(&bytes);
+        // the local header has no room for the offset, only the sizes can move to its extra field
+        let zip64 = size >= self.zip64_limit;
+        let extra_len: u16 = if zip64 { 20 } else { 0 };
+
+        let w = &mut self.writer;
+        w.write_all(&ZIP_LOCAL_HEADER.to_le_bytes())?;
+        w.write_all(&zip_version(zip64).to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // flags
+        w.write_all(&0u16.to_le_bytes())?; // stored
+        w.write_all(&0u16.to_le_bytes())?; // time
+        w.write_all(&ZIP_DOS_DATE.to_le_bytes())?;
+        w.write_all(&crc.to_le_bytes())?;
+        w.write_all(&zip32(size, zip64).to_le_bytes())?; // compressed
+        w.write_all(&zip32(size, zip64).to_le_bytes())?; // uncompressed
+        w.write_all(&(name.len() as u16).to_le_bytes())?;
+        w.write_all(&extra_len.to_le_bytes())?;
+        w.write_all(name.as_bytes())?;
+        if zip64 {
+            w.write_all(&ZIP64_EXTRA.to_le_bytes())?;
+            w.write_all(&16u16.to_le_bytes())?;
+            w.write_all(&size.to_le_bytes())?; // uncompressed
+            w.write_all(&size.to_le_bytes())?; // compressed
+        }
+        w.write_all(&bytes)?;
+
+        let offset = self.offset;
+        self.offset += 30 + name.len() as u64 + extra_len as u64 + size;
+        self.entries.push(NpzEntry {
+            name,
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        let limit = self.zip64_limit;
+        let directory_offset = self.offset;
+        let mut directory_size = 0u64;
+
+        let w = &mut self.writer;
+        for entry in &self.entries {
+            let (large_size, large_offset) = (entry.size >= limit, entry.offset >= limit);
+            let extra_data = if large_size { 16 } else { 0 } + if large_offset { 8 } else { 0 };
+            let extra_len: u16 = if extra_data > 0 { 4 + extra_data } else { 0 };
+
+            w.write_all(&ZIP_CENTRAL_HEADER.to_le_bytes())?;
+            w.write_all(&ZIP64_VERSION.to_le_bytes())?; // made by
+            w.write_all(&zip_version(extra_len > 0).to_le_bytes())?; // needed
+            w.write_all(&0u16.to_le_bytes())?; // flags
+            w.write_all(&0u16.to_le_bytes())?; // stored
+            w.write_all(&0u16.to_le_bytes())?; // time
+            w.write_all(&ZIP_DOS_DATE.to_le_bytes())?;
+            w.write_all(&entry.crc.to_le_bytes())?;
+            w.write_all(&zip32(entry.size, large_size).to_le_bytes())?;
+            w.write_all(&zip32(entry.size, large_size).to_le_bytes())?;
+            w.write_all(&(entry.name.len() as u16).to_le_bytes())?;
+            w.write_all(&extra_len.to_le_bytes())?;
+            w.write_all(&0u16.to_le_bytes())?; // comment
+            w.write_all(&0u16.to_le_bytes())?; // disk
+            w.write_all(&0u16.to_le_bytes())?; // internal attributes
+            w.write_all(&0u32.to_le_bytes())?; // external attributes
+            w.write_all(&zip32(entry.offset, large_offset).to_le_bytes())?;
+            w.write_all(entry.name.as_bytes())?;
+            // only the fields that did not fit, in this order
+            if extra_len > 0 {
+                w.write_all(&ZIP64_EXTRA.to_le_bytes())?;
+                w.write_all(&extra_data.to_le_bytes())?;
+            }
+            if large_size {
+                w.write_all(&entry.size.to_le_bytes())?;
+                w.write_all(&entry.size.to_le_bytes())?;
+            }
+            if large_offset {
+                w.write_all(&entry.offset.to_le_bytes())?;
+            }
+            directory_size += 46 + entry.name.len() as u64 + extra_len as u64;
+        }
+
+        let count = self.entries.len() as u64;
+        let zip64 = count >= u16::MAX as u64 || directory_offset >= limit || directory_size >= limit;
+        if zip64 {
+            let end_offset = directory_offset + directory_size;
+            w.write_all(&ZIP64_END_OF_CENTRAL_DIR.to_le_bytes())?;
+            w.write_all(&44u64.to_le_bytes())?; // size of the rest of the record
+            w.write_all(&ZIP64_VERSION.to_le_bytes())?; // made by
+            w.write_all(&ZIP64_VERSION.to_le_bytes())?; // needed
+            w.write_all(&0u32.to_le_bytes())?; // disk
+            w.write_all(&0u32.to_le_bytes())?; // directory disk
+            w.write_all(&count.to_le_bytes())?;
+            w.write_all(&count.to_le_bytes())?;
+            w.write_all(&directory_size.to_le_bytes())?;
+            w.write_all(&directory_offset.to_le_bytes())?;
+
+            w.write_all(&ZIP64_END_LOCATOR.to_le_bytes())?;
+            w.write_all(&0u32.to_le_bytes())?; // disk
+            w.write_all(&end_offset.to_le_bytes())?;
+            w.write_all(&1u32.to_le_bytes())?; // disks
+        }
+
+        let count = if zip64 { u16::MAX } else { count as u16 };
+        w.write_all(&ZIP_END_OF_CENTRAL_DIR.to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // disk
+        w.write_all(&0u16.to_le_bytes())?; // directory disk
+        w.write_all(&count.to_le_bytes())?;
+        w.write_all(&count.to_le_bytes())?;
+        w.write_all(&zip32(directory_size, zip64).to_le_bytes())?;
+        w.write_all(&zip32(directory_offset, zip64).to_le_bytes())?;
+        w.write_all(&0u16.to_le_bytes())?; // comment
+        w.flush()
+    }
+}
+
+fn zip_version(zip64: bool) -> u16 {
+    if zip64 { ZIP64_VERSION } else { ZIP_VERSION }
+}
+
+// a 32-bit field, or the marker telling readers to look in the zip64 record
+fn zip32(value: u64, zip64: bool) -> u32 {
+    if zip64 { u32::MAX } else { value as u32 }
+}
+
+// CRC-32/ISO-HDLC as used by zip, a byte at a time
+fn crc32(bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_npy_header() {
+        let bytes = npy_bytes(&[3, 2], &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+
+        assert_eq!(&bytes[0..6], NPY_MAGIC);
+        assert_eq!((10 + header_len) % NPY_HEADER_ALIGN, 0);
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3, 2), }"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(bytes.len(), 10 + header_len + 6 * 4);
+        assert_eq!(&bytes[10 + header_len + 4..10 + header_len + 8], &1f32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_npy_header_1d_shape() {
+        let bytes = npy_bytes(&[4], &[0.0; 4]);
+        let header = String::from_utf8_lossy(&bytes[10..]);
+        assert!(header.contains("'shape': (4,)"));
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_npz_directory() {
+        let path = temp_dir().join("unrecord_test_npz_directory.npz");
+        let mut npz = NpzWriter::create(&path).unwrap();
+        npz.add("a", &[2], &[1.0, 2.0]).unwrap();
+        npz.add("b", &[1, 1], &[3.0]).unwrap();
+        npz.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(&bytes[0..4], &ZIP_LOCAL_HEADER.to_le_bytes());
+        assert_eq!(&bytes[30..35], b"a.npy");
+        let end = &bytes[bytes.len() - 22..];
+        assert_eq!(&end[0..4], &ZIP_END_OF_CENTRAL_DIR.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+        let directory_offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+        assert_eq!(
+            &bytes[directory_offset..directory_offset + 4],
+            &ZIP_CENTRAL_HEADER.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_npz_zip64_records() {
+        let path = temp_dir().join(format!("unrecord_test_npz_zip64_{}.npz", std::process::id()));
+        let mut npz = NpzWriter::create(&path).unwrap();
+        npz.zip64_limit = 0;
+        npz.add("a", &[2], &[1.0, 2.0]).unwrap();
+        npz.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        // the local sizes are in the extra field after the name
+        assert_eq!(u32_at(18), u32::MAX);
+        let size = npy_bytes(&[2], &[1.0, 2.0]).len() as u64;
+        assert_eq!(u64_at(30 + 5 + 4), size);
+
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(end), ZIP_END_OF_CENTRAL_DIR);
+        assert_eq!(u32_at(end + 16), u32::MAX);
+        let locator = end - 20;
+        assert_eq!(u32_at(locator), ZIP64_END_LOCATOR);
+        let record = u64_at(locator + 8) as usize;
+        assert_eq!(u32_at(record), ZIP64_END_OF_CENTRAL_DIR);
+        assert_eq!(u64_at(record + 32), 1);
+        let directory_offset = u64_at(record + 48) as usize;
+        assert_eq!(u32_at(directory_offset), ZIP_CENTRAL_HEADER);
+        assert_eq!(directory_offset as u64, 30 + 5 + 20 + size);
+    }
+}
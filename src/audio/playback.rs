@@ -0,0 +1,70 @@
+use crate::audio::CoreAudioUnit;
+use anyhow::Result;
+use coreaudio::audio_unit::audio_format::LinearPcmFlags;
+use coreaudio::audio_unit::render_callback::data::Interleaved;
+use coreaudio::audio_unit::{AudioUnit, IOType, SampleFormat, StreamFormat, render_callback};
+use objc2_core_audio::AudioObjectID;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const PLAYBACK_CHANNELS: usize = 2;
+
+type Args = render_callback::Args<Interleaved<f32>>;
+
+/// Stereo output to a device, played from a shared queue of interleaved samples.
+pub struct PlaybackStream {
+    io_unit: AudioUnit,
+}
+
+impl PlaybackStream {
+    /// The render callback pops from `queue` and adds the frames it took to `played_frames`,
+    /// an empty queue plays silence. The callback never waits for the queue, it plays silence while the mixer fills it.
+    pub fn open(
+        device_id: AudioObjectID,
+        sample_rate: f64,
+        queue: Arc<Mutex<VecDeque<f32>>>,
+        played_frames: Arc<AtomicUsize>,
+    ) -> Result<Self> {
+        let mut io_unit = AudioUnit::new(IOType::HalOutput)?;
+        io_unit.enable_io_output()?;
+        io_unit.set_device(device_id)?;
+        io_unit.set_sample_rate(sample_rate)?;
+
+        let stream_format = StreamFormat {
+            sample_rate,
+            sample_format: SampleFormat::F32,
+            flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
+            channels: PLAYBACK_CHANNELS as u32,
+        };
+        io_unit.set_output_stream_format_spec(&stream_format)?;
+
+        io_unit.set_render_callback(move |args: Args| {
+            let data: Interleaved<f32> = args.data;
+            let samples = (args.num_frames * PLAYBACK_CHANNELS).min(data.buffer.len());
+            let Ok(mut queue) = queue.try_lock() else {
+                data.buffer[..samples].fill(0.0);
+                return Ok(());
+            };
+            let available = queue.len().min(samples);
+            let source = queue.drain(..available).chain(std::iter::repeat(0.0));
+            for (target, sample) in data.buffer[..samples].iter_mut().zip(source) {
+                *target = sample;
+            }
+            played_frames.fetch_add(available / PLAYBACK_CHANNELS, Ordering::Relaxed);
+            Ok(())
+        })?;
+
+        Ok(Self { io_unit })
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.io_unit.start()?;
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<()> {
+        self.io_unit.stop()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+use std::f64::consts::PI;
+
+// above this the phase table is quantized instead of exact
+const MAX_PHASES: usize = 4096;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResamplerQuality {
+    Low,
+    Medium,
+    High,
+    Best,
+}
+
+impl ResamplerQuality {
+    pub const ALL: [ResamplerQuality; 4] = [Self::Low, Self::Medium, Self::High, Self::Best];
+
+    fn taps(self) -> usize {
+        match self {
+            ResamplerQuality::Low => 16,
+            ResamplerQuality::Medium => 32,
+            ResamplerQuality::High => 64,
+            ResamplerQuality::Best => 128,
+        }
+    }
+
+    // passband edge relative to the lower Nyquist frequency
+    fn rolloff(self) -> f64 {
+        match self {
+            ResamplerQuality::Low => 0.85,
+            ResamplerQuality::Medium => 0.90,
+            ResamplerQuality::High => 0.945,
+            ResamplerQuality::Best => 0.97,
+        }
+    }
+
+    fn kaiser_beta(self) -> f64 {
+        match self {
+            ResamplerQuality::Low => 6.0,
+            ResamplerQuality::Medium => 7.0,
+            ResamplerQuality::High => 8.6,
+            ResamplerQuality::Best => 10.0,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ResamplerQuality::Low => "Low",
+            ResamplerQuality::Medium => "Medium",
+            ResamplerQuality::High => "High",
+            ResamplerQuality::Best => "Best",
+        }
+    }
+}
+
+/// Band-limited polyphase windowed-sinc resampler for a fixed rational ratio.
+#[derive(Clone)]
+pub struct Resampler {
+    up: usize,
+    down: usize,
+    taps: usize,
+    phases: usize,
+    coefficients: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32, quality: ResamplerQuality) -> Self {
+        assert!(from_rate > 0 && to_rate > 0);
+        let divisor = gcd(from_rate as usize, to_rate as usize);
+        let up = to_rate as usize / divisor;
+        let down = from_rate as usize / divisor;
+
+        let taps = quality.taps();
+        let phases = up.min(MAX_PHASES);
+        let cutoff = (up as f64 / down as f64).min(1.0) * quality.rolloff();
+        let beta = quality.kaiser_beta();
+        let half = (taps / 2) as f64;
+
+        let mut coefficients = Vec::with_capacity(phases * taps);
+        for phase in 0..phases {
+            let frac = phase as f64 / phases as f64;
+            let start = coefficients.len();
+            for tap in 0..taps {
+                let distance = frac + half - 1.0 - tap as f64;
+                let value = cutoff * sinc(cutoff * distance) * kaiser(distance / half, beta);
+                coefficients.push(value as f32);
+            }
+            // unity DC gain for every phase
+            let sum: f32 = coefficients[start..].iter().sum();
+            if sum.abs() > f32::EPSILON {
+                coefficients[start..].iter_mut().for_each(|c| *c /= sum);
+            }
+        }
+
+        Self {
+            up,
+            down,
+            taps,
+            phases,
+            coefficients,
+        }
+    }
+
+    pub fn output_frames(&self, input_frames: usize) -> usize {
+        (input_frames * self.up).div_ceil(self.down)
+    }
+
+    pub fn process_interleaved(&self, input: &[f32], channels: usize) -> Vec<f32> {
+        if self.up == self.down {
+            return input.to_vec();
+        }
+        let input_frames = input.len() / channels;
+        let output_frames = self.output_frames(input_frames);
+        let half = self.taps / 2;
+
+        let mut output = vec![0f32; output_frames * channels];
+        for frame in 0..output_frames {
+            let position = frame * self.down;
+            let index = position / self.up;
+            let phase = (position % self.up) * self.phases / self.up;
+            let kernel = &self.coefficients[phase * self.taps..(phase + 1) * self.taps];
+
+            let first = index as isize - half as isize + 1;
+            for (tap, coefficient) in kernel.iter().enumerate() {
+                let source = first + tap as isize;
+                if source < 0 || source as usize >= input_frames {
+                    continue;
+                }
+                let source = source as usize * channels;
+                for channel in 0..channels {
+                    output[frame * channels + channel] += input[source + channel] * coefficient;
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Converts interleaved samples between rates, returns the input as is when rates match.
+pub fn resample_interleaved(
+    samples: &[f32],
+    channels: usize,
+    from_rate: u32,
+    to_rate: u32,
+    quality: ResamplerQuality,
+) -> Vec<f32> {
+    if from_rate == to_rate {
+        return samples.to_vec();
+    }
+    Resampler::new(from_rate, to_rate, quality).process_interleaved(samples, channels)
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn kaiser(x: f64, beta: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(beta * (1.0 - x * x).sqrt()) / bessel_i0(beta)
+}
+
+// zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= half / k as f64;
+        let next = term * term;
+        sum += next;
+        if next < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sine(rate: u32, frequency: f64, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * PI * frequency * i as f64 / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_ratio() {
+        let resampler = Resampler::new(44100, 48000, ResamplerQuality::High);
+        assert_eq!(resampler.up, 160);
+        assert_eq!(resampler.down, 147);
+        assert_eq!(resampler.output_frames(44100), 48000);
+    }
+
+    #[test]
+    fn test_same_rate_is_identity() {
+        let samples = sine(48000, 1000.0, 256);
+        let actual = resample_interleaved(&samples, 1, 48000, 48000, ResamplerQuality::Low);
+        assert_eq!(actual, samples);
+    }
+
+    #[test]
+    fn test_dc_gain() {
+        let samples = vec![0.5f32; 2 * 4096];
+        let output = Resampler::new(44100, 48000, ResamplerQuality::High).process_interleaved(&samples, 2);
+        // skip filter edges
+        for sample in &output[256..output.len() - 256] {
+            assert!((sample - 0.5).abs() < 1e-4, "sample={sample}");
+        }
+    }
+
+    #[test]
+    fn test_sine_up_sampling() {
+        let input = sine(44100, 1000.0, 4410);
+        let expected = sine(48000, 1000.0, 4800);
+        let output = Resampler::new(44100, 48000, ResamplerQuality::Best).process_interleaved(&input, 1);
+
+        assert_eq!(output.len(), expected.len());
+        for i in 256..(expected.len() - 256) {
+            assert!((output[i] - expected[i]).abs() < 1e-3, "frame={i}");
+        }
+    }
+
+    #[test]
+    fn test_down_sampling_rejects_above_nyquist() {
+        // 30 kHz folds into the audible band at 48 kHz unless filtered
+        let input = sine(96000, 30000.0, 9600);
+        let output = Resampler::new(96000, 48000, ResamplerQuality::High).process_interleaved(&input, 1);
+        let peak = output[256..output.len() - 256]
+            .iter()
+            .fold(0f32, |max, s| max.max(s.abs()));
+        assert!(peak < 0.01, "peak={peak}");
+    }
+}
@@ -1,6 +1,8 @@
 use crate::audio::{
-    CoreAudioUnit, estimate_latency_by_peak_in_window_f32_stereo_interleaved_frames,
-    make_impulse_test_f32_stereo_interleaved, read_file, sample_stats, write_file,
+    CoreAudioDevice, CoreAudioUnit, ResamplerQuality,
+    estimate_latency_by_peak_in_window_f32_stereo_interleaved_frames,
+    make_impulse_test_f32_stereo_interleaved, read_file, resample_interleaved, sample_stats,
+    write_file,
 };
 use anyhow::{Context, Result};
 use async_std::task;
@@ -21,11 +23,30 @@ const SAMPLE_FORMAT: SampleFormat = SampleFormat::F32;
 
 type Args = render_callback::Args<Interleaved<f32>>;
 
+/// How a source whose rate differs from the device rate is handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SessionResampling {
+    // None leaves rate conversion to the OS
+    pub quality: Option<ResamplerQuality>,
+    pub convert_back: bool,
+}
+
+impl Default for SessionResampling {
+    fn default() -> Self {
+        Self {
+            quality: Some(ResamplerQuality::High),
+            convert_back: true,
+        }
+    }
+}
+
 pub struct RecordSession {
     destination_path: PathBuf,
     io_unit: Option<AudioUnit>,
     device_id: AudioObjectID,
     sample_rate: f64,
+    source_sample_rate: u32,
+    resampling: SessionResampling,
     source_samples: Vec<f32>,
     pre_silence_frames: usize,
     post_silence_frames: usize,
@@ -41,13 +62,31 @@ impl RecordSession {
         device_id: AudioObjectID,
         source_path: F,
         destination_path: T,
+        resampling: SessionResampling,
     ) -> Result<Self> {
         let source_path = source_path.as_ref().to_path_buf();
         let destination_path = destination_path.as_ref().to_path_buf();
 
-        let (spec, source_samples) = read_file(&source_path)?;
-        let pre_silence_frames = (0.25 * spec.sample_rate as f64) as usize;
-        let post_silence_frames = (0.75 * spec.sample_rate as f64) as usize;
+        let (spec, original_samples) = read_file(&source_path)?;
+
+        // the OS converter takes the source rate, the device is only asked when resampling here
+        let session_sample_rate = match resampling.quality {
+            Some(_) => CoreAudioDevice::from_id(device_id).get_sample_rate()? as u32,
+            None => spec.sample_rate,
+        };
+        let source_samples = match resampling.quality {
+            Some(quality) if session_sample_rate != spec.sample_rate => {
+                info!("resample source {} Hz -> {} Hz", spec.sample_rate, session_sample_rate);
+                // logged here to compare with the converted back reference, the original is not kept
+                sample_stats(&original_samples);
+                let (from, to) = (spec.sample_rate, session_sample_rate);
+                resample_interleaved(&original_samples, 2, from, to, quality)
+            }
+            _ => original_samples,
+        };
+
+        let pre_silence_frames = (0.25 * session_sample_rate as f64) as usize;
+        let post_silence_frames = (0.75 * session_sample_rate as f64) as usize;
 
         let impulse_amp: f32 = 0.75;
         let test_samples = make_impulse_test_f32_stereo_interleaved(
@@ -58,7 +97,7 @@ impl RecordSession {
         );
 
         let output_len = test_samples.len() + source_samples.len();
-        let record_duration = output_len as f64 / spec.sample_rate as f64 / 2.0 + 1.0;
+        let record_duration = output_len as f64 / session_sample_rate as f64 / 2.0 + 1.0;
         let record_duration = Duration::from_secs_f64(record_duration);
 
         let mut output_samples: VecDeque<f32> = VecDeque::with_capacity(output_len);
@@ -72,7 +111,9 @@ impl RecordSession {
             destination_path,
             io_unit: Some(io_unit),
             device_id,
-            sample_rate: spec.sample_rate as f64,
+            sample_rate: session_sample_rate as f64,
+            source_sample_rate: spec.sample_rate,
+            resampling,
             source_samples,
             pre_silence_frames,
             post_silence_frames,
@@ -201,18 +242,65 @@ impl RecordSession {
         sample_stats(&self.source_samples);
         sample_stats(&final_samples);
 
+        let session_sample_rate = self.sample_rate as u32;
+        let (final_sample_rate, final_samples) = match self.resampling.quality {
+            Some(quality)
+                if self.resampling.convert_back && session_sample_rate != self.source_sample_rate =>
+            {
+                info!(
+                    "resample capture {} Hz -> {} Hz",
+                    session_sample_rate, self.source_sample_rate
+                );
+                let samples = resample_interleaved(
+                    &final_samples,
+                    2,
+                    session_sample_rate,
+                    self.source_sample_rate,
+                    quality,
+                );
+
+                // the same round trip without the device, isolates what the converter contributes
+                let reference = resample_interleaved(
+                    &self.source_samples,
+                    2,
+                    session_sample_rate,
+                    self.source_sample_rate,
+                    quality,
+                );
+                let reference_path = self.src_reference_path();
+                sample_stats(&reference);
+                info!("write src reference to {:?}", &reference_path);
+                write_file(&reference_path, self.source_sample_rate as f64, &reference)?;
+
+                (self.source_sample_rate as f64, samples)
+            }
+            _ => (self.sample_rate, final_samples),
+        };
+
         info!("write result to {:?}", &self.destination_path);
-        write_file(&self.destination_path, self.sample_rate, &final_samples)?;
+        write_file(&self.destination_path, final_sample_rate, &final_samples)?;
         info!("done");
         Ok(())
     }
 
+    fn src_reference_path(&self) -> PathBuf {
+        let stem = self
+            .destination_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.destination_path.with_file_name(format!("{stem}_src_only.wav"))
+    }
+
     pub async fn new<F: AsRef<Path>, T: AsRef<Path>>(
         device_id: AudioObjectID,
         source_path: F,
         destination_path: T,
+        resampling: SessionResampling,
     ) -> Result<Self> {
-        task::block_on(async move { Self::new_blocking(device_id, source_path, destination_path) })
+        task::block_on(async move {
+            Self::new_blocking(device_id, source_path, destination_path, resampling)
+        })
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -0,0 +1,524 @@
+use crate::audio::WavStream;
+use crate::audio::error::AudioResult;
+use crate::audio::wav_file::{i16_to_f32, i32_24bit_to_f32, i32_to_f32, u8_to_f32};
+use hound::{Error, Result, SampleFormat, WavSpec};
+use log::warn;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Sony Wave64 uses GUIDs instead of FourCC chunk ids
+const W64_RIFF_GUID: [u8; 16] = [
+    0x72, 0x69, 0x66, 0x66, 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+const W64_WAVE_GUID: [u8; 16] = [
+    0x77, 0x61, 0x76, 0x65, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+const W64_FMT_GUID: [u8; 16] = [
+    0x66, 0x6D, 0x74, 0x20, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+const W64_DATA_GUID: [u8; 16] = [
+    0x64, 0x61, 0x74, 0x61, 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+
+const FORMAT_PCM: u16 = 0x0001;
+const FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+const RF64_SIZE_PLACEHOLDER: u32 = 0xFFFF_FFFF;
+
+/// Largest `data` chunk a plain RIFF file can address, header included.
+pub const RIFF_MAX_DATA_SIZE: u64 = u32::MAX as u64 - 36;
+
+const DECODE_CHUNK_FRAMES: usize = 64 * 1024;
+// `fmt ` and `ds64` are a few dozen bytes, larger sizes come from a damaged header
+const MAX_HEADER_CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WavContainer {
+    Riff,
+    Rf64,
+    Wave64,
+}
+
+impl WavContainer {
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 16];
+        reader.read_exact(&mut magic)?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        if magic == W64_RIFF_GUID {
+            return Ok(WavContainer::Wave64);
+        }
+        match &magic[0..4] {
+            b"RIFF" => Ok(WavContainer::Riff),
+            b"RF64" | b"BW64" => Ok(WavContainer::Rf64),
+            _ => Err(Error::FormatError("unknown wave container")),
+        }
+    }
+}
+
+/// Layout of the sample data as described by the `fmt ` chunk.
+#[derive(Copy, Clone, Debug)]
+struct WavFormat {
+    spec: WavSpec,
+    bytes_per_sample: usize,
+}
+
+impl WavFormat {
+    fn parse(chunk: &[u8]) -> Result<Self> {
+        if chunk.len() < 16 {
+            return Err(Error::FormatError("fmt chunk too short"));
+        }
+        let mut format_tag = read_u16(&chunk[0..]);
+        let channels = read_u16(&chunk[2..]);
+        let sample_rate = read_u32(&chunk[4..]);
+        let block_align = read_u16(&chunk[12..]) as usize;
+        let bits_per_sample = read_u16(&chunk[14..]);
+
+        if format_tag == FORMAT_EXTENSIBLE {
+            if chunk.len() < 40 {
+                return Err(Error::FormatError("extensible fmt chunk too short"));
+            }
+            // first two bytes of the sub format GUID carry the actual format tag
+            format_tag = read_u16(&chunk[24..]);
+        }
+
+        if channels == 0 || block_align == 0 || !block_align.is_multiple_of(channels as usize) {
+            return Err(Error::FormatError("invalid block align"));
+        }
+        let bytes_per_sample = block_align / channels as usize;
+
+        let sample_format = match (format_tag, bytes_per_sample) {
+            (FORMAT_PCM, 1..=4) => SampleFormat::Int,
+            (FORMAT_IEEE_FLOAT, 4 | 8) => SampleFormat::Float,
+            _ => return Err(Error::Unsupported),
+        };
+
+        let spec = WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: bits_per_sample.min(bytes_per_sample as u16 * 8),
+            sample_format,
+        };
+        Ok(Self { spec, bytes_per_sample })
+    }
+
+    fn decode(&self, bytes: &[u8], samples: &mut Vec<f32>) {
+        samples.extend(bytes.chunks_exact(self.bytes_per_sample).map(|b| self.decode_sample(b)));
+    }
+
+    // every `channels`-th sample from `channel`, the others are skipped without decoding
+    fn decode_channel(&self, bytes: &[u8], channel: usize, samples: &mut Vec<f32>) {
+        let width = self.bytes_per_sample;
+        let frame_size = width * self.spec.channels as usize;
+        let offset = channel * width;
+        samples.extend(
+            bytes
+                .chunks_exact(frame_size)
+                .map(|frame| self.decode_sample(&frame[offset..offset + width])),
+        );
+    }
+
+    fn decode_sample(&self, b: &[u8]) -> f32 {
+        match (self.spec.sample_format, self.bytes_per_sample) {
+            (SampleFormat::Int, 1) => u8_to_f32(b[0]),
+            (SampleFormat::Int, 2) => i16_to_f32(i16::from_le_bytes([b[0], b[1]])),
+            (SampleFormat::Int, 3) => i32_24bit_to_f32(i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8),
+            (SampleFormat::Int, _) => i32_to_f32(i32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            (SampleFormat::Float, 4) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            (SampleFormat::Float, _) => f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+        }
+    }
+}
+
+fn read_header_chunk<R: Read>(reader: &mut R, size: u64) -> Result<Vec<u8>> {
+    if size > MAX_HEADER_CHUNK_SIZE {
+        return Err(Error::FormatError("header chunk too large"));
+    }
+    let mut chunk = vec![0u8; size as usize];
+    reader.read_exact(&mut chunk)?;
+    Ok(chunk)
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[0..8]);
+    u64::from_le_bytes(buf)
+}
+
+// all channels interleaved, or only `channel`
+fn read_data<R: Read>(reader: &mut R, format: &WavFormat, data_size: u64, channel: Option<usize>) -> Result<Vec<f32>> {
+    let frame_size = format.bytes_per_sample * format.spec.channels as usize;
+    let frame_count = (data_size / frame_size as u64) as usize;
+    let mut samples = match channel {
+        Some(_) => Vec::with_capacity(frame_count),
+        None => Vec::with_capacity(frame_count * format.spec.channels as usize),
+    };
+
+    let mut buffer = vec![0u8; (DECODE_CHUNK_FRAMES * frame_size).min(data_size as usize)];
+    let mut remaining = data_size - data_size % frame_size as u64;
+    while remaining > 0 {
+        let len = remaining.min(buffer.len() as u64) as usize;
+        reader.read_exact(&mut buffer[..len])?;
+        match channel {
+            Some(channel) => format.decode_channel(&buffer[..len], channel, &mut samples),
+            None => format.decode(&buffer[..len], &mut samples),
+        }
+        remaining -= len as u64;
+    }
+    Ok(samples)
+}
+
+/// Location and format of the sample data inside a RIFF, RF64 or Wave64 file.
+#[derive(Copy, Clone, Debug)]
+pub struct WavLayout {
+    format: WavFormat,
+    data_offset: u64,
+    data_size: u64,
+    declared_data_size: u64,
+}
+
+impl WavLayout {
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let container = WavContainer::detect(reader)?;
+        let (format, data_offset, declared_data_size) = match container {
+            WavContainer::Riff | WavContainer::Rf64 => Self::parse_riff(reader)?,
+            WavContainer::Wave64 => Self::parse_w64(reader)?,
+        };
+
+        // an interrupted capture has less data than declared, or no size at all if the header was never finalized
+        let available = reader.seek(SeekFrom::End(0))?.saturating_sub(data_offset);
+        let data_size = if declared_data_size == 0 || declared_data_size > available {
+            available
+        } else {
+            declared_data_size
+        };
+
+        Ok(Self {
+            format,
+            data_offset,
+            data_size,
+            declared_data_size,
+        })
+    }
+
+    // RF64 (EBU Tech 3306) and BW64 take 64-bit sizes from the `ds64` chunk
+    fn parse_riff<R: Read + Seek>(reader: &mut R) -> Result<(WavFormat, u64, u64)> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[8..12] != b"WAVE" {
+            return Err(Error::FormatError("no WAVE tag found"));
+        }
+
+        let mut ds64_data_size: Option<u64> = None;
+        let mut format: Option<WavFormat> = None;
+        loop {
+            let mut chunk_header = [0u8; 8];
+            reader.read_exact(&mut chunk_header)?;
+            let chunk_id = &chunk_header[0..4];
+            let chunk_size = read_u32(&chunk_header[4..]);
+            // chunks are word aligned
+            let padding = (chunk_size & 1) as i64;
+
+            match chunk_id {
+                b"ds64" => {
+                    let chunk = read_header_chunk(reader, chunk_size as u64)?;
+                    if chunk.len() < 24 {
+                        return Err(Error::FormatError("ds64 chunk too short"));
+                    }
+                    ds64_data_size = Some(read_u64(&chunk[8..]));
+                    reader.seek_relative(padding)?;
+                }
+                b"fmt " => {
+                    let chunk = read_header_chunk(reader, chunk_size as u64)?;
+                    format = Some(WavFormat::parse(&chunk)?);
+                    reader.seek_relative(padding)?;
+                }
+                b"data" => {
+                    let format = format.ok_or(Error::FormatError("data chunk before fmt chunk"))?;
+                    let data_size = match ds64_data_size {
+                        Some(data_size) if chunk_size == RF64_SIZE_PLACEHOLDER => data_size,
+                        _ => chunk_size as u64,
+                    };
+                    let data_offset = reader.stream_position()?;
+                    return Ok((format, data_offset, data_size));
+                }
+                _ => reader.seek_relative(chunk_size as i64 + padding)?,
+            }
+        }
+    }
+
+    // Wave64 chunk ids are GUIDs and chunks are 8-byte aligned
+    fn parse_w64<R: Read + Seek>(reader: &mut R) -> Result<(WavFormat, u64, u64)> {
+        let mut header = [0u8; 40];
+        reader.read_exact(&mut header)?;
+        if header[0..16] != W64_RIFF_GUID || header[24..40] != W64_WAVE_GUID {
+            return Err(Error::FormatError("no Wave64 header found"));
+        }
+
+        let mut format: Option<WavFormat> = None;
+        loop {
+            let mut chunk_header = [0u8; 24];
+            reader.read_exact(&mut chunk_header)?;
+            // the chunk size includes its own 24-byte header
+            let body_size = read_u64(&chunk_header[16..])
+                .checked_sub(24)
+                .ok_or(Error::FormatError("invalid Wave64 chunk size"))?;
+            let padding = (8 - body_size % 8) % 8;
+
+            if chunk_header[0..16] == W64_FMT_GUID {
+                let chunk = read_header_chunk(reader, body_size)?;
+                format = Some(WavFormat::parse(&chunk)?);
+                reader.seek_relative(padding as i64)?;
+            } else if chunk_header[0..16] == W64_DATA_GUID {
+                let format = format.ok_or(Error::FormatError("data chunk before fmt chunk"))?;
+                let data_offset = reader.stream_position()?;
+                return Ok((format, data_offset, body_size));
+            } else {
+                reader.seek_relative((body_size + padding) as i64)?;
+            }
+        }
+    }
+
+    pub fn spec(&self) -> WavSpec {
+        self.format.spec
+    }
+
+    pub fn frame_size(&self) -> usize {
+        self.format.bytes_per_sample * self.format.spec.channels as usize
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.data_size / self.frame_size() as u64) as usize
+    }
+
+    /// Byte offset of `frame` in the file.
+    pub fn frame_offset(&self, frame: usize) -> u64 {
+        self.data_offset + frame as u64 * self.frame_size() as u64
+    }
+
+    /// Whether the data size declared by the header disagrees with the file.
+    pub fn is_truncated(&self) -> bool {
+        self.data_size != self.declared_data_size
+    }
+
+    /// Reads up to `frames` interleaved frames starting at `start_frame`.
+    pub fn read_frames<R: Read + Seek>(&self, reader: &mut R, start_frame: usize, frames: usize) -> Result<Vec<f32>> {
+        let start_frame = start_frame.min(self.frame_count());
+        let frames = frames.min(self.frame_count() - start_frame);
+        let frame_size = self.frame_size() as u64;
+
+        reader.seek(SeekFrom::Start(self.data_offset + start_frame as u64 * frame_size))?;
+        read_data(reader, &self.format, frames as u64 * frame_size, None)
+    }
+
+    /// Reads `channel` of up to `frames` frames starting at `start_frame`.
+    pub fn read_channel<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        channel: usize,
+        start_frame: usize,
+        frames: usize,
+    ) -> Result<Vec<f32>> {
+        if channel >= self.format.spec.channels as usize {
+            return Err(Error::FormatError("channel out of range"));
+        }
+        let start_frame = start_frame.min(self.frame_count());
+        let frames = frames.min(self.frame_count() - start_frame);
+        let frame_size = self.frame_size() as u64;
+
+        reader.seek(SeekFrom::Start(self.data_offset + start_frame as u64 * frame_size))?;
+        read_data(reader, &self.format, frames as u64 * frame_size, Some(channel))
+    }
+}
+
+/// Reads a whole RIFF, RF64 or Wave64 file, a truncated one is read up to the last complete frame.
+pub fn read_layout_file<P: AsRef<Path>>(path: P) -> AudioResult<(WavSpec, Vec<f32>)> {
+    let stream = WavStream::open(path)?;
+    if let Some(truncation) = stream.truncation() {
+        warn!("{truncation}");
+    }
+    let samples = stream.read_frames(0, stream.frame_count())?;
+    Ok((stream.spec(), samples))
+}
+
+/// Writes interleaved 32-bit float samples as RF64, which has no 4 GB size limit.
+pub fn write_rf64<P: AsRef<Path>>(path: P, spec: WavSpec, samples: &[f32]) -> Result<()> {
+    if spec.sample_format != SampleFormat::Float || spec.bits_per_sample != 32 {
+        return Err(Error::Unsupported);
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    let block_align = spec.channels as u32 * 4;
+    let data_size = samples.len() as u64 * 4;
+    let frame_count = samples.len() as u64 / spec.channels as u64;
+    let riff_size = 4 + (8 + 28) + (8 + 16) + 8 + data_size;
+
+    writer.write_all(b"RF64")?;
+    writer.write_all(&RF64_SIZE_PLACEHOLDER.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"ds64")?;
+    writer.write_all(&28u32.to_le_bytes())?;
+    writer.write_all(&riff_size.to_le_bytes())?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    writer.write_all(&frame_count.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?; // table length
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&FORMAT_IEEE_FLOAT.to_le_bytes())?;
+    writer.write_all(&spec.channels.to_le_bytes())?;
+    writer.write_all(&spec.sample_rate.to_le_bytes())?;
+    writer.write_all(&(spec.sample_rate * block_align).to_le_bytes())?;
+    writer.write_all(&(block_align as u16).to_le_bytes())?;
+    writer.write_all(&32u16.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&RF64_SIZE_PLACEHOLDER.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::audio::AudioError;
+    use std::env::temp_dir;
+
+    fn float_spec() -> WavSpec {
+        WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        }
+    }
+
+    #[test]
+    fn test_rf64_round_trip() {
+        let path = temp_dir().join("unrecord_test_rf64_round_trip.wav");
+        let samples: Vec<f32> = (0..2048).map(|i| (i as f32 / 2048.0) - 0.5).collect();
+
+        write_rf64(&path, float_spec(), &samples).unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(WavContainer::detect(&mut file).unwrap(), WavContainer::Rf64);
+
+        let (spec, actual) = read_layout_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(spec, float_spec());
+        assert_eq!(actual, samples);
+    }
+
+    #[test]
+    fn test_read_channel_decodes_one_channel() {
+        let path = temp_dir().join("unrecord_test_read_channel_decodes_one_channel.wav");
+        let samples: Vec<f32> = (0..2048).map(|i| i as f32 / 2048.0).collect();
+        write_rf64(&path, float_spec(), &samples).unwrap();
+
+        let stream = WavStream::open(&path).unwrap();
+        let right = stream.read_channel(1, 10, 20).unwrap();
+        let past_end = stream.read_channel(0, 1020, 2000).unwrap();
+        let invalid = stream.read_channel(2, 0, 10);
+        std::fs::remove_file(&path).ok();
+
+        let expected: Vec<f32> = samples[21..40].iter().step_by(2).copied().collect();
+        assert_eq!(right, expected);
+        assert_eq!(
+            past_end,
+            vec![samples[2040], samples[2042], samples[2044], samples[2046]]
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_truncated_data_recovers_prefix() {
+        let path = temp_dir().join("unrecord_test_truncated_data_recovers_prefix.wav");
+        let samples: Vec<f32> = (0..2048).map(|i| i as f32 / 2048.0).collect();
+        write_rf64(&path, float_spec(), &samples).unwrap();
+
+        // cut the file in the middle of a frame
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 4 * 1024 - 2]).unwrap();
+
+        let stream = WavStream::open(&path).unwrap();
+        let truncation = stream.truncation();
+        let actual = stream.read_frames(0, stream.frame_count()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(
+            truncation,
+            Some(AudioError::Truncated {
+                recovered_frames: 511,
+                ..
+            })
+        ));
+        assert_eq!(actual, samples[..511 * 2]);
+    }
+
+    #[test]
+    fn test_w64_read_pcm16() {
+        let path = temp_dir().join("unrecord_test_w64_read_pcm16.w64");
+        let frames: [i16; 4] = [0, 16384, -16384, i16::MIN];
+
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&44100u32.to_le_bytes());
+        fmt.extend_from_slice(&(44100u32 * 4).to_le_bytes());
+        fmt.extend_from_slice(&4u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+
+        let data: Vec<u8> = frames.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&W64_RIFF_GUID);
+        let total = 40 + (24 + 16) + (24 + data.len() as u64);
+        bytes.extend_from_slice(&total.to_le_bytes());
+        bytes.extend_from_slice(&W64_WAVE_GUID);
+        bytes.extend_from_slice(&W64_FMT_GUID);
+        bytes.extend_from_slice(&(24 + fmt.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&fmt);
+        bytes.extend_from_slice(&W64_DATA_GUID);
+        bytes.extend_from_slice(&(24 + data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        assert_eq!(WavContainer::detect(&mut file).unwrap(), WavContainer::Wave64);
+        let (spec, samples) = read_layout_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.sample_rate, 44100);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, SampleFormat::Int);
+        assert_eq!(samples, vec![0.0, 0.5, -0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_oversized_header_chunk_is_rejected() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&36u32.to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+
+        let result = WavLayout::parse(&mut std::io::Cursor::new(bytes));
+        assert!(matches!(result, Err(Error::FormatError("header chunk too large"))));
+    }
+}
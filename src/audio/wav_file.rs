@@ -1,4 +1,6 @@
-use hound::{Error, Result, WavReader, WavSpec, WavWriter};
+use crate::audio::error::{AudioError, AudioResult};
+use crate::audio::wav64::{RIFF_MAX_DATA_SIZE, read_layout_file, write_rf64};
+use hound::{Result, WavSpec, WavWriter};
 use log::info;
 use std::path::Path;
 
@@ -7,61 +9,37 @@ fn i8_to_f32(x: i8) -> f32 {
     (x as f32) / 128.0
 }
 
+// 8-bit wave data is unsigned
 #[inline]
-fn i16_to_f32(x: i16) -> f32 {
+pub(super) fn u8_to_f32(x: u8) -> f32 {
+    i8_to_f32((x as i16 - 128) as i8)
+}
+
+#[inline]
+pub(super) fn i16_to_f32(x: i16) -> f32 {
     (x as f32) / 32768.0
 }
 
 #[inline]
-fn i32_to_f32(x: i32) -> f32 {
+pub(super) fn i32_to_f32(x: i32) -> f32 {
     (x as f32) / 2147483648.0 // 2^31
 }
 
 #[inline]
-fn i32_24bit_to_f32(x: i32) -> f32 {
+pub(super) fn i32_24bit_to_f32(x: i32) -> f32 {
     (x as f32) / 8_388_608.0 // 2^23
 }
 
-pub fn read_file<P: AsRef<Path>>(path: P) -> Result<(WavSpec, Vec<f32>)> {
-    let reader = WavReader::open(path)?;
-    let spec = reader.spec();
+pub fn read_file<P: AsRef<Path>>(path: P) -> AudioResult<(WavSpec, Vec<f32>)> {
+    let path = path.as_ref();
+    let (spec, samples) = read_layout_file(path)?;
     if spec.channels != 2 {
-        return Err(Error::FormatError("expected 2 channels"));
+        return Err(AudioError::ChannelMismatch {
+            path: path.to_path_buf(),
+            expected: 2,
+            actual: spec.channels,
+        });
     }
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Float => reader.into_samples::<f32>().map(|s| s.unwrap()).collect(),
-        hound::SampleFormat::Int => match spec.bits_per_sample {
-            8 => reader
-                .into_samples::<i8>()
-                .map(|s| {
-                    let sample = s.unwrap();
-                    i8_to_f32(sample)
-                })
-                .collect(),
-            16 => reader
-                .into_samples::<i16>()
-                .map(|s| {
-                    let sample = s.unwrap();
-                    i16_to_f32(sample)
-                })
-                .collect(),
-            24 => reader
-                .into_samples::<i32>()
-                .map(|s| {
-                    let sample = s.unwrap();
-                    i32_24bit_to_f32(sample)
-                })
-                .collect(),
-            32 => reader
-                .into_samples::<i32>()
-                .map(|s| {
-                    let sample = s.unwrap();
-                    i32_to_f32(sample)
-                })
-                .collect(),
-            _ => return Err(Error::FormatError("Unsupported sample format")),
-        },
-    };
     Ok((spec, samples))
 }
 
@@ -72,12 +50,16 @@ pub fn write_file<P: AsRef<Path>>(path: P, sample_rate: f64, samples: &Vec<f32>)
         bits_per_sample: 32,
         sample_format: hound::SampleFormat::Float,
     };
+    if samples.len() as u64 * 4 > RIFF_MAX_DATA_SIZE {
+        info!("write rf64 file");
+        return write_rf64(path, spec, samples);
+    }
     info!("open wav writer");
     let mut writer = WavWriter::create(path, spec)?;
     info!("write samples");
-    samples
-        .iter()
-        .for_each(|s| writer.write_sample(*s).unwrap());
+    for sample in samples {
+        writer.write_sample(*sample)?;
+    }
     info!("flush wav file");
     writer.finalize()?;
     Ok(())
@@ -0,0 +1,113 @@
+use crate::audio::WavLayout;
+use crate::audio::error::{AudioError, AudioResult};
+use hound::WavSpec;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+/// Seekable reader over the sample data of a wave file, only requested frames are kept in memory.
+/// Reads are positional, so any number of threads read concurrently without sharing a cursor.
+pub struct WavStream {
+    path: PathBuf,
+    layout: WavLayout,
+    file: File,
+}
+
+// cursor of one read over the shared file
+struct PositionedReader<'a> {
+    file: &'a File,
+    position: u64,
+}
+
+impl Read for PositionedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.file.read_at(buf, self.position)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for PositionedReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.file.metadata()?.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or(io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.position)
+    }
+}
+
+impl WavStream {
+    pub fn open<P: AsRef<Path>>(path: P) -> AudioResult<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| AudioError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut reader = PositionedReader {
+            file: &file,
+            position: 0,
+        };
+        let layout = WavLayout::parse(&mut reader).map_err(|error| AudioError::decode(path, reader.position, error))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            layout,
+            file,
+        })
+    }
+
+    pub fn spec(&self) -> WavSpec {
+        self.layout.spec()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.layout.frame_count()
+    }
+
+    /// Set when the file holds less data than its header declares, only the complete frames are readable.
+    pub fn truncation(&self) -> Option<AudioError> {
+        self.layout.is_truncated().then(|| AudioError::Truncated {
+            path: self.path.clone(),
+            offset: self.layout.frame_offset(self.frame_count()),
+            recovered_frames: self.frame_count(),
+        })
+    }
+
+    /// Interleaved samples of `frames` frames starting at `start_frame`.
+    pub fn read_frames(&self, start_frame: usize, frames: usize) -> AudioResult<Vec<f32>> {
+        self.layout
+            .read_frames(&mut self.reader(), start_frame, frames)
+            .map_err(|error| AudioError::decode(&self.path, self.layout.frame_offset(start_frame), error))
+    }
+
+    /// Samples of a single channel in `start_frame..end_frame`, only that channel is decoded.
+    pub fn read_channel(&self, channel: usize, start_frame: usize, end_frame: usize) -> AudioResult<Vec<f32>> {
+        let frames = end_frame.saturating_sub(start_frame);
+        self.layout
+            .read_channel(&mut self.reader(), channel, start_frame, frames)
+            .map_err(|error| AudioError::decode(&self.path, self.layout.frame_offset(start_frame), error))
+    }
+
+    fn reader(&self) -> PositionedReader<'_> {
+        PositionedReader {
+            file: &self.file,
+            position: 0,
+        }
+    }
+
+    /// Walks the whole file sequentially, calling `f` with interleaved chunks of up to `chunk_frames` frames.
+    pub fn for_each_chunk(&self, chunk_frames: usize, mut f: impl FnMut(&[f32])) -> AudioResult<()> {
+        let frame_count = self.frame_count();
+        let mut start_frame = 0;
+        while start_frame < frame_count {
+            let frames = chunk_frames.min(frame_count - start_frame);
+            let chunk = self.read_frames(start_frame, frames)?;
+            f(&chunk);
+            start_frame += frames;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::components::waveform::{ClipExport, ClipExportFormat, WaveClip};
+use anyhow::{Context, Result, anyhow};
+use log::info;
+use std::path::PathBuf;
+
+const EXPORT_USAGE: &str = "usage: unrecord export <input.wav> <output> [--format npy|npz|raw] \
+[--start <frame>] [--end <frame>] [--reference <reference.wav>] [--no-mipmap] [--no-analysis]";
+
+/// Runs a command line subcommand, `None` when the arguments do not name one and the UI should start.
+pub fn run(args: &[String]) -> Option<Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("export") => Some(export(&args[1..])),
+        _ => None,
+    }
+}
+
+fn export(args: &[String]) -> Result<()> {
+    let mut positional = Vec::new();
+    let mut format = ClipExportFormat::Npz;
+    let mut start_frame = None;
+    let mut end_frame = None;
+    let mut reference = None;
+    let mut mip_map = true;
+    let mut analysis = true;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(anyhow!("missing value for {arg}\n{EXPORT_USAGE}"));
+        match arg.as_str() {
+            "--format" => {
+                let value = value()?;
+                format = ClipExportFormat::parse(value).ok_or(anyhow!("unknown format {value}"))?;
+            }
+            "--start" => start_frame = Some(value()?.parse::<usize>().context("invalid --start")?),
+            "--end" => end_frame = Some(value()?.parse::<usize>().context("invalid --end")?),
+            "--reference" => reference = Some(PathBuf::from(value()?)),
+            "--no-mipmap" => mip_map = false,
+            "--no-analysis" => analysis = false,
+            _ if arg.starts_with("--") => return Err(anyhow!("unknown option {arg}\n{EXPORT_USAGE}")),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+    let [input, output] = positional.as_slice() else {
+        return Err(anyhow!(EXPORT_USAGE));
+    };
+
+    let clip = WaveClip::open(input)?;
+    if let Some(truncation) = clip.truncation() {
+        eprintln!("warning: {truncation}");
+    }
+    let start_frame = start_frame.unwrap_or(0);
+    let end_frame = end_frame.unwrap_or(clip.frame_count());
+    let mut export = ClipExport::new(&clip)
+        .range(start_frame, end_frame)
+        .mip_map(mip_map)
+        .analysis(analysis);
+    if let Some(reference) = reference {
+        let reference = WaveClip::open(reference)?;
+        export = export.reference(&reference, 0, reference.frame_count(), 0);
+    }
+
+    for path in export.write(format, output)? {
+        info!("exported {:?}", path);
+        println!("{}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use crate::audio::AudioResult;
+use crate::components::region::TrackRegion;
+use crate::components::track::TRACK_COLORS;
+use crate::components::waveform::{WaveForm, measure_lag};
+use log::error;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// frames from the start of each region correlated against the first layer
+const LAG_WINDOW_FRAMES: usize = 1 << 15;
+// furthest lag looked for either way, covers the latency of any audio interface
+const MAX_LAG_FRAMES: usize = 1 << 14;
+// frames of each layer read at a time while the difference peaks are built
+const DIFFERENCE_CHUNK_FRAMES: usize = 64 * 1024;
+
+/// Region drawn on the comparison lane, its audio comes `lag` frames later than that of the first layer.
+#[derive(Clone)]
+pub struct ComparisonLayer {
+    pub region: TrackRegion,
+    pub color: u32,
+    pub lag: isize,
+}
+
+impl ComparisonLayer {
+    /// Lane frames covered by the layer, lane frames count from the start of the first layer.
+    pub fn lane_range(&self) -> (isize, isize) {
+        (-self.lag, self.region.frames() as isize - self.lag)
+    }
+
+    /// Frames after the region start at `lane_frame`.
+    pub fn region_frame(&self, lane_frame: isize) -> isize {
+        lane_frame + self.lag
+    }
+
+    pub fn title(&self) -> String {
+        self.region.clip().metadata().filename().to_string()
+    }
+}
+
+/// Front layer minus the layer behind it as they play, one waveform per channel from lane frame `start`.
+#[derive(Clone)]
+pub struct ComparisonDifference {
+    pub start: isize,
+    pub channels: Vec<WaveForm>,
+}
+
+/// Layers whose difference is wanted, computed off the main thread and handed back with `generation`.
+pub struct PendingDifference {
+    pub generation: usize,
+    front: ComparisonLayer,
+    back: ComparisonLayer,
+}
+
+impl PendingDifference {
+    /// Reads both layers in full, see `GridComparison::finish_difference`.
+    pub fn compute(&self) -> AudioResult<ComparisonDifference> {
+        layer_difference(&self.front, &self.back)
+    }
+}
+
+struct GridComparisonInner {
+    // track frame of lane frame 0
+    anchor: usize,
+    layers: Vec<ComparisonLayer>,
+    front: usize,
+    show_difference: bool,
+    difference: Option<ComparisonDifference>,
+    // bumped whenever the layers or the front one change, results of older layers are dropped
+    generation: usize,
+    // generation of the difference being computed
+    computing: Option<usize>,
+}
+
+impl GridComparisonInner {
+    fn invalidate_difference(&mut self) {
+        self.difference = None;
+        self.generation += 1;
+    }
+}
+
+/// Overlay of regions on one lane, aligned by their measured lag. The regions are copies taken when the
+/// comparison opens, later edits of the timeline do not change them.
+#[derive(Clone)]
+pub struct GridComparison {
+    inner: Rc<RefCell<GridComparisonInner>>,
+}
+
+impl GridComparison {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(GridComparisonInner {
+                anchor: 0,
+                layers: Vec::new(),
+                front: 0,
+                show_difference: false,
+                difference: None,
+                generation: 0,
+                computing: None,
+            })),
+        }
+    }
+
+    /// Compares `regions` against the first one, needs at least two.
+    pub fn open(&self, regions: Vec<TrackRegion>) -> AudioResult<bool> {
+        let Some(reference) = regions.first().cloned() else {
+            return Ok(false);
+        };
+        if regions.len() < 2 {
+            return Ok(false);
+        }
+        let window = reference.read_channel(0, 0, LAG_WINDOW_FRAMES)?;
+        let layers = regions
+            .into_iter()
+            .enumerate()
+            .map(|(index, region)| {
+                let lag = match index {
+                    0 => 0,
+                    _ => {
+                        let other = region.read_channel(0, 0, LAG_WINDOW_FRAMES)?;
+                        measure_lag(&window, &other, MAX_LAG_FRAMES).unwrap_or(0)
+                    }
+                };
+                Ok(ComparisonLayer {
+                    region,
+                    color: TRACK_COLORS[index % TRACK_COLORS.len()].1,
+                    lag,
+                })
+            })
+            .collect::<AudioResult<Vec<_>>>()?;
+
+        let mut inner = self.inner.borrow_mut();
+        inner.anchor = reference.track_start_frame();
+        inner.layers = layers;
+        inner.front = 0;
+        inner.invalidate_difference();
+        Ok(true)
+    }
+
+    pub fn close(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.layers.clear();
+        inner.invalidate_difference();
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.inner.borrow().layers.is_empty()
+    }
+
+    pub fn anchor(&self) -> usize {
+        self.inner.borrow().anchor
+    }
+
+    pub fn layers(&self) -> Vec<ComparisonLayer> {
+        self.inner.borrow().layers.clone()
+    }
+
+    pub fn front(&self) -> usize {
+        self.inner.borrow().front
+    }
+
+    /// A/B switch, brings the next layer to the front.
+    pub fn toggle_front(&self) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.layers.is_empty() {
+            return;
+        }
+        inner.front = (inner.front + 1) % inner.layers.len();
+        inner.invalidate_difference();
+    }
+
+    /// Layer indices from back to front.
+    pub fn paint_order(&self) -> Vec<usize> {
+        let inner = self.inner.borrow();
+        let front = inner.front;
+        (0..inner.layers.len())
+            .filter(|index| *index != front)
+            .chain((front < inner.layers.len()).then_some(front))
+            .collect()
+    }
+
+    pub fn show_difference(&self) -> bool {
+        self.inner.borrow().show_difference
+    }
+
+    pub fn set_show_difference(&self, show: bool) {
+        self.inner.borrow_mut().show_difference = show;
+    }
+
+    /// Difference of the front layer and the first one, or the second when the first is in front.
+    /// None while shown until `finish_difference` delivers it.
+    pub fn difference(&self) -> Option<ComparisonDifference> {
+        let inner = self.inner.borrow();
+        match inner.show_difference {
+            true => inner.difference.clone(),
+            false => None,
+        }
+    }
+
+    /// Layers to subtract when the difference is shown but neither computed nor computing.
+    pub fn start_difference(&self) -> Option<PendingDifference> {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.show_difference || inner.difference.is_some() || inner.computing == Some(inner.generation) {
+            return None;
+        }
+        let back = if inner.front == 0 { 1 } else { 0 };
+        let pending = PendingDifference {
+            generation: inner.generation,
+            front: inner.layers.get(inner.front)?.clone(),
+            back: inner.layers.get(back)?.clone(),
+        };
+        inner.computing = Some(pending.generation);
+        Some(pending)
+    }
+
+    /// Keeps the result of `PendingDifference::compute` unless the layers changed meanwhile, a failed read
+    /// hides the difference.
+    pub fn finish_difference(&self, generation: usize, difference: AudioResult<ComparisonDifference>) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.generation != generation {
+            return;
+        }
+        inner.computing = None;
+        match difference {
+            Ok(difference) => inner.difference = Some(difference),
+            Err(err) => {
+                error!("comparison difference failed: {err}");
+                inner.show_difference = false;
+            }
+        }
+    }
+}
+
+// the peaks are built a chunk at a time, samples are subtracted again on reads for close zooms
+fn layer_difference(front: &ComparisonLayer, back: &ComparisonLayer) -> AudioResult<ComparisonDifference> {
+    let (front_start, front_end) = front.lane_range();
+    let (back_start, back_end) = back.lane_range();
+    let start = front_start.max(back_start);
+    let end = front_end.min(back_end).max(start);
+
+    let frames = (end - start) as usize;
+    let channel_count = front
+        .region
+        .clip()
+        .channel_count()
+        .min(back.region.clip().channel_count());
+    let channels = (0..channel_count)
+        .map(|channel| {
+            let (front_region, back_region) = (front.region.clone(), back.region.clone());
+            let (front_offset, back_offset) = (front.region_frame(start) as usize, back.region_frame(start) as usize);
+            // frames after lane frame `start`
+            let read = move |from: usize, to: usize| -> AudioResult<Vec<f32>> {
+                let front = front_region.read_channel(channel, front_offset + from, front_offset + to)?;
+                let back = back_region.read_channel(channel, back_offset + from, back_offset + to)?;
+                Ok(front.iter().zip(back).map(|(a, b)| a - b).collect())
+            };
+
+            let mut builder = WaveForm::mip_map_builder();
+            for from in (0..frames).step_by(DIFFERENCE_CHUNK_FRAMES) {
+                let to = (from + DIFFERENCE_CHUNK_FRAMES).min(frames);
+                read(from, to)?.into_iter().for_each(|sample| builder.push(sample));
+            }
+            Ok(WaveForm::from_reader(frames, builder.finish(), read))
+        })
+        .collect::<AudioResult<Vec<_>>>()?;
+    Ok(ComparisonDifference { start, channels })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::waveform::test_clip;
+
+    #[test]
+    fn test_open_and_toggle() {
+        let clip = test_clip("comparison", 4096);
+        let comparison = GridComparison::new();
+        assert!(!comparison.open(vec![TrackRegion::new(&clip, 0, 4096, 100)]).unwrap());
+        assert!(!comparison.is_open());
+
+        let mut inverted = TrackRegion::new(&clip, 0, 4096, 0);
+        inverted.set_inverted(true);
+        assert!(
+            comparison
+                .open(vec![TrackRegion::new(&clip, 0, 4096, 100), inverted])
+                .unwrap()
+        );
+        assert_eq!(comparison.anchor(), 100);
+        assert_eq!(comparison.paint_order(), vec![1, 0]);
+
+        comparison.toggle_front();
+        assert_eq!(comparison.front(), 1);
+        assert_eq!(comparison.paint_order(), vec![0, 1]);
+
+        // the inverted copy differs by twice the signal
+        assert!(comparison.start_difference().is_none());
+        comparison.set_show_difference(true);
+        let pending = comparison.start_difference().unwrap();
+        assert!(comparison.start_difference().is_none());
+        assert!(comparison.difference().is_none());
+        comparison.finish_difference(pending.generation, pending.compute());
+        let difference = comparison.difference().unwrap();
+        assert_eq!(difference.channels.len(), 2);
+        assert_eq!(difference.channels[0].read_frames(0, 4).unwrap(), vec![-0.5; 4]);
+
+        // a result for the layers before the switch is dropped
+        assert!(comparison.start_difference().is_none());
+        comparison.toggle_front();
+        let stale = comparison.start_difference().unwrap();
+        comparison.toggle_front();
+        comparison.finish_difference(stale.generation, stale.compute());
+        assert!(comparison.difference().is_none());
+        assert!(comparison.start_difference().is_some());
+
+        comparison.close();
+        assert!(!comparison.is_open());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::components::region::{RegionId, TrackRegion};
+use crate::components::track::Track;
+use crate::components::waveform::WaveClip;
+use std::collections::HashSet;
+
+/// Copied region with its position relative to the first copied track and frame.
+#[derive(Clone)]
+pub struct ClipboardRegion {
+    pub track_offset: usize,
+    pub frame_offset: usize,
+    pub region: TrackRegion,
+}
+
+pub fn copy_regions(selected: &[(usize, TrackRegion)]) -> Vec<ClipboardRegion> {
+    let first_track = selected.iter().map(|(index, _)| *index).min().unwrap_or(0);
+    let first_frame = selected
+        .iter()
+        .map(|(_, region)| region.track_start_frame())
+        .min()
+        .unwrap_or(0);
+    selected
+        .iter()
+        .map(|(index, region)| ClipboardRegion {
+            track_offset: index - first_track,
+            frame_offset: region.track_start_frame() - first_frame,
+            region: region.clone(),
+        })
+        .collect()
+}
+
+/// Pastes copies of the clipboard with its first region at `track_index` and `frame`, returns the new regions.
+/// Regions falling below the last track are dropped.
+pub fn paste_regions(
+    tracks: &[Track],
+    clipboard: &[ClipboardRegion],
+    track_index: usize,
+    frame: usize,
+) -> Vec<RegionId> {
+    let mut pasted = Vec::new();
+    for item in clipboard {
+        let Some(track) = tracks.get(track_index + item.track_offset) else {
+            continue;
+        };
+        let mut region = item.region.duplicate();
+        region.set_track_start_frame(frame + item.frame_offset);
+        pasted.push(region.id());
+        track.add_region(region);
+    }
+    pasted
+}
+
+/// Places dropped clips at `frame`, one after another on the track at `track_index`,
+/// or each on a new track when the index is past the last track. Returns the new regions.
+pub fn place_clips(tracks: &mut Vec<Track>, clips: &[WaveClip], track_index: usize, frame: usize) -> Vec<RegionId> {
+    let mut placed = Vec::new();
+    let mut next_frame = frame;
+    for clip in clips {
+        let region = TrackRegion::new(clip, 0, clip.frame_count(), next_frame);
+        placed.push(region.id());
+        match tracks.get(track_index) {
+            Some(track) => {
+                next_frame += clip.frame_count();
+                track.add_region(region);
+            }
+            None => {
+                let track = Track::new(clip.metadata().filename());
+                track.add_region(region);
+                tracks.push(track);
+            }
+        }
+    }
+    placed
+}
+
+/// Splits regions crossing `frame`, only `ids` when given. Returns the new right hand regions.
+pub fn split_regions(tracks: &[Track], ids: Option<&HashSet<RegionId>>, frame: usize) -> Vec<RegionId> {
+    let mut created = Vec::new();
+    for track in tracks {
+        let mut regions = track.regions();
+        let mut right_parts = Vec::new();
+        for region in regions.iter_mut() {
+            if ids.is_some_and(|ids| !ids.contains(&region.id())) {
+                continue;
+            }
+            if let Some(right) = region.split_at(frame) {
+                created.push(right.id());
+                right_parts.push(right);
+            }
+        }
+        if !right_parts.is_empty() {
+            regions.extend(right_parts);
+            track.set_regions(regions);
+            track.clean_regions();
+        }
+    }
+    created
+}
+
+/// Places a copy of the regions right after the span they cover, returns the copies.
+pub fn duplicate_regions(tracks: &[Track], selected: &[(usize, TrackRegion)]) -> Vec<RegionId> {
+    let start_frame = selected.iter().map(|(_, r)| r.track_start_frame()).min().unwrap_or(0);
+    let end_frame = selected.iter().map(|(_, r)| r.track_end_frame()).max().unwrap_or(0);
+    let clipboard = copy_regions(selected);
+    let first_track = selected.iter().map(|(index, _)| *index).min().unwrap_or(0);
+    paste_regions(tracks, &clipboard, first_track, end_frame.max(start_frame))
+}
+
+/// Moves the track at `from` in front of the track at `to`, an index past the last track moves it to the end.
+pub fn move_track(tracks: &mut Vec<Track>, from: usize, to: usize) {
+    if from >= tracks.len() || from == to {
+        return;
+    }
+    let track = tracks.remove(from);
+    // the tracks after `from` moved up by one
+    let to = if from < to { to - 1 } else { to };
+    tracks.insert(to.min(tracks.len()), track);
+}
+
+/// Removes the regions, `ripple` closes the gaps by moving later regions of the same track left.
+pub fn delete_regions(tracks: &[Track], ids: &HashSet<RegionId>, ripple: bool) {
+    for track in tracks {
+        let (removed, mut kept): (Vec<TrackRegion>, Vec<TrackRegion>) = track
+            .regions()
+            .into_iter()
+            .partition(|region| ids.contains(&region.id()));
+        if removed.is_empty() {
+            continue;
+        }
+        if ripple {
+            for region in kept.iter_mut() {
+                let shift: usize = removed
+                    .iter()
+                    .filter(|r| r.track_start_frame() < region.track_start_frame())
+                    .map(|r| r.frames())
+                    .sum();
+                region.set_track_start_frame(region.track_start_frame().saturating_sub(shift));
+            }
+        }
+        track.set_regions(kept);
+        track.clean_regions();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::waveform::test_clip;
+
+    fn placements(track: &Track) -> Vec<(usize, usize)> {
+        track
+            .regions()
+            .iter()
+            .map(|region| (region.track_start_frame(), region.track_end_frame()))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_regions() {
+        let clip = test_clip("split_regions", 1000);
+        let tracks = vec![Track::new("a")];
+        tracks[0].add_clip(&clip, 100);
+
+        let created = split_regions(&tracks, None, 400);
+        assert_eq!(created.len(), 1);
+        assert_eq!(placements(&tracks[0]), vec![(100, 400), (400, 1100)]);
+        assert_eq!(tracks[0].regions()[1].clip_start_frame(), 300);
+        assert!(split_regions(&tracks, None, 100).is_empty());
+    }
+
+    #[test]
+    fn test_paste_keeps_relative_layout() {
+        let clip = test_clip("paste_layout", 100);
+        let tracks = vec![Track::new("a"), Track::new("b"), Track::new("c")];
+        tracks[0].add_clip(&clip, 50);
+        tracks[1].add_clip(&clip, 80);
+
+        let clipboard = copy_regions(&[(0, tracks[0].regions()[0].clone()), (1, tracks[1].regions()[0].clone())]);
+        let pasted = paste_regions(&tracks, &clipboard, 1, 1000);
+        assert_eq!(pasted.len(), 2);
+        assert_eq!(placements(&tracks[1]), vec![(80, 180), (1000, 1100)]);
+        assert_eq!(placements(&tracks[2]), vec![(1030, 1130)]);
+        assert!(!pasted.contains(&tracks[0].regions()[0].id()));
+    }
+
+    #[test]
+    fn test_move_track_lands_in_front_of_the_target() {
+        let titles = |tracks: &[Track]| tracks.iter().map(|track| track.title().to_string()).collect::<Vec<_>>();
+        let mut tracks = vec![Track::new("a"), Track::new("b"), Track::new("c")];
+        move_track(&mut tracks, 0, 2);
+        assert_eq!(titles(&tracks), vec!["b", "a", "c"]);
+        move_track(&mut tracks, 2, 0);
+        assert_eq!(titles(&tracks), vec!["c", "b", "a"]);
+        move_track(&mut tracks, 0, 3);
+        assert_eq!(titles(&tracks), vec!["b", "a", "c"]);
+        // dropped on the track right below lands where it was
+        move_track(&mut tracks, 0, 1);
+        assert_eq!(titles(&tracks), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn test_duplicate_regions() {
+        let clip = test_clip("duplicate_regions", 100);
+        let tracks = vec![Track::new("a")];
+        tracks[0].add_clip(&clip, 10);
+
+        let duplicated = duplicate_regions(&tracks, &[(0, tracks[0].regions()[0].clone())]);
+        assert_eq!(duplicated.len(), 1);
+        assert_eq!(placements(&tracks[0]), vec![(10, 110), (110, 210)]);
+    }
+
+    #[test]
+    fn test_delete_regions_ripple() {
+        let clip = test_clip("delete_ripple", 100);
+        let tracks = vec![Track::new("a"), Track::new("b")];
+        for offset in [0, 200, 400] {
+            tracks[0].add_clip(&clip, offset);
+            tracks[1].add_clip(&clip, offset);
+        }
+        let middle = |track: &Track| HashSet::from([track.regions()[1].id()]);
+
+        delete_regions(&tracks[0..1], &middle(&tracks[0]), false);
+        assert_eq!(placements(&tracks[0]), vec![(0, 100), (400, 500)]);
+
+        delete_regions(&tracks[1..2], &middle(&tracks[1]), true);
+        assert_eq!(placements(&tracks[1]), vec![(0, 100), (300, 400)]);
+    }
+
+    #[test]
+    fn test_place_clips_on_track_or_below() {
+        let clip = test_clip("place_clips", 100);
+        let mut tracks = vec![Track::new("a")];
+
+        let placed = place_clips(&mut tracks, &[clip.clone(), clip.clone()], 0, 50);
+        assert_eq!(placed.len(), 2);
+        assert_eq!(placements(&tracks[0]), vec![(50, 150), (150, 250)]);
+
+        place_clips(&mut tracks, &[clip.clone(), clip], 3, 10);
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(placements(&tracks[1]), vec![(10, 110)]);
+        assert_eq!(placements(&tracks[2]), vec![(10, 110)]);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::components::grid::{GridMarkers, GridMarkersSnapshot};
+use crate::components::region::TrackRegion;
+use crate::components::track::{Track, TrackHeading};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Track list, track headings and region placement captured before an edit.
+#[derive(Clone)]
+pub struct TracksSnapshot {
+    tracks: Vec<Track>,
+    headings: Vec<TrackHeading>,
+    regions: Vec<Vec<TrackRegion>>,
+}
+
+impl TracksSnapshot {
+    pub fn capture(tracks: &[Track]) -> Self {
+        Self {
+            tracks: tracks.to_vec(),
+            headings: tracks.iter().map(|track| track.heading()).collect(),
+            regions: tracks.iter().map(|track| track.regions()).collect(),
+        }
+    }
+}
+
+struct HeadingEdit {
+    track: Track,
+    before: TrackHeading,
+    after: TrackHeading,
+}
+
+struct RegionsEdit {
+    track: Track,
+    before: Vec<TrackRegion>,
+    after: Vec<TrackRegion>,
+}
+
+struct MarkersEdit {
+    markers: GridMarkers,
+    before: GridMarkersSnapshot,
+    after: GridMarkersSnapshot,
+}
+
+/// Reversible change of the timeline, the difference between two snapshots.
+pub struct GridEdit {
+    label: String,
+    tracks: Option<(Vec<Track>, Vec<Track>)>,
+    headings: Vec<HeadingEdit>,
+    regions: Vec<RegionsEdit>,
+    markers: Option<MarkersEdit>,
+}
+
+impl GridEdit {
+    /// Diffs the current tracks against `before`, `None` when nothing changed.
+    pub fn diff(label: impl Into<String>, before: &TracksSnapshot, after: &[Track]) -> Option<Self> {
+        let same_tracks =
+            before.tracks.len() == after.len() && before.tracks.iter().zip(after).all(|(a, b)| a.ptr_eq(b));
+        let tracks = (!same_tracks).then(|| (before.tracks.clone(), after.to_vec()));
+
+        // tracks created by the edit restore with the list, only tracks that existed before need their regions
+        let regions: Vec<RegionsEdit> = before
+            .tracks
+            .iter()
+            .zip(&before.regions)
+            .filter_map(|(track, regions)| {
+                let current = track.regions();
+                (*regions != current).then(|| RegionsEdit {
+                    track: track.clone(),
+                    before: regions.clone(),
+                    after: current,
+                })
+            })
+            .collect();
+
+        let headings: Vec<HeadingEdit> = before
+            .tracks
+            .iter()
+            .zip(&before.headings)
+            .filter_map(|(track, heading)| {
+                let current = track.heading();
+                (*heading != current).then(|| HeadingEdit {
+                    track: track.clone(),
+                    before: heading.clone(),
+                    after: current,
+                })
+            })
+            .collect();
+
+        if tracks.is_none() && headings.is_empty() && regions.is_empty() {
+            return None;
+        }
+        Some(Self {
+            label: label.into(),
+            tracks,
+            headings,
+            regions,
+            markers: None,
+        })
+    }
+
+    /// Diffs the current markers against `before`, `None` when nothing changed.
+    pub fn markers(label: impl Into<String>, before: GridMarkersSnapshot, markers: &GridMarkers) -> Option<Self> {
+        let after = markers.snapshot();
+        (before != after).then(|| Self {
+            label: label.into(),
+            tracks: None,
+            headings: Vec::new(),
+            regions: Vec::new(),
+            markers: Some(MarkersEdit {
+                markers: markers.clone(),
+                before,
+                after,
+            }),
+        })
+    }
+
+    fn undo(&self, tracks: &mut Vec<Track>) {
+        if let Some(edit) = &self.markers {
+            edit.markers.restore(&edit.before);
+        }
+        for edit in self.regions.iter().rev() {
+            edit.track.set_regions(edit.before.clone());
+        }
+        for edit in &self.headings {
+            edit.track.set_heading(edit.before.clone());
+        }
+        if let Some((before, _)) = &self.tracks {
+            *tracks = before.clone();
+        }
+    }
+
+    fn redo(&self, tracks: &mut Vec<Track>) {
+        if let Some((_, after)) = &self.tracks {
+            *tracks = after.clone();
+        }
+        for edit in &self.headings {
+            edit.track.set_heading(edit.after.clone());
+        }
+        for edit in &self.regions {
+            edit.track.set_regions(edit.after.clone());
+        }
+        if let Some(edit) = &self.markers {
+            edit.markers.restore(&edit.after);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridHistoryEntry {
+    pub label: String,
+    pub applied: bool,
+}
+
+struct GridHistoryInner {
+    undo: Vec<GridEdit>,
+    redo: Vec<GridEdit>,
+}
+
+/// Unlimited undo and redo stacks of timeline edits.
+#[derive(Clone)]
+pub struct GridHistory {
+    inner: Rc<RefCell<GridHistoryInner>>,
+}
+
+impl GridHistory {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(GridHistoryInner {
+                undo: Vec::new(),
+                redo: Vec::new(),
+            })),
+        }
+    }
+
+    /// Records an applied edit, dropping anything that was undone.
+    pub fn push(&self, edit: GridEdit) {
+        let mut inner = self.inner.borrow_mut();
+        inner.undo.push(edit);
+        inner.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.inner.borrow().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.inner.borrow().redo.is_empty()
+    }
+
+    /// Reverts the last edit, returns its label.
+    pub fn undo(&self, tracks: &mut Vec<Track>) -> Option<String> {
+        let mut inner = self.inner.borrow_mut();
+        let edit = inner.undo.pop()?;
+        edit.undo(tracks);
+        let label = edit.label.clone();
+        inner.redo.push(edit);
+        Some(label)
+    }
+
+    /// Re-applies the last undone edit, returns its label.
+    pub fn redo(&self, tracks: &mut Vec<Track>) -> Option<String> {
+        let mut inner = self.inner.borrow_mut();
+        let edit = inner.redo.pop()?;
+        edit.redo(tracks);
+        let label = edit.label.clone();
+        inner.undo.push(edit);
+        Some(label)
+    }
+
+    /// Number of applied edits, the position in `entries`.
+    pub fn position(&self) -> usize {
+        self.inner.borrow().undo.len()
+    }
+
+    /// Undoes or redoes until `position` edits are applied.
+    pub fn jump_to(&self, position: usize, tracks: &mut Vec<Track>) {
+        while self.position() > position && self.undo(tracks).is_some() {}
+        while self.position() < position && self.redo(tracks).is_some() {}
+    }
+
+    /// Every edit from oldest to newest, undone edits come last.
+    pub fn entries(&self) -> Vec<GridHistoryEntry> {
+        let inner = self.inner.borrow();
+        let applied = inner.undo.iter().map(|edit| GridHistoryEntry {
+            label: edit.label.clone(),
+            applied: true,
+        });
+        let undone = inner.redo.iter().rev().map(|edit| GridHistoryEntry {
+            label: edit.label.clone(),
+            applied: false,
+        });
+        applied.chain(undone).collect()
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.undo.clear();
+        inner.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn add_track(history: &GridHistory, tracks: &mut Vec<Track>, title: &str) {
+        let before = TracksSnapshot::capture(tracks);
+        tracks.push(Track::new(title.to_string()));
+        history.push(GridEdit::diff(format!("Add {title}"), &before, tracks).unwrap());
+    }
+
+    #[test]
+    fn test_unchanged_is_not_an_edit() {
+        let tracks = vec![Track::new("a")];
+        let before = TracksSnapshot::capture(&tracks);
+        assert!(GridEdit::diff("noop", &before, &tracks).is_none());
+    }
+
+    #[test]
+    fn test_undo_redo_track_list() {
+        let history = GridHistory::new();
+        let mut tracks = Vec::new();
+        add_track(&history, &mut tracks, "a");
+        add_track(&history, &mut tracks, "b");
+
+        assert_eq!(history.undo(&mut tracks).as_deref(), Some("Add b"));
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(history.undo(&mut tracks).as_deref(), Some("Add a"));
+        assert!(tracks.is_empty());
+        assert!(history.undo(&mut tracks).is_none());
+
+        assert_eq!(history.redo(&mut tracks).as_deref(), Some("Add a"));
+        assert_eq!(tracks[0].title(), "a");
+    }
+
+    #[test]
+    fn test_push_drops_redo() {
+        let history = GridHistory::new();
+        let mut tracks = Vec::new();
+        add_track(&history, &mut tracks, "a");
+        history.undo(&mut tracks);
+        assert!(history.can_redo());
+
+        add_track(&history, &mut tracks, "b");
+        assert!(!history.can_redo());
+        assert_eq!(
+            history.entries(),
+            vec![GridHistoryEntry {
+                label: "Add b".to_string(),
+                applied: true
+            }]
+        );
+    }
+
+    #[test]
+    fn test_undo_redo_track_heading() {
+        let history = GridHistory::new();
+        let mut tracks = vec![Track::new("a")];
+        let before = TracksSnapshot::capture(&tracks);
+        tracks[0].set_title("renamed");
+        tracks[0].set_color(Some(0xE5484D));
+        history.push(GridEdit::diff("Rename track", &before, &tracks).unwrap());
+
+        history.undo(&mut tracks);
+        assert_eq!(tracks[0].title(), "a");
+        assert_eq!(tracks[0].color(), None);
+        history.redo(&mut tracks);
+        assert_eq!(tracks[0].title(), "renamed");
+        assert_eq!(tracks[0].color(), Some(0xE5484D));
+    }
+
+    #[test]
+    fn test_undo_redo_markers() {
+        let history = GridHistory::new();
+        let mut tracks = Vec::new();
+        let markers = GridMarkers::new();
+        let before = markers.snapshot();
+        let id = markers.add_marker(Some("impulse".into()), 100);
+        history.push(GridEdit::markers("Add marker", before, &markers).unwrap());
+        assert!(GridEdit::markers("noop", markers.snapshot(), &markers).is_none());
+
+        assert_eq!(history.undo(&mut tracks).as_deref(), Some("Add marker"));
+        assert!(markers.markers().is_empty());
+        history.redo(&mut tracks);
+        assert_eq!(markers.marker(id).map(|marker| marker.frame), Some(100));
+    }
+
+    #[test]
+    fn test_jump_to() {
+        let history = GridHistory::new();
+        let mut tracks = Vec::new();
+        for title in ["a", "b", "c"] {
+            add_track(&history, &mut tracks, title);
+        }
+
+        history.jump_to(1, &mut tracks);
+        assert_eq!(tracks.len(), 1);
+        let applied: Vec<bool> = history.entries().iter().map(|entry| entry.applied).collect();
+        assert_eq!(applied, vec![true, false, false]);
+
+        history.jump_to(3, &mut tracks);
+        assert_eq!(tracks.len(), 3);
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::{Result, anyhow};
+use gpui::SharedString;
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// kept in the session output directory, next to the recordings the markers point into
+const MARKERS_FILE: &str = "markers.txt";
+const MARKERS_HEADER: &str = "unrecord markers 1";
+
+pub type MarkerId = usize;
+
+/// Named point on the timeline, or a named range when it has an end.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineMarker {
+    pub id: MarkerId,
+    pub name: SharedString,
+    pub frame: usize,
+    pub end_frame: Option<usize>,
+}
+
+impl TimelineMarker {
+    pub fn range(&self) -> Option<(usize, usize)> {
+        self.end_frame.map(|end_frame| (self.frame, end_frame))
+    }
+}
+
+/// Markers and loop range at one point in time, for undo and the markers file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GridMarkersSnapshot {
+    markers: Vec<TimelineMarker>,
+    loop_range: Option<(usize, usize)>,
+}
+
+impl GridMarkersSnapshot {
+    /// One tab separated line per marker, range and the loop, names lose their line breaks.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{MARKERS_HEADER}\n");
+        for marker in &self.markers {
+            let name = marker.name.replace(['\t', '\r', '\n'], " ");
+            let _ = match marker.end_frame {
+                Some(end_frame) => writeln!(text, "range\t{}\t{end_frame}\t{name}", marker.frame),
+                None => writeln!(text, "marker\t{}\t{name}", marker.frame),
+            };
+        }
+        if let Some((start, end)) = self.loop_range {
+            let _ = writeln!(text, "loop\t{start}\t{end}");
+        }
+        text
+    }
+
+    /// Reads what `to_text` wrote, markers get new ids when restored. Empty and reversed ranges and loops
+    /// of an edited file are dropped.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next() != Some(MARKERS_HEADER) {
+            return Err(anyhow!("not a markers file"));
+        }
+        let mut snapshot = Self::default();
+        for (index, line) in lines.enumerate().filter(|(_, line)| !line.is_empty()) {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            let frame = |field: usize| {
+                let value = fields.get(field).and_then(|value| value.parse::<usize>().ok());
+                value.ok_or(anyhow!("invalid marker on line {}", index + 2))
+            };
+            let marker = |frame, end_frame, name: Option<&&str>| TimelineMarker {
+                id: 0,
+                name: name.map(|name| name.to_string()).unwrap_or_default().into(),
+                frame,
+                end_frame,
+            };
+            match fields[0] {
+                "marker" => snapshot.markers.push(marker(frame(1)?, None, fields.get(2))),
+                "range" => {
+                    let (start, end) = (frame(1)?, frame(2)?);
+                    if start < end {
+                        snapshot.markers.push(marker(start, Some(end), fields.get(3)));
+                    }
+                }
+                "loop" => snapshot.loop_range = non_empty(Some((frame(1)?, frame(2)?))),
+                kind => return Err(anyhow!("unknown marker kind \"{kind}\" on line {}", index + 2)),
+            }
+        }
+        snapshot.markers.sort_by_key(|marker| marker.frame);
+        Ok(snapshot)
+    }
+}
+
+// the loop wraps and ranges select by `end - start`, both need their start first
+fn non_empty(range: Option<(usize, usize)>) -> Option<(usize, usize)> {
+    range.filter(|(start, end)| start < end)
+}
+
+struct GridMarkersInner {
+    next_id: MarkerId,
+    // sorted by frame
+    markers: Vec<TimelineMarker>,
+    loop_range: Option<(usize, usize)>,
+    // markers file every change is saved to
+    path: Option<PathBuf>,
+}
+
+/// Markers, named ranges and the loop range of the timeline.
+#[derive(Clone)]
+pub struct GridMarkers {
+    inner: Rc<RefCell<GridMarkersInner>>,
+}
+
+impl GridMarkers {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(GridMarkersInner {
+                next_id: 1,
+                markers: Vec::new(),
+                loop_range: None,
+                path: None,
+            })),
+        }
+    }
+
+    pub fn markers(&self) -> Vec<TimelineMarker> {
+        self.inner.borrow().markers.clone()
+    }
+
+    pub fn ranges(&self) -> Vec<TimelineMarker> {
+        let inner = self.inner.borrow();
+        inner
+            .markers
+            .iter()
+            .filter(|marker| marker.end_frame.is_some())
+            .cloned()
+            .collect()
+    }
+
+    pub fn marker(&self, id: MarkerId) -> Option<TimelineMarker> {
+        self.inner
+            .borrow()
+            .markers
+            .iter()
+            .find(|marker| marker.id == id)
+            .cloned()
+    }
+
+    /// Adds a marker at `frame`, unnamed markers are numbered.
+    pub fn add_marker(&self, name: Option<SharedString>, frame: usize) -> MarkerId {
+        self.insert(name, "Marker", frame, None)
+    }
+
+    /// Adds a named range, the frames may come in any order. `None` for an empty range.
+    pub fn add_range(&self, name: Option<SharedString>, a: usize, b: usize) -> Option<MarkerId> {
+        (a != b).then(|| self.insert(name, "Range", a.min(b), Some(a.max(b))))
+    }
+
+    pub fn rename(&self, id: MarkerId, name: SharedString) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(marker) = inner.markers.iter_mut().find(|marker| marker.id == id) {
+            marker.name = name;
+        }
+    }
+
+    pub fn remove(&self, id: MarkerId) {
+        self.inner.borrow_mut().markers.retain(|marker| marker.id != id);
+    }
+
+    /// Start of the first marker or range after `frame`.
+    pub fn next_after(&self, frame: usize) -> Option<usize> {
+        let inner = self.inner.borrow();
+        inner
+            .markers
+            .iter()
+            .map(|marker| marker.frame)
+            .find(|start| *start > frame)
+    }
+
+    /// Start of the last marker or range before `frame`.
+    pub fn previous_before(&self, frame: usize) -> Option<usize> {
+        let inner = self.inner.borrow();
+        inner
+            .markers
+            .iter()
+            .rev()
+            .map(|marker| marker.frame)
+            .find(|start| *start < frame)
+    }
+
+    pub fn loop_range(&self) -> Option<(usize, usize)> {
+        self.inner.borrow().loop_range
+    }
+
+    /// Empty ranges clear the loop.
+    pub fn set_loop_range(&self, range: Option<(usize, usize)>) {
+        self.inner.borrow_mut().loop_range = non_empty(range);
+    }
+
+    pub fn snapshot(&self) -> GridMarkersSnapshot {
+        let inner = self.inner.borrow();
+        GridMarkersSnapshot {
+            markers: inner.markers.clone(),
+            loop_range: inner.loop_range,
+        }
+    }
+
+    /// Replaces the markers and the loop, markers without an id get a new one.
+    pub fn restore(&self, snapshot: &GridMarkersSnapshot) {
+        let mut inner = self.inner.borrow_mut();
+        inner.markers = snapshot.markers.clone();
+        inner
+            .markers
+            .retain(|marker| marker.end_frame.is_none_or(|end_frame| marker.frame < end_frame));
+        for index in 0..inner.markers.len() {
+            if inner.markers[index].id == 0 {
+                inner.markers[index].id = inner.next_id;
+                inner.next_id += 1;
+            }
+        }
+        let last_id = inner.markers.iter().map(|marker| marker.id).max().unwrap_or(0);
+        inner.next_id = inner.next_id.max(last_id + 1);
+        inner.loop_range = non_empty(snapshot.loop_range);
+    }
+
+    /// Keeps the markers in the markers file of `directory`, loading it when there is one.
+    pub fn open(&self, directory: &Path) -> Result<()> {
+        let path = directory.join(MARKERS_FILE);
+        if path.exists() {
+            let snapshot = GridMarkersSnapshot::parse(&std::fs::read_to_string(&path)?)?;
+            self.restore(&snapshot);
+        }
+        self.inner.borrow_mut().path = Some(path);
+        self.save()
+    }
+
+    /// Writes the markers file, if one was opened.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = self.inner.borrow().path.clone() else {
+            return Ok(());
+        };
+        std::fs::write(path, self.snapshot().to_text())?;
+        Ok(())
+    }
+
+    fn insert(&self, name: Option<SharedString>, kind: &str, frame: usize, end_frame: Option<usize>) -> MarkerId {
+        let mut inner = self.inner.borrow_mut();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let name = name.unwrap_or_else(|| format!("{kind} {id}").into());
+        let index = inner.markers.partition_point(|marker| marker.frame <= frame);
+        inner.markers.insert(
+            index,
+            TimelineMarker {
+                id,
+                name,
+                frame,
+                end_frame,
+            },
+        );
+        id
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_markers_stay_sorted_and_navigate() {
+        let markers = GridMarkers::new();
+        markers.add_marker(Some("program start".into()), 500);
+        markers.add_marker(None, 100);
+        markers.add_range(Some("glitch @ gen 37".into()), 900, 300);
+
+        let frames: Vec<usize> = markers.markers().iter().map(|marker| marker.frame).collect();
+        assert_eq!(frames, vec![100, 300, 500]);
+        assert_eq!(markers.markers()[0].name, "Marker 2");
+        assert_eq!(markers.ranges()[0].range(), Some((300, 900)));
+
+        assert_eq!(markers.next_after(100), Some(300));
+        assert_eq!(markers.next_after(500), None);
+        assert_eq!(markers.previous_before(300), Some(100));
+        assert_eq!(markers.previous_before(100), None);
+    }
+
+    #[test]
+    fn test_markers_text_round_trip() {
+        let markers = GridMarkers::new();
+        markers.add_marker(Some("program start".into()), 500);
+        markers.add_range(Some("glitch @ gen 37\tlate".into()), 300, 900);
+        markers.set_loop_range(Some((20, 40)));
+
+        let snapshot = GridMarkersSnapshot::parse(&markers.snapshot().to_text()).unwrap();
+        let restored = GridMarkers::new();
+        restored.restore(&snapshot);
+        assert_eq!(restored.ranges()[0].name, "glitch @ gen 37 late");
+        assert_eq!(restored.ranges()[0].range(), Some((300, 900)));
+        assert_eq!(restored.markers()[1].name, "program start");
+        assert_eq!(restored.loop_range(), Some((20, 40)));
+        // new markers do not reuse restored ids
+        let id = restored.add_marker(None, 0);
+        assert_eq!(restored.markers().iter().filter(|marker| marker.id == id).count(), 1);
+
+        assert!(GridMarkersSnapshot::parse("marker\t1\ta").is_err());
+        assert!(GridMarkersSnapshot::parse("unrecord markers 1\nmarker\tx\ta").is_err());
+    }
+
+    #[test]
+    fn test_open_saves_and_loads_the_markers_file() {
+        let directory = std::env::temp_dir().join(format!("unrecord_test_markers_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let markers = GridMarkers::new();
+        markers.add_marker(Some("impulse".into()), 100);
+        markers.open(&directory).unwrap();
+
+        let reopened = GridMarkers::new();
+        reopened.open(&directory).unwrap();
+        assert_eq!(reopened.markers()[0].name, "impulse");
+        assert_eq!(reopened.markers()[0].frame, 100);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_empty_ranges_are_ignored() {
+        let markers = GridMarkers::new();
+        assert_eq!(markers.add_range(None, 10, 10), None);
+        markers.set_loop_range(Some((20, 20)));
+        assert_eq!(markers.loop_range(), None);
+        markers.set_loop_range(Some((20, 40)));
+        assert_eq!(markers.loop_range(), Some((20, 40)));
+    }
+
+    #[test]
+    fn test_parse_drops_empty_and_reversed_ranges() {
+        let reversed = "unrecord markers 1\nrange\t40\t20\tback\nrange\t5\t5\tempty\nloop\t40\t20\n";
+        let snapshot = GridMarkersSnapshot::parse(reversed).unwrap();
+        assert_eq!(snapshot, GridMarkersSnapshot::default());
+
+        let empty = GridMarkersSnapshot::parse("unrecord markers 1\nloop\t20\t20\nrange\t1\t2\tok\n").unwrap();
+        assert_eq!(empty.loop_range, None);
+        assert_eq!(empty.markers.len(), 1);
+
+        // snapshots built in code go through the same filter
+        let markers = GridMarkers::new();
+        markers.restore(&GridMarkersSnapshot {
+            markers: Vec::new(),
+            loop_range: Some((40, 20)),
+        });
+        assert_eq!(markers.loop_range(), None);
+    }
+}
@@ -1,6 +1,20 @@
+mod comparison;
+mod edit;
+mod history;
+mod markers;
+mod overview;
+mod selection;
+mod snap;
 mod state;
 mod viewport;
 
+pub use comparison::*;
+pub use edit::*;
+pub use history::*;
+pub use markers::*;
+pub use overview::*;
+pub use selection::*;
+pub use snap::*;
 #[allow(unused_imports)]
 pub use state::*;
 #[allow(unused_imports)]
@@ -0,0 +1,62 @@
+use crate::components::track::Track;
+
+/// Peak of a bucket of the coarsest mipmap level, placed on the track.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OverviewBar {
+    pub start_frame: usize,
+    pub end_frame: usize,
+    pub peak: f32,
+}
+
+/// Low resolution peaks of the regions of a track, loudest channel of the clip before region gain.
+pub fn track_overview(track: &Track) -> Vec<OverviewBar> {
+    let mut bars = Vec::new();
+    for region in track.regions() {
+        let clip = region.clip();
+        let Some(first) = clip.channels().first() else {
+            continue;
+        };
+        let frames_per_bucket = first.overview_level().frames_per_bucket();
+        let (clip_start, clip_end) = (region.clip_start_frame(), region.clip_end_frame());
+
+        let mut bucket = clip_start / frames_per_bucket;
+        while bucket * frames_per_bucket < clip_end {
+            let peak = clip
+                .channels()
+                .iter()
+                .filter_map(|channel| channel.overview_level().buckets().get(bucket))
+                .fold(0.0f32, |peak, bucket| peak.max(bucket.min.abs()).max(bucket.max.abs()));
+            let start = (bucket * frames_per_bucket).max(clip_start);
+            let end = ((bucket + 1) * frames_per_bucket).min(clip_end);
+            bars.push(OverviewBar {
+                start_frame: region.track_start_frame() + start - clip_start,
+                end_frame: region.track_start_frame() + end - clip_start,
+                peak,
+            });
+            bucket += 1;
+        }
+    }
+    bars
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::region::TrackRegion;
+    use crate::components::waveform::test_clip;
+
+    #[test]
+    fn test_track_overview() {
+        let clip = test_clip("overview", 64 * 64);
+        let frames_per_bucket = clip.channels()[0].overview_level().frames_per_bucket();
+        let track = Track::new("overview");
+        track.add_region(TrackRegion::new(&clip, 10, 64 * 64, 1000));
+
+        let bars = track_overview(&track);
+        assert_eq!(bars.len(), 64 * 64 / frames_per_bucket);
+        assert_eq!(bars[0].start_frame, 1000);
+        assert_eq!(bars[0].end_frame, 1000 + frames_per_bucket - 10);
+        assert_eq!(bars.last().unwrap().end_frame, 1000 + 64 * 64 - 10);
+        assert!(bars.iter().all(|bar| bar.peak == 0.25));
+    }
+}
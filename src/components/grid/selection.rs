@@ -0,0 +1,537 @@
+use crate::components::grid::{GridEdit, GridSnap, TracksSnapshot, region_zero_crossing};
+use crate::components::region::{Envelope, EnvelopePoint, Fade, RegionId, TrackRegion};
+use crate::components::track::Track;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegionHitKind {
+    Body,
+    Start,
+    End,
+    FadeIn,
+    FadeOut,
+}
+
+/// Grab tolerances, region edges in frames and the fade handle band at the top of a row,
+/// with the padding above and below regions within their row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HitSlop {
+    pub edge_frames: usize,
+    pub handle_rows: f32,
+    pub padding_rows: f32,
+}
+
+#[derive(Clone)]
+pub struct RegionHit {
+    pub track_index: usize,
+    pub region: TrackRegion,
+    pub kind: RegionHitKind,
+}
+
+/// Pointer position on the timeline, a track frame and a fractional track row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridPoint {
+    pub frame: usize,
+    pub row: f32,
+}
+
+impl GridPoint {
+    pub fn track_index(&self) -> Option<usize> {
+        (self.row >= 0.0).then_some(self.row.floor() as usize)
+    }
+}
+
+#[derive(Clone)]
+pub struct DraggedRegion {
+    pub origin_track: usize,
+    pub current_track: usize,
+    pub region: TrackRegion,
+}
+
+#[derive(Clone)]
+pub enum GridDrag {
+    Move {
+        origin: GridPoint,
+        regions: Vec<DraggedRegion>,
+    },
+    Trim {
+        kind: RegionHitKind,
+        track_index: usize,
+        region: TrackRegion,
+    },
+    Select {
+        origin: GridPoint,
+        position: GridPoint,
+        base: HashSet<RegionId>,
+    },
+    Envelope {
+        track_index: usize,
+        region: TrackRegion,
+        // breakpoint of `region` being moved, a new one is added otherwise
+        grabbed: Option<usize>,
+        remove: bool,
+        padding_rows: f32,
+    },
+}
+
+/// Classifies `frame` against a region spanning `start_frame..end_frame`, the edges take up to `edge_frames`.
+pub fn region_hit_kind(
+    start_frame: usize,
+    end_frame: usize,
+    frame: usize,
+    edge_frames: usize,
+) -> Option<RegionHitKind> {
+    if frame < start_frame || frame >= end_frame {
+        return None;
+    }
+    // keep a grabbable body on short regions
+    let edge_frames = edge_frames.min((end_frame - start_frame) / 3);
+    if frame < start_frame + edge_frames {
+        Some(RegionHitKind::Start)
+    } else if frame >= end_frame - edge_frames {
+        Some(RegionHitKind::End)
+    } else {
+        Some(RegionHitKind::Body)
+    }
+}
+
+/// Track rows touched by a vertical span between two fractional rows.
+pub fn rows_in_span(a: f32, b: f32, track_count: usize) -> std::ops::Range<usize> {
+    let top = a.min(b).max(0.0).floor() as usize;
+    let bottom = a.max(b).max(0.0).floor() as usize + 1;
+    top.min(track_count)..bottom.min(track_count)
+}
+
+/// Fade handle at `frame`, the handles sit where the fade-in ends and the fade-out starts.
+pub fn fade_handle_hit(region: &TrackRegion, frame: usize, edge_frames: usize) -> Option<RegionHitKind> {
+    let fade_in_end = region.track_start_frame() + region.fade_in().frames;
+    let fade_out_start = region.track_end_frame() - region.fade_out().frames;
+    let near = |handle: usize| frame.abs_diff(handle) <= edge_frames;
+    // on short regions the handle closer to the pointer wins
+    match (near(fade_in_end), near(fade_out_start)) {
+        (true, true) if frame.abs_diff(fade_out_start) < frame.abs_diff(fade_in_end) => Some(RegionHitKind::FadeOut),
+        (true, _) => Some(RegionHitKind::FadeIn),
+        (false, true) => Some(RegionHitKind::FadeOut),
+        (false, false) => None,
+    }
+}
+
+/// Height of `point` within the regions of a track row, 0 at the bottom and 1 at the top.
+pub fn region_height(point: GridPoint, track_index: usize, padding_rows: f32) -> f32 {
+    let from_top = point.row - track_index as f32 - padding_rows;
+    (1.0 - from_top / (1.0 - 2.0 * padding_rows)).clamp(0.0, 1.0)
+}
+
+pub fn hit_test(tracks: &[Track], point: GridPoint, slop: HitSlop) -> Option<RegionHit> {
+    let track_index = point.track_index()?;
+    let track = tracks.get(track_index)?;
+    let in_handle_band = point.row.fract() < slop.handle_rows;
+    // later regions paint on top
+    track.regions().into_iter().rev().find_map(|region| {
+        let kind = region_hit_kind(
+            region.track_start_frame(),
+            region.track_end_frame(),
+            point.frame,
+            slop.edge_frames,
+        )?;
+        let kind = in_handle_band
+            .then(|| fade_handle_hit(&region, point.frame, slop.edge_frames))
+            .flatten()
+            .unwrap_or(kind);
+        Some(RegionHit {
+            track_index,
+            region,
+            kind,
+        })
+    })
+}
+
+struct GridSelectionInner {
+    selected: HashSet<RegionId>,
+    drag: Option<GridDrag>,
+    // tracks as they were when the drag started
+    before: Option<TracksSnapshot>,
+    // edit position for split and paste, the last press
+    cursor: Option<GridPoint>,
+    snapping: bool,
+}
+
+#[derive(Clone)]
+pub struct GridSelection {
+    inner: Rc<RefCell<GridSelectionInner>>,
+}
+
+impl GridSelection {
+    pub fn new() -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(GridSelectionInner {
+                selected: HashSet::new(),
+                drag: None,
+                before: None,
+                cursor: None,
+                snapping: true,
+            })),
+        }
+    }
+
+    pub fn is_selected(&self, id: RegionId) -> bool {
+        self.inner.borrow().selected.contains(&id)
+    }
+
+    pub fn selected(&self) -> HashSet<RegionId> {
+        self.inner.borrow().selected.clone()
+    }
+
+    pub fn set_selected(&self, selected: HashSet<RegionId>) {
+        self.inner.borrow_mut().selected = selected;
+    }
+
+    pub fn clear(&self) {
+        self.inner.borrow_mut().selected.clear();
+    }
+
+    pub fn cursor(&self) -> Option<GridPoint> {
+        self.inner.borrow().cursor
+    }
+
+    /// Whether moves and trims snap, the track list builds the targets.
+    pub fn snapping(&self) -> bool {
+        self.inner.borrow().snapping
+    }
+
+    pub fn set_snapping(&self, snapping: bool) {
+        self.inner.borrow_mut().snapping = snapping;
+    }
+
+    pub fn drag(&self) -> Option<GridDrag> {
+        self.inner.borrow().drag.clone()
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.inner.borrow().drag.is_some()
+    }
+
+    /// Selected regions with the index of the track holding them.
+    pub fn selected_regions(&self, tracks: &[Track]) -> Vec<(usize, TrackRegion)> {
+        let inner = self.inner.borrow();
+        tracks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, track)| track.regions().into_iter().map(move |region| (index, region)))
+            .filter(|(_, region)| inner.selected.contains(&region.id()))
+            .collect()
+    }
+
+    /// Selects the region at `point` unless it is already part of the selection.
+    pub fn select_at(&self, tracks: &[Track], point: GridPoint, slop: HitSlop) {
+        let Some(hit) = hit_test(tracks, point, slop) else {
+            return;
+        };
+        if !self.is_selected(hit.region.id()) {
+            self.set_selected(HashSet::from([hit.region.id()]));
+        }
+    }
+
+    /// Handles a mouse press, `extend` toggles the hit region or adds a rubber band to the selection.
+    pub fn press(&self, tracks: &[Track], point: GridPoint, slop: HitSlop, extend: bool) {
+        self.inner.borrow_mut().cursor = Some(point);
+        let Some(hit) = hit_test(tracks, point, slop) else {
+            if !extend {
+                self.clear();
+            }
+            let base = self.selected();
+            self.inner.borrow_mut().drag = Some(GridDrag::Select {
+                origin: point,
+                position: point,
+                base,
+            });
+            return;
+        };
+
+        let id = hit.region.id();
+        if extend && self.is_selected(id) {
+            self.inner.borrow_mut().selected.remove(&id);
+            return;
+        }
+        if !extend && !self.is_selected(id) {
+            self.clear();
+        }
+        self.inner.borrow_mut().selected.insert(id);
+
+        let drag = match hit.kind {
+            RegionHitKind::Body => GridDrag::Move {
+                origin: point,
+                regions: self
+                    .selected_regions(tracks)
+                    .into_iter()
+                    .map(|(index, region)| DraggedRegion {
+                        origin_track: index,
+                        current_track: index,
+                        region,
+                    })
+                    .collect(),
+            },
+            kind => GridDrag::Trim {
+                kind,
+                track_index: hit.track_index,
+                region: hit.region,
+            },
+        };
+        let mut inner = self.inner.borrow_mut();
+        inner.drag = Some(drag);
+        inner.before = Some(TracksSnapshot::capture(tracks));
+    }
+
+    /// Starts a volume envelope drag on the region at `point`, grabbing the breakpoint next to it
+    /// or adding one. With `remove` the grabbed breakpoint is deleted instead.
+    pub fn press_envelope(&self, tracks: &[Track], point: GridPoint, slop: HitSlop, remove: bool) {
+        self.inner.borrow_mut().cursor = Some(point);
+        let Some(hit) = hit_test(tracks, point, slop) else {
+            return;
+        };
+        let clip_frame = hit.region.clip_start_frame() + point.frame - hit.region.track_start_frame();
+        let grabbed = hit.region.envelope().nearest(clip_frame, slop.edge_frames);
+        self.set_selected(HashSet::from([hit.region.id()]));
+
+        let mut inner = self.inner.borrow_mut();
+        inner.drag = Some(GridDrag::Envelope {
+            track_index: hit.track_index,
+            region: hit.region,
+            grabbed,
+            remove,
+            padding_rows: slop.padding_rows,
+        });
+        inner.before = Some(TracksSnapshot::capture(tracks));
+    }
+
+    /// Applies the drag in progress to the tracks for live feedback. With `snap` moved region edges
+    /// and trimmed edges snap to its targets, to other regions and trims also to zero crossings.
+    pub fn update_drag(&self, tracks: &[Track], point: GridPoint, snap: Option<&GridSnap>) {
+        let mut inner = self.inner.borrow_mut();
+        let Some(drag) = inner.drag.as_mut() else {
+            return;
+        };
+        let selected = match drag {
+            GridDrag::Move { origin, regions } => {
+                let Some(origin_row) = origin.track_index().filter(|_| !regions.is_empty()) else {
+                    return;
+                };
+                let first_frame = regions.iter().map(|r| r.region.track_start_frame()).min().unwrap_or(0);
+                let mut frame_delta = (point.frame as i64 - origin.frame as i64).max(-(first_frame as i64));
+                if let Some(snap) = snap {
+                    let dragged: HashSet<RegionId> = regions.iter().map(|r| r.region.id()).collect();
+                    let last_frame = regions.iter().map(|r| r.region.track_end_frame()).max().unwrap_or(0);
+                    let edges = [first_frame, last_frame].map(|edge| (edge as i64 + frame_delta) as usize);
+                    let shift = snap.clone().with_region_edges(tracks, &dragged).snap_edges(&edges);
+                    frame_delta = (frame_delta + shift).max(-(first_frame as i64));
+                }
+
+                let first_track = regions.iter().map(|r| r.origin_track).min().unwrap_or(0) as i64;
+                let last_track = regions.iter().map(|r| r.origin_track).max().unwrap_or(0) as i64;
+                let max_track = tracks.len() as i64 - 1;
+                let row = point.row.floor() as i64;
+                let track_delta = (row - origin_row as i64).clamp(-first_track, max_track - last_track);
+
+                for dragged in regions.iter_mut() {
+                    let mut region = dragged.region.clone();
+                    region.set_track_start_frame((region.track_start_frame() as i64 + frame_delta) as usize);
+                    let target = (dragged.origin_track as i64 + track_delta) as usize;
+                    tracks[dragged.current_track].take_region(region.id());
+                    tracks[target].insert_region(region);
+                    dragged.current_track = target;
+                }
+                None
+            }
+            GridDrag::Trim {
+                kind,
+                track_index,
+                region,
+            } => {
+                let Some(track) = tracks.get(*track_index) else {
+                    return;
+                };
+                let frame = match snap {
+                    Some(snap) => {
+                        let mut snap = snap.clone().with_region_edges(tracks, &HashSet::from([region.id()]));
+                        if matches!(kind, RegionHitKind::Start | RegionHitKind::End) {
+                            let crossing = region_zero_crossing(region, point.frame, snap.threshold_frames());
+                            snap = snap.with_targets(crossing);
+                        }
+                        snap.snap(point.frame)
+                    }
+                    None => point.frame,
+                };
+                track.update_region(region.id(), |current| {
+                    *current = region.clone();
+                    match kind {
+                        RegionHitKind::Start => current.trim_start_to(frame),
+                        RegionHitKind::End => current.trim_end_to(frame),
+                        RegionHitKind::FadeIn => current.set_fade_in(Fade {
+                            frames: frame.saturating_sub(current.track_start_frame()),
+                            ..current.fade_in()
+                        }),
+                        RegionHitKind::FadeOut => current.set_fade_out(Fade {
+                            frames: current.track_end_frame().saturating_sub(frame),
+                            ..current.fade_out()
+                        }),
+                        RegionHitKind::Body => {}
+                    }
+                });
+                None
+            }
+            GridDrag::Select { origin, position, base } => {
+                *position = point;
+                let start_frame = origin.frame.min(point.frame);
+                let end_frame = origin.frame.max(point.frame);
+                let mut selected = base.clone();
+                for index in rows_in_span(origin.row, point.row, tracks.len()) {
+                    let regions = tracks[index].regions();
+                    let inside = regions
+                        .iter()
+                        .filter(|r| r.track_start_frame() <= end_frame && r.track_end_frame() > start_frame);
+                    selected.extend(inside.map(|r| r.id()));
+                }
+                Some(selected)
+            }
+            GridDrag::Envelope {
+                track_index,
+                region,
+                grabbed,
+                remove,
+                padding_rows,
+            } => {
+                let Some(track) = tracks.get(*track_index) else {
+                    return;
+                };
+                let start = region.track_start_frame();
+                let frame = point
+                    .frame
+                    .clamp(start, region.track_end_frame().saturating_sub(1).max(start));
+                let breakpoint = EnvelopePoint {
+                    frame: region.clip_start_frame() + frame - region.track_start_frame(),
+                    gain_db: Envelope::height_to_db(region_height(point, *track_index, *padding_rows)),
+                };
+                track.update_region(region.id(), |current| {
+                    *current = region.clone();
+                    let envelope = current.envelope_mut();
+                    if let Some(index) = grabbed {
+                        envelope.remove(*index);
+                    }
+                    if !*remove {
+                        envelope.insert(breakpoint);
+                    }
+                });
+                None
+            }
+        };
+        if let Some(selected) = selected {
+            inner.selected = selected;
+        }
+    }
+
+    /// Finishes the drag, resolving overlaps on every track it touched. Returns the edit when regions changed.
+    pub fn end_drag(&self, tracks: &[Track]) -> Option<GridEdit> {
+        let (drag, before) = {
+            let mut inner = self.inner.borrow_mut();
+            (inner.drag.take()?, inner.before.take())
+        };
+        let (label, touched): (&str, HashSet<usize>) = match drag {
+            GridDrag::Move { regions, .. } => (
+                "Move regions",
+                regions.iter().flat_map(|r| [r.origin_track, r.current_track]).collect(),
+            ),
+            GridDrag::Trim {
+                kind: RegionHitKind::FadeIn | RegionHitKind::FadeOut,
+                track_index,
+                ..
+            } => ("Adjust fade", HashSet::from([track_index])),
+            GridDrag::Trim { track_index, .. } => ("Trim region", HashSet::from([track_index])),
+            GridDrag::Envelope {
+                track_index, remove, ..
+            } => match remove {
+                true => ("Remove envelope point", HashSet::from([track_index])),
+                false => ("Edit volume envelope", HashSet::from([track_index])),
+            },
+            GridDrag::Select { .. } => return None,
+        };
+        for index in touched {
+            if let Some(track) = tracks.get(index) {
+                track.clean_regions();
+            }
+        }
+        self.retain_existing(tracks);
+        GridEdit::diff(label, &before?, tracks)
+    }
+
+    /// Drops selected regions that are no longer on any track.
+    pub fn retain_existing(&self, tracks: &[Track]) {
+        let remaining: HashSet<RegionId> = tracks
+            .iter()
+            .flat_map(|track| track.regions())
+            .map(|region| region.id())
+            .collect();
+        self.inner.borrow_mut().selected.retain(|id| remaining.contains(id));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_region_hit_kind() {
+        assert_eq!(region_hit_kind(100, 200, 99, 10), None);
+        assert_eq!(region_hit_kind(100, 200, 200, 10), None);
+        assert_eq!(region_hit_kind(100, 200, 105, 10), Some(RegionHitKind::Start));
+        assert_eq!(region_hit_kind(100, 200, 150, 10), Some(RegionHitKind::Body));
+        assert_eq!(region_hit_kind(100, 200, 195, 10), Some(RegionHitKind::End));
+    }
+
+    #[test]
+    fn test_region_hit_kind_short_region() {
+        // edges shrink to a third of the region
+        assert_eq!(region_hit_kind(0, 9, 2, 10), Some(RegionHitKind::Start));
+        assert_eq!(region_hit_kind(0, 9, 4, 10), Some(RegionHitKind::Body));
+        assert_eq!(region_hit_kind(0, 9, 6, 10), Some(RegionHitKind::End));
+    }
+
+    #[test]
+    fn test_fade_handle_hit() {
+        let clip = crate::components::waveform::test_clip("fade_handle_hit", 1000);
+        let mut region = TrackRegion::new(&clip, 0, 1000, 100);
+        region.set_fade_in(Fade {
+            frames: 200,
+            ..Fade::NONE
+        });
+        assert_eq!(fade_handle_hit(&region, 305, 10), Some(RegionHitKind::FadeIn));
+        // without a fade-out its handle sits on the region end
+        assert_eq!(fade_handle_hit(&region, 1095, 10), Some(RegionHitKind::FadeOut));
+        assert_eq!(fade_handle_hit(&region, 600, 10), None);
+    }
+
+    #[test]
+    fn test_rows_in_span() {
+        assert_eq!(rows_in_span(0.5, 0.7, 10), 0..1);
+        assert_eq!(rows_in_span(2.9, 0.2, 10), 0..3);
+        assert_eq!(rows_in_span(-1.0, 1.5, 10), 0..2);
+        assert_eq!(rows_in_span(8.5, 14.0, 10), 8..10);
+    }
+
+    #[test]
+    fn test_region_height() {
+        let point = |row| GridPoint { frame: 0, row };
+        assert_eq!(region_height(point(2.1), 2, 0.1), 1.0);
+        assert_eq!(region_height(point(2.5), 2, 0.1), 0.5);
+        assert_eq!(region_height(point(2.95), 2, 0.1), 0.0);
+        // dragged above the row
+        assert_eq!(region_height(point(1.5), 2, 0.1), 1.0);
+    }
+
+    #[test]
+    fn test_grid_point_track_index() {
+        assert_eq!(GridPoint { frame: 0, row: 1.5 }.track_index(), Some(1));
+        assert_eq!(GridPoint { frame: 0, row: -0.5 }.track_index(), None);
+    }
+}
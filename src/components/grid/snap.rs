@@ -0,0 +1,120 @@
+use crate::components::region::{RegionId, TrackRegion};
+use crate::components::track::Track;
+use std::collections::HashSet;
+
+/// Frames a drag snaps to when it comes within `threshold_frames` of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GridSnap {
+    targets: Vec<usize>,
+    threshold_frames: usize,
+}
+
+impl GridSnap {
+    pub fn new(threshold_frames: usize) -> Self {
+        Self {
+            targets: Vec::new(),
+            threshold_frames,
+        }
+    }
+
+    pub fn with_targets(mut self, targets: impl IntoIterator<Item = usize>) -> Self {
+        self.targets.extend(targets);
+        self.targets.sort_unstable();
+        self.targets.dedup();
+        self
+    }
+
+    /// Adds the start and end of every region that is not in `exclude`.
+    pub fn with_region_edges(self, tracks: &[Track], exclude: &HashSet<RegionId>) -> Self {
+        let edges: Vec<usize> = tracks
+            .iter()
+            .flat_map(|track| track.regions())
+            .filter(|region| !exclude.contains(&region.id()))
+            .flat_map(|region| [region.track_start_frame(), region.track_end_frame()])
+            .collect();
+        self.with_targets(edges)
+    }
+
+    pub fn threshold_frames(&self) -> usize {
+        self.threshold_frames
+    }
+
+    /// Nearest target within the threshold, `frame` itself otherwise.
+    pub fn snap(&self, frame: usize) -> usize {
+        self.nearest(frame).unwrap_or(frame)
+    }
+
+    /// Shift that snaps the closest of `edges` to a target, 0 when none is in reach.
+    pub fn snap_edges(&self, edges: &[usize]) -> i64 {
+        edges
+            .iter()
+            .filter_map(|edge| self.nearest(*edge).map(|target| target as i64 - *edge as i64))
+            .min_by_key(|shift| shift.abs())
+            .unwrap_or(0)
+    }
+
+    fn nearest(&self, frame: usize) -> Option<usize> {
+        let index = self.targets.partition_point(|target| *target < frame);
+        let before = index.checked_sub(1).map(|index| self.targets[index]);
+        let after = self.targets.get(index).copied();
+        [before, after]
+            .into_iter()
+            .flatten()
+            .filter(|target| target.abs_diff(frame) <= self.threshold_frames)
+            .min_by_key(|target| target.abs_diff(frame))
+    }
+}
+
+/// Index of the zero crossing closest to `center`, where the sample is zero or its sign differs from the previous one.
+pub fn nearest_zero_crossing(samples: &[f32], center: usize) -> Option<usize> {
+    let crossing =
+        |index: usize| samples[index] == 0.0 || (index > 0 && (samples[index - 1] < 0.0) != (samples[index] < 0.0));
+    (0..samples.len())
+        .filter(|index| crossing(*index))
+        .min_by_key(|index| index.abs_diff(center))
+}
+
+/// Track frame of the zero crossing of the first clip channel closest to `track_frame`, within `max_frames`.
+/// The search covers the whole clip, so a trim can reach a crossing outside the region.
+pub fn region_zero_crossing(region: &TrackRegion, track_frame: usize, max_frames: usize) -> Option<usize> {
+    let clip = region.clip();
+    let channel = clip.channels().first()?;
+    let offset = region.clip_start_frame() as i64 - region.track_start_frame() as i64;
+    let clip_frame = (track_frame as i64 + offset).clamp(0, clip.frame_count() as i64) as usize;
+    let start = clip_frame.saturating_sub(max_frames);
+    let end = (clip_frame + max_frames + 1).min(clip.frame_count());
+    let samples = channel.read_frames(start, end).ok()?;
+    let crossing = start + nearest_zero_crossing(&samples, clip_frame - start)?;
+    usize::try_from(crossing as i64 - offset).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_snap_to_nearest_target_in_reach() {
+        let snap = GridSnap::new(10).with_targets([100, 200, 120]);
+        assert_eq!(snap.snap(95), 100);
+        assert_eq!(snap.snap(112), 120);
+        assert_eq!(snap.snap(150), 150);
+        assert_eq!(snap.snap(0), 0);
+    }
+
+    #[test]
+    fn test_snap_edges_takes_the_closest_edge() {
+        let snap = GridSnap::new(10).with_targets([100, 300]);
+        assert_eq!(snap.snap_edges(&[92, 297]), 3);
+        assert_eq!(snap.snap_edges(&[95, 250]), 5);
+        assert_eq!(snap.snap_edges(&[150, 250]), 0);
+    }
+
+    #[test]
+    fn test_nearest_zero_crossing() {
+        let samples = [0.5, 0.25, -0.25, -0.5, -0.25, 0.25, 0.5];
+        assert_eq!(nearest_zero_crossing(&samples, 1), Some(2));
+        assert_eq!(nearest_zero_crossing(&samples, 4), Some(5));
+        assert_eq!(nearest_zero_crossing(&[0.25, 0.5], 0), None);
+        assert_eq!(nearest_zero_crossing(&[0.25, 0.0, 0.5], 2), Some(1));
+    }
+}
@@ -1,17 +1,52 @@
-use crate::components::grid::GridViewport;
+use crate::audio::AudioError;
+use crate::components::grid::{
+    ClipboardRegion, GridComparison, GridEdit, GridHistory, GridMarkers, GridPoint, GridSelection, GridViewport,
+    GridViewportHandle, TracksSnapshot, copy_regions, delete_regions, duplicate_regions, move_track, paste_regions,
+    place_clips, split_regions,
+};
+use crate::components::region::TrackRegion;
 use crate::components::track::Track;
+use crate::components::transport::Transport;
 use crate::components::waveform::WaveClip;
 use crate::time::SampleRate;
-use gpui::{AppContext, Context, Entity, Subscription, px};
+use anyhow::Result;
+use gpui::{App, AppContext, Context, Entity, Pixels, Subscription, Task, Window, px};
+use gpui_component::input::{InputEvent, InputState};
 use gpui_component::slider::{SliderEvent, SliderScale, SliderState};
-use log::info;
+use log::{error, info};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// playhead refresh while playing
+const PLAYHEAD_REFRESH: Duration = Duration::from_millis(30);
+// bounds of a track row resized by dragging its header
+const MIN_TRACK_HEIGHT: Pixels = px(80.0);
+const MAX_TRACK_HEIGHT: Pixels = px(800.0);
+
+// inline title edit of a track header
+struct TrackRename {
+    track: Track,
+    input: Entity<InputState>,
+    _subscription: Subscription,
+}
 
 pub struct GridState {
     tracks: Arc<Mutex<Vec<Track>>>,
     pub viewport: GridViewport,
+    pub selection: GridSelection,
+    pub history: GridHistory,
+    pub markers: GridMarkers,
+    pub comparison: GridComparison,
+    pub transport: Transport,
+    clipboard: Mutex<Vec<ClipboardRegion>>,
     pub x_slider: Entity<SliderState>,
     pub y_slider: Entity<SliderState>,
+    rename: Option<TrackRename>,
+    // tracks before the resize drag in progress
+    resize: Option<TracksSnapshot>,
+    // last pointer position of a file drag over the track list
+    drop_point: Option<GridPoint>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -49,32 +84,433 @@ impl GridState {
             }
         });
 
+        let tracks = Arc::new(Mutex::new(Vec::new()));
         let state = Self {
-            tracks: Arc::new(Mutex::new(random_tracks())),
+            transport: Transport::new(&tracks, sample_rate.into()),
+            tracks,
             viewport: GridViewport::new(sample_rate),
+            selection: GridSelection::new(),
+            history: GridHistory::new(),
+            markers: GridMarkers::new(),
+            comparison: GridComparison::new(),
+            clipboard: Mutex::new(Vec::new()),
             x_slider,
             y_slider,
+            rename: None,
+            resize: None,
+            drop_point: None,
             _subscriptions: vec![x_sub, y_sub],
         };
         state.update_viewport();
         state
     }
 
+    pub fn load_test_tracks(&self) -> Result<()> {
+        let tracks = random_tracks()?;
+        *self.tracks.lock().unwrap() = tracks;
+        self.history.clear();
+        self.update_viewport();
+        Ok(())
+    }
+
     pub fn tracks(&self) -> Vec<Track> {
         self.tracks.lock().unwrap().clone()
     }
 
-    pub fn update_tracks(&self, updater: impl Fn(&mut Vec<Track>)) {
+    /// Applies an edit to the tracks and records it for undo under `label`.
+    pub fn update_tracks(&self, label: &str, updater: impl FnOnce(&mut Vec<Track>)) {
         info!("update_tracks get lock");
         let mut tracks = self.tracks.lock().unwrap();
+        let before = TracksSnapshot::capture(&tracks);
         info!("update_tracks call function");
         updater(&mut tracks);
+        if let Some(edit) = GridEdit::diff(label, &before, &tracks) {
+            self.history.push(edit);
+        }
         drop(tracks);
         info!("update_tracks update viewport");
         self.update_viewport();
         info!("update_tracks complete");
     }
 
+    pub fn undo(&self) -> Option<String> {
+        let label = self.history.undo(&mut self.tracks.lock().unwrap());
+        self.after_history_change();
+        label
+    }
+
+    pub fn redo(&self) -> Option<String> {
+        let label = self.history.redo(&mut self.tracks.lock().unwrap());
+        self.after_history_change();
+        label
+    }
+
+    /// Undoes or redoes until `position` edits of the history are applied.
+    pub fn jump_history(&self, position: usize) {
+        self.history.jump_to(position, &mut self.tracks.lock().unwrap());
+        self.after_history_change();
+    }
+
+    /// Splits the selected regions at the edit cursor, or every region under it on the cursor track.
+    pub fn split_at_cursor(&self) {
+        let Some(cursor) = self.selection.cursor() else {
+            return;
+        };
+        let mut selected = self.selection.selected();
+        let mut created = Vec::new();
+        self.update_tracks("Split regions", |tracks| {
+            created = match (selected.is_empty(), cursor.track_index()) {
+                (false, _) => split_regions(tracks, Some(&selected), cursor.frame),
+                (true, Some(index)) if index < tracks.len() => {
+                    split_regions(&tracks[index..=index], None, cursor.frame)
+                }
+                _ => Vec::new(),
+            };
+        });
+        if !selected.is_empty() {
+            selected.extend(created);
+            self.selection.set_selected(selected);
+        }
+    }
+
+    /// Copies the selected regions, returns how many were copied.
+    pub fn copy_selection(&self) -> usize {
+        let clipboard = copy_regions(&self.selection.selected_regions(&self.tracks()));
+        let count = clipboard.len();
+        if count > 0 {
+            *self.clipboard.lock().unwrap() = clipboard;
+        }
+        count
+    }
+
+    pub fn cut_selection(&self) {
+        if self.copy_selection() > 0 {
+            self.delete_selection("Cut regions", false);
+        }
+    }
+
+    /// Pastes the copied regions at the edit cursor, keeping their relative tracks and offsets.
+    pub fn paste_at_cursor(&self) {
+        let clipboard = self.clipboard.lock().unwrap().clone();
+        let Some(cursor) = self.selection.cursor().filter(|_| !clipboard.is_empty()) else {
+            return;
+        };
+        let track_index = cursor.track_index().unwrap_or(0);
+        let mut pasted = Vec::new();
+        self.update_tracks("Paste regions", |tracks| {
+            pasted = paste_regions(tracks, &clipboard, track_index, cursor.frame);
+        });
+        self.selection.set_selected(pasted.into_iter().collect());
+    }
+
+    pub fn duplicate_selection(&self) {
+        let selected = self.selection.selected_regions(&self.tracks());
+        if selected.is_empty() {
+            return;
+        }
+        let mut duplicated = Vec::new();
+        self.update_tracks("Duplicate regions", |tracks| {
+            duplicated = duplicate_regions(tracks, &selected);
+        });
+        self.selection.set_selected(duplicated.into_iter().collect());
+    }
+
+    pub fn set_drop_point(&mut self, point: GridPoint) {
+        self.drop_point = Some(point);
+    }
+
+    pub fn drop_point(&self) -> Option<GridPoint> {
+        self.drop_point
+    }
+
+    /// Adds dropped clips at `point` and selects them, rows past the last track get new tracks.
+    pub fn place_clips(&self, clips: &[WaveClip], point: GridPoint) {
+        let track_index = point.track_index().unwrap_or(0);
+        let mut placed = Vec::new();
+        self.update_tracks("Drop files", |tracks| {
+            placed = place_clips(tracks, clips, track_index, point.frame);
+        });
+        self.selection.set_selected(placed.into_iter().collect());
+    }
+
+    /// Applies `updater` to every selected region as a single edit.
+    pub fn update_selected_regions(&self, label: &str, updater: impl Fn(&mut TrackRegion)) {
+        let selected = self.selection.selected();
+        if selected.is_empty() {
+            return;
+        }
+        self.update_tracks(label, |tracks| {
+            for track in tracks.iter() {
+                for id in &selected {
+                    track.update_region(*id, &updater);
+                }
+            }
+        });
+    }
+
+    /// Inserts an empty track at `index`, numbered after the track count.
+    pub fn add_track(&self, index: usize) {
+        self.update_tracks("Add track", |tracks| {
+            let track = Track::new(format!("Track {}", tracks.len() + 1));
+            tracks.insert(index.min(tracks.len()), track);
+        });
+    }
+
+    pub fn delete_track(&self, track: &Track) {
+        self.update_tracks("Delete track", |tracks| tracks.retain(|current| !current.ptr_eq(track)));
+        self.selection.retain_existing(&self.tracks());
+    }
+
+    /// Moves the track at `from` in front of the track at `to`, where the drop indicator shows.
+    pub fn move_track(&self, from: usize, to: usize) {
+        self.update_tracks("Move track", |tracks| move_track(tracks, from, to));
+    }
+
+    /// Sets the row height of `track`, `None` returns it to the default height.
+    pub fn set_track_height(&self, track: &Track, height: Option<Pixels>) {
+        self.update_tracks("Resize track", |_| track.set_height(clamp_track_height(height)));
+    }
+
+    /// Follows a resize drag of `track`, the whole drag becomes one edit in `finish_track_resize`.
+    pub fn resize_track(&mut self, track: &Track, height: Pixels) {
+        if self.resize.is_none() {
+            self.resize = Some(TracksSnapshot::capture(&self.tracks()));
+        }
+        track.set_height(clamp_track_height(Some(height)));
+        self.update_viewport();
+    }
+
+    /// Records the resize drag in progress, true when there was one.
+    pub fn finish_track_resize(&mut self) -> bool {
+        let Some(before) = self.resize.take() else {
+            return false;
+        };
+        if let Some(edit) = GridEdit::diff("Resize track", &before, &self.tracks()) {
+            self.history.push(edit);
+        }
+        true
+    }
+
+    pub fn set_track_color(&self, track: &Track, color: Option<u32>) {
+        self.update_tracks("Change track colour", |_| track.set_color(color));
+    }
+
+    /// Replaces the title of `track` with a focused input until enter or blur.
+    pub fn start_rename(&mut self, track: &Track, window: &mut Window, cx: &mut Context<Self>) {
+        let input = cx.new(|cx| InputState::new(window, cx).default_value(track.title()));
+        input.update(cx, |input, cx| input.focus(window, cx));
+        let subscription = cx.subscribe(&input, |this, _, event, cx| match event {
+            InputEvent::PressEnter { .. } | InputEvent::Blur => {
+                this.finish_rename(cx);
+                cx.notify();
+            }
+            _ => {}
+        });
+        self.rename = Some(TrackRename {
+            track: track.clone(),
+            input,
+            _subscription: subscription,
+        });
+    }
+
+    /// Input of the title edit in progress on `track`.
+    pub fn rename_input(&self, track: &Track) -> Option<Entity<InputState>> {
+        self.rename
+            .as_ref()
+            .filter(|rename| rename.track.ptr_eq(track))
+            .map(|rename| rename.input.clone())
+    }
+
+    fn finish_rename(&mut self, cx: &App) {
+        let Some(rename) = self.rename.take() else {
+            return;
+        };
+        let title = rename.input.read(cx).value();
+        if !title.trim().is_empty() {
+            self.update_tracks("Rename track", |_| rename.track.set_title(title));
+        }
+    }
+
+    /// Track frames from the start of the first to the end of the last selected region.
+    pub fn selection_span(&self) -> Option<(usize, usize)> {
+        let selected = self.selection.selected_regions(&self.tracks());
+        let start = selected.iter().map(|(_, region)| region.track_start_frame()).min()?;
+        let end = selected.iter().map(|(_, region)| region.track_end_frame()).max()?;
+        Some((start, end))
+    }
+
+    /// Removes the selected regions, `ripple` moves later regions left to close the gap.
+    pub fn delete_selection(&self, label: &str, ripple: bool) {
+        let selected = self.selection.selected();
+        if selected.is_empty() {
+            return;
+        }
+        self.update_tracks(label, |tracks| delete_regions(tracks, &selected, ripple));
+        self.selection.clear();
+    }
+
+    /// Starts or stops playback, the view follows the playhead until it passes the last region.
+    /// The returned task ends when playback stops, with the read error that stopped it if any.
+    pub fn toggle_playback(&self, cx: &mut Context<Self>) -> Result<Task<Option<AudioError>>> {
+        if self.transport.is_playing() {
+            self.transport.stop();
+            cx.notify();
+            return Ok(Task::ready(None));
+        }
+        self.transport.play()?;
+        cx.notify();
+        Ok(cx.spawn(async move |state, cx| {
+            loop {
+                cx.background_executor().timer(PLAYHEAD_REFRESH).await;
+                let status = state.update(cx, |state, cx| {
+                    let failure = state.transport.take_failure();
+                    if failure.is_some() || state.transport.position() >= state.tracks_frames() {
+                        state.transport.stop();
+                    }
+                    cx.notify();
+                    (state.transport.is_playing(), failure)
+                });
+                match status {
+                    Ok((true, _)) => {}
+                    Ok((false, failure)) => return failure,
+                    Err(_) => return None,
+                }
+            }
+        }))
+    }
+
+    pub fn locate(&self, frame: usize) {
+        self.transport.locate(frame.min(self.tracks_frames()));
+    }
+
+    /// Applies a change of the markers, ranges or loop and records it for undo under `label`.
+    pub fn update_markers(&self, label: &str, updater: impl FnOnce(&GridMarkers)) {
+        let before = self.markers.snapshot();
+        updater(&self.markers);
+        if let Some(edit) = GridEdit::markers(label, before, &self.markers) {
+            self.history.push(edit);
+            self.after_markers_change();
+        }
+    }
+
+    /// Keeps the markers in the session output `directory`, taking over the markers saved there.
+    pub fn open_markers(&self, directory: &Path) -> Result<()> {
+        let result = self.markers.open(directory);
+        self.transport.set_loop_range(self.markers.loop_range());
+        result
+    }
+
+    pub fn add_marker_at_playhead(&self) {
+        let frame = self.transport.position();
+        self.update_markers("Add marker", |markers| {
+            markers.add_marker(None, frame);
+        });
+    }
+
+    pub fn add_range_from_selection(&self) {
+        if let Some((start, end)) = self.selection_span() {
+            self.update_markers("Add range", |markers| {
+                markers.add_range(None, start, end);
+            });
+        }
+    }
+
+    /// Loops playback over `range`, `None` plays through.
+    pub fn set_loop_range(&self, range: Option<(usize, usize)>) {
+        let label = if range.is_some() { "Set loop" } else { "Clear loop" };
+        self.update_markers(label, |markers| markers.set_loop_range(range));
+    }
+
+    /// Clears the loop, or loops the selection when there is none.
+    pub fn toggle_loop(&self) {
+        match self.markers.loop_range() {
+            Some(_) => self.set_loop_range(None),
+            None => self.set_loop_range(self.selection_span()),
+        }
+    }
+
+    pub fn locate_next_marker(&self) {
+        if let Some(frame) = self.markers.next_after(self.transport.position()) {
+            self.locate(frame);
+        }
+    }
+
+    pub fn locate_previous_marker(&self) {
+        if let Some(frame) = self.markers.previous_before(self.transport.position()) {
+            self.locate(frame);
+        }
+    }
+
+    /// Zooms by `factor` around the edit cursor, or the middle of the view when the cursor is out of sight.
+    pub fn zoom_at_cursor(&self, factor: f64) {
+        let width = self.viewport.viewport_size().width;
+        let anchor_x = self
+            .selection
+            .cursor()
+            .map(|cursor| self.viewport.frame_to_scroll_offset(cursor.frame))
+            .filter(|x| *x >= px(0.0) && *x <= width)
+            .unwrap_or(width / 2.0);
+        self.viewport.zoom_at(factor, anchor_x);
+    }
+
+    pub fn zoom_to_selection(&self) {
+        if let Some((start, end)) = self.selection_span() {
+            self.viewport.zoom_to_frames(start, end);
+        }
+    }
+
+    /// Overlays the selected regions on the comparison lane, ordered by track and start.
+    pub fn compare_selection(&self) {
+        let mut selected = self.selection.selected_regions(&self.tracks());
+        selected.sort_by_key(|(index, region)| (*index, region.track_start_frame()));
+        let regions = selected.into_iter().map(|(_, region)| region).collect();
+        if let Err(err) = self.comparison.open(regions) {
+            error!("comparison not opened: {err}");
+        }
+    }
+
+    /// A/B switch of the comparison, the difference of the new front layer is computed if shown.
+    pub fn toggle_comparison_front(&self, cx: &mut Context<Self>) {
+        self.comparison.toggle_front();
+        self.update_comparison_difference(cx);
+    }
+
+    /// Shows or hides the comparison difference, computed in the background and drawn once ready.
+    pub fn toggle_comparison_difference(&self, cx: &mut Context<Self>) {
+        self.comparison.set_show_difference(!self.comparison.show_difference());
+        self.update_comparison_difference(cx);
+    }
+
+    fn update_comparison_difference(&self, cx: &mut Context<Self>) {
+        let Some(pending) = self.comparison.start_difference() else {
+            return;
+        };
+        cx.spawn(async move |state, cx| {
+            let generation = pending.generation;
+            let difference = cx.background_spawn(async move { pending.compute() }).await;
+            state
+                .update(cx, |state, cx| {
+                    state.comparison.finish_difference(generation, difference);
+                    cx.notify();
+                })
+                .ok();
+        })
+        .detach();
+    }
+
+    fn after_history_change(&self) {
+        self.selection.retain_existing(&self.tracks());
+        self.update_viewport();
+        self.after_markers_change();
+    }
+
+    fn after_markers_change(&self) {
+        self.transport.set_loop_range(self.markers.loop_range());
+        if let Err(error) = self.markers.save() {
+            error!("failed to save markers: {error}");
+        }
+    }
+
     fn tracks_frames(&self) -> usize {
         self.tracks
             .lock()
@@ -84,16 +520,16 @@ impl GridState {
     }
 
     fn update_viewport(&self) {
-        let tracks_count = self.tracks.lock().unwrap().len();
-        self.viewport.set_tracks_count(tracks_count);
+        let heights = self.tracks.lock().unwrap().iter().map(Track::height).collect();
+        self.viewport.set_track_heights(heights);
         self.viewport.set_total_frames(self.tracks_frames());
     }
 }
 
-fn random_tracks() -> Vec<Track> {
-    let clip = WaveClip::open("test_signal.wav").unwrap();
+fn random_tracks() -> Result<Vec<Track>> {
+    let clip = WaveClip::open("test_signal.wav")?;
 
-    (0..100)
+    let tracks = (0..100)
         .map(|t| {
             let title = format!("test {t}");
             let track = Track::new(title);
@@ -101,5 +537,10 @@ fn random_tracks() -> Vec<Track> {
             track.add_clip(&clip, clip.frame_count());
             track
         })
-        .collect()
+        .collect();
+    Ok(tracks)
+}
+
+fn clamp_track_height(height: Option<Pixels>) -> Option<Pixels> {
+    height.map(|height| height.max(MIN_TRACK_HEIGHT).min(MAX_TRACK_HEIGHT))
 }
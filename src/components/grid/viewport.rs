@@ -1,4 +1,4 @@
-use crate::components::tick::{GridTick, GridTickGenerator};
+use crate::components::tick::{GridTick, GridTickGenerator, RulerFormat};
 use crate::time::{Duration, SampleRate, TimeCode};
 use gpui::{Negate, Pixels, Point, Size, point, px, size};
 use gpui_component::PixelsExt;
@@ -6,6 +6,22 @@ use gpui_component::scroll::ScrollbarHandle;
 use std::cell::RefCell;
 use std::ops::Sub;
 use std::rc::Rc;
+use std::time::{Duration as StdDuration, Instant};
+
+// zoom steps closer than this form one gesture and one history entry
+const ZOOM_GESTURE_GAP: StdDuration = StdDuration::from_millis(500);
+// zoom history entries kept for back
+const ZOOM_HISTORY_LIMIT: usize = 50;
+// shaping of the zoom slider, 1.5..3.0 and 3..30
+const SCALE_LOG_GAMMA: f64 = 3.0;
+const SCALE_LOG_K: f64 = 9.0;
+
+/// Zoom and left edge of the view, restored by zoom back and forward.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct GridZoom {
+    scale: f64,
+    frame: f64,
+}
 
 #[derive(Clone)]
 pub struct GridViewport {
@@ -65,31 +81,123 @@ impl GridViewport {
     pub fn set_scale_log(&self, scale: f64) {
         let t = (scale / 100.0).clamp(0.0, 1.0);
         // если макс зум в начале (x=0), больше точности:
-        let gamma = 3.0f64; // 1.5..3.0
-        let t_shaped = t.powf(gamma);
+        let t_shaped = t.powf(SCALE_LOG_GAMMA);
 
         // лог-ощущение
-        let k = 9.0_f64; // 3..30
+        let k = SCALE_LOG_K;
         let scale_log_x = ((1.0 + k * t_shaped).ln() / (1.0 + k).ln()).clamp(0.0, 1.0);
         self.set_scale(scale_log_x);
     }
 
+    /// Slider position of the current zoom, the inverse of `set_scale_log`.
+    pub fn scale_log(&self) -> f64 {
+        let k = SCALE_LOG_K;
+        let t_shaped = (((1.0 + k).powf(self.scale()) - 1.0) / k).clamp(0.0, 1.0);
+        100.0 * t_shaped.powf(1.0 / SCALE_LOG_GAMMA)
+    }
+
     pub fn set_scale(&self, scale: f64) {
         let mut state = self.inner.borrow_mut();
-        let prev_frames_per_px = state.frames_per_px;
+        state.record_zoom_gesture();
+        state.set_scale_at(scale, px(0.0));
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.inner.borrow().scale
+    }
+
+    /// Multiplies the zoom by `factor` keeping the frame under `anchor_x` in place, `factor < 1` zooms in.
+    pub fn zoom_at(&self, factor: f64, anchor_x: Pixels) {
+        let mut state = self.inner.borrow_mut();
+        state.record_zoom_gesture();
+        let scale = state.scale * factor;
+        state.set_scale_at(scale, anchor_x);
+    }
 
-        state.scale = scale;
+    /// Shows the whole project.
+    pub fn zoom_to_fit(&self) {
+        let mut state = self.inner.borrow_mut();
+        state.record_zoom();
+        state.apply_zoom(GridZoom { scale: 1.0, frame: 0.0 });
+    }
+
+    /// Fits frames `start..end` to the view, centered when the zoom limit leaves room around them.
+    /// Calls in quick succession, like dragging the minimap edges, are one history entry.
+    pub fn zoom_to_frames(&self, start: usize, end: usize) {
+        let mut state = self.inner.borrow_mut();
+        if end <= start || state.total_frames == 0 {
+            return;
+        }
+        state.record_zoom_gesture();
+        state.scale = ((end - start) as f64 / state.total_frames as f64).clamp(state.min_scale(), 1.0);
         state.update_frames_per_px();
-        state.update_track_width();
-        state.update_scroll_offset_x_on_scale(prev_frames_per_px);
-        state.update_scroll_offset_x();
-        state.update_ticks();
+        let center = state.viewport_size.width / 2.0;
+        state.scroll_frame_to((start + end) as f64 / 2.0, center);
+    }
+
+    pub fn can_zoom_back(&self) -> bool {
+        !self.inner.borrow().zoom_back.is_empty()
+    }
+
+    pub fn can_zoom_forward(&self) -> bool {
+        !self.inner.borrow().zoom_forward.is_empty()
+    }
+
+    /// Returns to the zoom before the last zoom change.
+    pub fn zoom_back(&self) {
+        let mut state = self.inner.borrow_mut();
+        if let Some(zoom) = state.zoom_back.pop() {
+            let current = state.zoom();
+            state.zoom_forward.push(current);
+            state.last_zoom_step = None;
+            state.apply_zoom(zoom);
+        }
+    }
+
+    pub fn zoom_forward(&self) {
+        let mut state = self.inner.borrow_mut();
+        if let Some(zoom) = state.zoom_forward.pop() {
+            let current = state.zoom();
+            state.zoom_back.push(current);
+            state.last_zoom_step = None;
+            state.apply_zoom(zoom);
+        }
     }
 
-    pub fn set_tracks_count(&self, count: usize) {
+    /// Row heights of the tracks in order, `None` rows take the header height.
+    pub fn set_track_heights(&self, heights: Vec<Option<Pixels>>) {
         let mut state = self.inner.borrow_mut();
-        state.tracks_count = count;
+        state.track_heights = heights;
         state.update_track_height();
+        state.update_scroll_offset_y();
+    }
+
+    pub fn track_height(&self, index: usize) -> Pixels {
+        self.inner.borrow().track_height(index)
+    }
+
+    /// Top of the track row `index` from the top of the first row.
+    pub fn track_top(&self, index: usize) -> Pixels {
+        self.inner.borrow().track_top(index)
+    }
+
+    /// Fractional row at `y` from the top of the first row, rows past the tracks take the header height.
+    pub fn y_to_row(&self, y: Pixels) -> f32 {
+        let state = self.inner.borrow();
+        if y < px(0.0) {
+            return y / state.header_size.height;
+        }
+        let index = state.track_tops.partition_point(|top| *top <= y).saturating_sub(1);
+        index as f32 + (y - state.track_top(index)) / state.track_height(index)
+    }
+
+    pub fn row_to_y(&self, row: f32) -> Pixels {
+        let state = self.inner.borrow();
+        if row < 0.0 {
+            return state.header_size.height * row;
+        }
+        let index = row.floor() as usize;
+        state.track_top(index) + state.track_height(index) * row.fract()
     }
 
     pub fn set_total_frames(&self, count: usize) {
@@ -101,6 +209,18 @@ impl GridViewport {
         state.update_ticks();
     }
 
+    pub fn total_frames(&self) -> usize {
+        self.inner.borrow().total_frames
+    }
+
+    /// Frames between the padding edges of the view, the range `zoom_to_frames` fits.
+    pub fn visible_frames(&self) -> (usize, usize) {
+        let state = self.inner.borrow();
+        let start = state.scroll_offset_to_frame(state.viewport_padding);
+        let end = state.scroll_offset_to_frame(state.viewport_size.width - state.viewport_padding);
+        (start.min(state.total_frames), end.min(state.total_frames))
+    }
+
     pub fn on_scroll(&self, delta: Point<Pixels>) {
         self.on_scroll_x(delta.x);
         self.on_scroll_y(delta.y);
@@ -136,6 +256,16 @@ impl GridViewport {
     pub fn ticks(&self) -> Vec<GridTick> {
         self.inner.borrow().ticks.clone()
     }
+
+    pub fn ruler_format(&self) -> RulerFormat {
+        self.inner.borrow().generator.format()
+    }
+
+    pub fn set_ruler_format(&self, format: RulerFormat) {
+        let mut state = self.inner.borrow_mut();
+        state.generator.set_format(format);
+        state.update_ticks();
+    }
 }
 
 // scroll_width = padding + track_width + padding
@@ -155,13 +285,18 @@ struct GridViewportInner {
 
     min_frames_per_px: f64, // configured
 
-    tracks_count: usize, // provided (project)
-    total_frames: usize, // provided (project)
-    frames_per_px: f64,  // derived
-    seconds_per_px: f64, // derived
+    track_heights: Vec<Option<Pixels>>, // provided (project)
+    track_tops: Vec<Pixels>,            // derived, one past the last track
+    total_frames: usize,                // provided (project)
+    frames_per_px: f64,                 // derived
+    seconds_per_px: f64,                // derived
 
     generator: GridTickGenerator,
     ticks: Vec<GridTick>,
+
+    zoom_back: Vec<GridZoom>,        // state, most recent last
+    zoom_forward: Vec<GridZoom>,     // state, cleared on a new zoom
+    last_zoom_step: Option<Instant>, // state, continuous zoom gesture
 }
 
 impl GridViewportInner {
@@ -176,12 +311,16 @@ impl GridViewportInner {
             scroll_offset: point(px(0.0), px(0.0)),
             scale: 1.0,
             min_frames_per_px: 0.125,
-            tracks_count: 0,
+            track_heights: vec![],
+            track_tops: vec![px(0.0)],
             total_frames: 0,
             frames_per_px: 0.0,
             seconds_per_px: 0.0,
             generator: GridTickGenerator::new(px(60.0)),
             ticks: vec![],
+            zoom_back: vec![],
+            zoom_forward: vec![],
+            last_zoom_step: None,
         }
     }
 
@@ -191,31 +330,123 @@ impl GridViewportInner {
             (self.track_size.width + self.viewport_padding + self.viewport_padding).max(self.viewport_padding);
     }
 
+    fn track_height(&self, index: usize) -> Pixels {
+        self.track_heights
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.header_size.height)
+    }
+
+    fn track_top(&self, index: usize) -> Pixels {
+        let count = self.track_heights.len();
+        match self.track_tops.get(index) {
+            Some(top) => *top,
+            None => self.track_tops[count] + self.header_size.height * (index - count),
+        }
+    }
+
     fn update_track_height(&mut self) {
-        let base = self.header_size.height * self.tracks_count;
+        let mut top = px(0.0);
+        self.track_tops = vec![top];
+        for index in 0..self.track_heights.len() {
+            top += self.track_height(index);
+            self.track_tops.push(top);
+        }
+        let base = top;
         self.track_size.height = base.max(self.viewport_size.height);
         self.scroll_size.height = self.track_size.height;
     }
 
+    // frames_per_px on scale 100%
+    fn base_frames_per_px(&self) -> f64 {
+        let base_width = self.viewport_size.width - self.viewport_padding - self.viewport_padding;
+        self.total_frames as f64 / base_width.as_f64()
+    }
+
+    // scale at which a pixel holds min_frames_per_px
+    fn min_scale(&self) -> f64 {
+        let base_frames_per_px = self.base_frames_per_px();
+        if base_frames_per_px > 0.0 {
+            (self.min_frames_per_px / base_frames_per_px).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
     fn update_frames_per_px(&mut self) {
         let sample_rate: f64 = self.sample_rate.into();
 
-        // compute frames_per_px on scale 100%
-        let base_width = self.viewport_size.width - self.viewport_padding - self.viewport_padding;
-        let base_frames_per_px = self.total_frames as f64 / base_width.as_f64();
-        let frames_per_px = base_frames_per_px * self.scale;
+        let frames_per_px = self.base_frames_per_px() * self.scale;
 
         self.frames_per_px = frames_per_px.max(self.min_frames_per_px);
         self.seconds_per_px = self.frames_per_px / sample_rate;
     }
 
-    fn update_scroll_offset_x_on_scale(&mut self, prev_frames_per_px: f64) {
-        if self.scroll_offset.x.abs() > self.viewport_padding {
-            self.scroll_offset.x = px(
-                ((self.scroll_offset.x + self.viewport_padding).as_f64() * prev_frames_per_px / self.frames_per_px)
-                    as f32,
-            ) - self.viewport_padding;
+    fn set_scale_at(&mut self, scale: f64, anchor_x: Pixels) {
+        let prev_frames_per_px = self.frames_per_px;
+
+        self.scale = scale.clamp(self.min_scale(), 1.0);
+        self.update_frames_per_px();
+        self.update_track_width();
+        self.update_scroll_offset_x_on_scale(prev_frames_per_px, anchor_x);
+        self.update_scroll_offset_x();
+        self.update_ticks();
+    }
+
+    // keeps the frame under anchor_x in place, a track start right of the anchor stays put
+    fn update_scroll_offset_x_on_scale(&mut self, prev_frames_per_px: f64, anchor_x: Pixels) {
+        let track_offset = anchor_x - self.scroll_offset.x - self.viewport_padding;
+        if track_offset <= px(0.0) {
+            return;
+        }
+        let frame = track_offset.as_f64() * prev_frames_per_px;
+        self.scroll_offset.x = anchor_x - self.viewport_padding - px((frame / self.frames_per_px) as f32);
+    }
+
+    // scrolls frame to anchor_x at the current zoom
+    fn scroll_frame_to(&mut self, frame: f64, anchor_x: Pixels) {
+        self.update_track_width();
+        self.scroll_offset.x = anchor_x - self.viewport_padding - px((frame / self.frames_per_px) as f32);
+        self.update_scroll_offset_x();
+        self.update_ticks();
+    }
+
+    fn zoom(&self) -> GridZoom {
+        GridZoom {
+            scale: self.scale,
+            frame: (self.scroll_offset.x.negate() - self.viewport_padding).as_f64() * self.frames_per_px,
+        }
+    }
+
+    fn apply_zoom(&mut self, zoom: GridZoom) {
+        self.scale = zoom.scale.clamp(self.min_scale(), 1.0);
+        self.update_frames_per_px();
+        self.scroll_frame_to(zoom.frame, px(0.0));
+    }
+
+    fn record_zoom(&mut self) {
+        let zoom = self.zoom();
+        if self.zoom_back.last() != Some(&zoom) {
+            self.zoom_back.push(zoom);
+            if self.zoom_back.len() > ZOOM_HISTORY_LIMIT {
+                self.zoom_back.remove(0);
+            }
         }
+        self.zoom_forward.clear();
+        self.last_zoom_step = None;
+    }
+
+    // records only the first step of a continuous zoom
+    fn record_zoom_gesture(&mut self) {
+        let now = Instant::now();
+        let continued = self
+            .last_zoom_step
+            .is_some_and(|last| now.duration_since(last) < ZOOM_GESTURE_GAP);
+        if !continued {
+            self.record_zoom();
+        }
+        self.last_zoom_step = Some(now);
     }
 
     fn update_scroll_offset_x(&mut self) {
@@ -263,6 +494,7 @@ impl ScrollbarHandle for GridViewport {
 
 #[allow(dead_code)]
 pub trait GridViewportHandle {
+    fn sample_rate(&self) -> f64;
     fn header_size(&self) -> Size<Pixels>;
     fn viewport_size(&self) -> Size<Pixels>;
     fn viewport_padding(&self) -> Pixels;
@@ -284,6 +516,10 @@ pub trait GridViewportHandle {
 }
 
 impl GridViewportHandle for GridViewportInner {
+    fn sample_rate(&self) -> f64 {
+        self.sample_rate.into()
+    }
+
     fn header_size(&self) -> Size<Pixels> {
         self.header_size
     }
@@ -372,6 +608,10 @@ impl GridViewportHandle for GridViewportInner {
 }
 
 impl GridViewportHandle for GridViewport {
+    fn sample_rate(&self) -> f64 {
+        self.inner.borrow().sample_rate.into()
+    }
+
     fn header_size(&self) -> Size<Pixels> {
         self.inner.borrow().header_size
     }
@@ -900,4 +1140,84 @@ mod test {
         assert_eq!(view.scroll_offset_to_frame(px(0.0)), SR * 5);
         assert_eq!(view.scroll_offset_to_frame(px(500.0)), SR * 10);
     }
+
+    #[test]
+    fn test_variable_track_heights() {
+        let view = GridViewport::new(SR);
+        view.set_header_height(px(100.0));
+        view.set_viewport_height(px(50.0));
+        view.set_track_heights(vec![None, Some(px(40.0)), None]);
+
+        assert_eq!(view.track_size().height, px(240.0));
+        assert_eq!(view.track_top(1), px(100.0));
+        assert_eq!(view.track_top(2), px(140.0));
+        assert_eq!(view.track_top(4), px(340.0));
+        assert_eq!(view.track_height(1), px(40.0));
+
+        assert_eq!(view.y_to_row(px(50.0)), 0.5);
+        assert_eq!(view.y_to_row(px(110.0)), 1.25);
+        assert_eq!(view.y_to_row(px(290.0)), 3.5);
+        assert_eq!(view.y_to_row(px(-50.0)), -0.5);
+        assert_eq!(view.row_to_y(1.25), px(110.0));
+        assert_eq!(view.row_to_y(3.5), px(290.0));
+
+        view.set_header_height(px(60.0));
+        assert_eq!(view.track_size().height, px(160.0));
+    }
+
+    #[test]
+    fn test_zoom_at_anchor_and_history() {
+        let view = GridViewport::new(SR);
+        view.set_viewport_padding(px(0.0));
+        view.set_viewport_width(px(100.0));
+        view.set_total_frames(SR * 10);
+
+        // the frame under the anchor stays in place
+        view.zoom_at(0.1, px(50.0));
+        assert_eq!(view.frames_per_px(), SR * 0.01);
+        assert_eq!(view.frame_to_scroll_offset(SR * 5), px(50.0));
+
+        // steps of one gesture are one history entry
+        view.zoom_at(0.5, px(50.0));
+        assert_eq!(view.frame_to_scroll_offset(SR * 5), px(50.0));
+        view.zoom_back();
+        assert_eq!(view.scale(), 1.0);
+        assert_eq!(view.scroll_offset().x, px(0.0));
+        assert!(!view.can_zoom_back());
+
+        view.zoom_forward();
+        assert_eq!(view.scale(), 0.05);
+        assert_eq!(view.frame_to_scroll_offset(SR * 5), px(50.0));
+
+        // the selection is centered
+        view.zoom_to_frames(SR * 2, SR * 4);
+        assert_eq!(view.scale(), 0.2);
+        assert_eq!(view.frame_to_scroll_offset(SR * 3), px(50.0));
+        assert_eq!(view.visible_frames(), (SR * 2, SR * 4));
+        assert!(!view.can_zoom_forward());
+
+        view.zoom_to_fit();
+        assert_eq!(view.scale(), 1.0);
+        view.zoom_back();
+        assert_eq!(view.scale(), 0.2);
+
+        // zooming in stops at min_frames_per_px
+        view.zoom_at(1e-9, px(0.0));
+        assert_eq!(view.frames_per_px(), 0.125);
+    }
+
+    #[test]
+    fn test_scale_log_inverts_set_scale_log() {
+        let view = GridViewport::new(SR);
+        view.set_viewport_padding(px(0.0));
+        view.set_viewport_width(px(100.0));
+        view.set_total_frames(SR * 10);
+
+        for slider in [100.0, 60.0, 25.0] {
+            view.set_scale_log(slider);
+            assert!((view.scale_log() - slider).abs() < 1e-6, "{slider}");
+        }
+        view.zoom_to_fit();
+        assert!((view.scale_log() - 100.0).abs() < 1e-6);
+    }
 }
@@ -3,3 +3,4 @@ pub mod region;
 pub mod track;
 pub mod waveform;
 pub mod tick;
+pub mod transport;
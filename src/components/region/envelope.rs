@@ -0,0 +1,128 @@
+/// Volume breakpoint at a clip frame, so it stays on the audio when the region is moved or trimmed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnvelopePoint {
+    pub frame: usize,
+    pub gain_db: f32,
+}
+
+/// Breakpoint volume automation, interpolated in dB and held flat before the first and after the last point.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Envelope {
+    points: Vec<EnvelopePoint>,
+}
+
+impl Envelope {
+    pub const MIN_DB: f32 = -48.0;
+    pub const MAX_DB: f32 = 12.0;
+
+    pub fn points(&self) -> &[EnvelopePoint] {
+        &self.points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Adds a point keeping the frame order, a point on the same frame is replaced. Returns its index.
+    pub fn insert(&mut self, point: EnvelopePoint) -> usize {
+        let point = EnvelopePoint {
+            gain_db: point.gain_db.clamp(Self::MIN_DB, Self::MAX_DB),
+            ..point
+        };
+        match self.points.binary_search_by_key(&point.frame, |p| p.frame) {
+            Ok(index) => {
+                self.points[index] = point;
+                index
+            }
+            Err(index) => {
+                self.points.insert(index, point);
+                index
+            }
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<EnvelopePoint> {
+        (index < self.points.len()).then(|| self.points.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Index of the point closest to `frame` within `max_frames`.
+    pub fn nearest(&self, frame: usize, max_frames: usize) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.frame.abs_diff(frame) <= max_frames)
+            .min_by_key(|(_, p)| p.frame.abs_diff(frame))
+            .map(|(index, _)| index)
+    }
+
+    /// Envelope level at a clip frame, 0 dB without points.
+    pub fn gain_db_at(&self, frame: usize) -> f32 {
+        let index = self.points.partition_point(|p| p.frame <= frame);
+        match (index.checked_sub(1).map(|i| self.points[i]), self.points.get(index)) {
+            (None, None) => 0.0,
+            (Some(a), None) => a.gain_db,
+            (None, Some(b)) => b.gain_db,
+            (Some(a), Some(b)) => {
+                let t = (frame - a.frame) as f32 / (b.frame - a.frame) as f32;
+                a.gain_db + (b.gain_db - a.gain_db) * t
+            }
+        }
+    }
+
+    /// Height of a level within the region, 0 at the bottom and 1 at the top.
+    pub fn db_to_height(gain_db: f32) -> f32 {
+        (gain_db.clamp(Self::MIN_DB, Self::MAX_DB) - Self::MIN_DB) / (Self::MAX_DB - Self::MIN_DB)
+    }
+
+    pub fn height_to_db(height: f32) -> f32 {
+        Self::MIN_DB + height.clamp(0.0, 1.0) * (Self::MAX_DB - Self::MIN_DB)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn envelope(points: &[(usize, f32)]) -> Envelope {
+        let mut envelope = Envelope::default();
+        for (frame, gain_db) in points {
+            envelope.insert(EnvelopePoint {
+                frame: *frame,
+                gain_db: *gain_db,
+            });
+        }
+        envelope
+    }
+
+    #[test]
+    fn test_gain_db_at() {
+        assert_eq!(Envelope::default().gain_db_at(10), 0.0);
+
+        let envelope = envelope(&[(300, 0.0), (100, -12.0)]);
+        assert_eq!(envelope.points()[0].frame, 100);
+        assert_eq!(envelope.gain_db_at(0), -12.0);
+        assert_eq!(envelope.gain_db_at(200), -6.0);
+        assert_eq!(envelope.gain_db_at(300), 0.0);
+        assert_eq!(envelope.gain_db_at(1000), 0.0);
+    }
+
+    #[test]
+    fn test_insert_replaces_and_clamps() {
+        let envelope = envelope(&[(100, -6.0), (100, 40.0)]);
+        assert_eq!(envelope.points().len(), 1);
+        assert_eq!(envelope.gain_db_at(100), Envelope::MAX_DB);
+        assert_eq!(envelope.nearest(105, 10), Some(0));
+        assert_eq!(envelope.nearest(150, 10), None);
+    }
+
+    #[test]
+    fn test_height_round_trip() {
+        assert_eq!(Envelope::db_to_height(Envelope::MIN_DB), 0.0);
+        assert_eq!(Envelope::db_to_height(Envelope::MAX_DB), 1.0);
+        assert!((Envelope::height_to_db(Envelope::db_to_height(-3.0)) + 3.0).abs() < 1e-4);
+    }
+}
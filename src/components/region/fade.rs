@@ -0,0 +1,101 @@
+use std::f32::consts::FRAC_PI_2;
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FadeCurve {
+    Linear,
+    EqualPower,
+    SCurve,
+    Log,
+}
+
+impl FadeCurve {
+    pub const ALL: [FadeCurve; 4] = [
+        FadeCurve::Linear,
+        FadeCurve::EqualPower,
+        FadeCurve::SCurve,
+        FadeCurve::Log,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            FadeCurve::Linear => "Linear",
+            FadeCurve::EqualPower => "Equal power",
+            FadeCurve::SCurve => "S-curve",
+            FadeCurve::Log => "Log",
+        }
+    }
+
+    /// Fade-in gain at `t` in 0..=1, a fade-out uses `1 - t`.
+    pub fn gain(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EqualPower => (t * FRAC_PI_2).sin(),
+            FadeCurve::SCurve => 0.5 - 0.5 * (t * PI).cos(),
+            // fast rise, slow settle
+            FadeCurve::Log => (1.0 + 9.0 * t).log10(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fade {
+    pub frames: usize,
+    pub curve: FadeCurve,
+}
+
+impl Fade {
+    pub const NONE: Fade = Fade {
+        frames: 0,
+        curve: FadeCurve::EqualPower,
+    };
+
+    /// Gain `frames_in` frames into a fade-in.
+    pub fn gain_in(&self, frames_in: usize) -> f32 {
+        match self.frames {
+            0 => 1.0,
+            frames => self.curve.gain(frames_in as f32 / frames as f32),
+        }
+    }
+}
+
+impl Default for Fade {
+    fn default() -> Self {
+        Fade::NONE
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_curve_end_points() {
+        for curve in FadeCurve::ALL {
+            assert!(curve.gain(0.0).abs() < 1e-6, "{curve:?}");
+            assert!((curve.gain(1.0) - 1.0).abs() < 1e-6, "{curve:?}");
+        }
+    }
+
+    #[test]
+    fn test_equal_power_crossfade_keeps_power() {
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let a = FadeCurve::EqualPower.gain(t);
+            let b = FadeCurve::EqualPower.gain(1.0 - t);
+            assert!((a * a + b * b - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_fade_gain_in() {
+        let fade = Fade {
+            frames: 100,
+            curve: FadeCurve::Linear,
+        };
+        assert_eq!(fade.gain_in(50), 0.5);
+        assert_eq!(fade.gain_in(200), 1.0);
+        assert_eq!(Fade::NONE.gain_in(0), 1.0);
+    }
+}
@@ -1,6 +1,10 @@
+mod envelope;
+mod fade;
 mod region;
 mod view;
 
+pub use envelope::*;
+pub use fade::*;
 #[allow(unused_imports)]
 pub use region::*;
 #[allow(unused_imports)]
@@ -1,26 +1,64 @@
-use crate::components::waveform::WaveClip;
+use crate::audio::AudioResult;
+use crate::components::region::{Envelope, Fade, FadeCurve};
+use crate::components::waveform::{SampleGain, WaveClip, apply_gain, db_to_gain};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub type RegionId = usize;
+
+static NEXT_REGION_ID: AtomicUsize = AtomicUsize::new(1);
 
 #[derive(Clone)]
 pub struct TrackRegion {
+    id: RegionId,
     clip: WaveClip,
     clip_start_frame: usize,
     clip_end_frame: usize,
     track_start_frame: usize,
+    fade_in: Fade,
+    fade_out: Fade,
+    // derived from the overlaps on the track, kept apart from the fades the user sets
+    crossfade_in: Fade,
+    crossfade_out: Fade,
+    gain_db: f32,
+    inverted: bool,
+    envelope: Envelope,
 }
 
 impl TrackRegion {
     pub fn new(clip: &WaveClip, clip_start_frame: usize, clip_end_frame: usize, track_offset: usize) -> Self {
         let mut region = Self {
+            id: NEXT_REGION_ID.fetch_add(1, Ordering::Relaxed),
             clip: clip.clone(),
             clip_start_frame: 0,
             clip_end_frame: 0,
             track_start_frame: track_offset,
+            fade_in: Fade::NONE,
+            fade_out: Fade::NONE,
+            crossfade_in: Fade::NONE,
+            crossfade_out: Fade::NONE,
+            gain_db: 0.0,
+            inverted: false,
+            envelope: Envelope::default(),
         };
         region.set_clip_start_frame(clip_start_frame);
         region.set_clip_end_frame(clip_end_frame);
         region
     }
 
+    /// Copy of the region with a new identity.
+    pub fn duplicate(&self) -> Self {
+        Self {
+            id: NEXT_REGION_ID.fetch_add(1, Ordering::Relaxed),
+            ..self.clone()
+        }
+    }
+
+    /// Identity kept across moves and trims, clones share it.
+    pub fn id(&self) -> RegionId {
+        self.id
+    }
+
     pub fn clip(&self) -> WaveClip {
         self.clip.clone()
     }
@@ -44,24 +82,230 @@ impl TrackRegion {
     pub fn set_clip_start_frame(&mut self, frame: usize) {
         self.clip_start_frame = frame.min(self.clip.frame_count().saturating_sub(1));
         self.clip_end_frame = self.clip_end_frame.max(self.clip_start_frame);
+        self.clamp_fades();
     }
 
     pub fn set_clip_end_frame(&mut self, frame: usize) {
-        self.clip_end_frame = frame.max(self.clip_start_frame);
+        self.clip_end_frame = frame.min(self.clip.frame_count()).max(self.clip_start_frame);
+        self.clamp_fades();
     }
 
-    #[allow(dead_code)]
     pub fn set_track_start_frame(&mut self, frame: usize) {
         self.track_start_frame = frame;
     }
 
     pub fn set_track_end_frame(&mut self, frame: usize) {
-        let len = self.track_start_frame.saturating_sub(frame);
+        let len = frame.saturating_sub(self.track_start_frame);
         let end_frame = self.clip_start_frame + len;
         self.set_clip_end_frame(end_frame);
     }
 
+    /// Moves the start of the region keeping the audio in place on the track.
+    pub fn trim_start_to(&mut self, track_frame: usize) {
+        let audio_start = self.track_start_frame as i64 - self.clip_start_frame as i64;
+        let clip_start = (track_frame as i64 - audio_start).max(0) as usize;
+        self.set_clip_start_frame(clip_start.min(self.clip_end_frame.saturating_sub(1)));
+        self.track_start_frame = (audio_start + self.clip_start_frame as i64).max(0) as usize;
+    }
+
+    /// Moves the end of the region keeping the audio in place on the track.
+    pub fn trim_end_to(&mut self, track_frame: usize) {
+        let len = track_frame.saturating_sub(self.track_start_frame).max(1);
+        self.set_clip_end_frame(self.clip_start_frame + len);
+    }
+
+    /// Cuts the region at a track frame, keeps the left part and returns the right part as a new region.
+    pub fn split_at(&mut self, track_frame: usize) -> Option<TrackRegion> {
+        if track_frame <= self.track_start_frame() || track_frame >= self.track_end_frame() {
+            return None;
+        }
+        let mut right = self.duplicate();
+        right.trim_start_to(track_frame);
+        right.fade_in = Fade::NONE;
+        right.crossfade_in = Fade::NONE;
+        self.trim_end_to(track_frame);
+        self.fade_out = Fade::NONE;
+        self.crossfade_out = Fade::NONE;
+        Some(right)
+    }
+
+    pub fn fade_in(&self) -> Fade {
+        self.fade_in
+    }
+
+    pub fn fade_out(&self) -> Fade {
+        self.fade_out
+    }
+
+    /// Sets the fade-in, shortened so it does not run into the fade-out.
+    pub fn set_fade_in(&mut self, fade: Fade) {
+        self.fade_in = fade;
+        self.fade_in.frames = fade.frames.min(self.frames().saturating_sub(self.fade_out.frames));
+    }
+
+    /// Sets the fade-out, shortened so it does not run into the fade-in.
+    pub fn set_fade_out(&mut self, fade: Fade) {
+        self.fade_out = fade;
+        self.fade_out.frames = fade.frames.min(self.frames().saturating_sub(self.fade_in.frames));
+    }
+
+    /// Equal power crossfades over the overlaps with the neighbouring regions, set by the track.
+    pub fn crossfade_in(&self) -> Fade {
+        self.crossfade_in
+    }
+
+    pub fn crossfade_out(&self) -> Fade {
+        self.crossfade_out
+    }
+
+    pub fn set_crossfades(&mut self, in_frames: usize, out_frames: usize) {
+        let region_frames = self.frames();
+        let crossfade = |frames: usize| Fade {
+            frames: frames.min(region_frames),
+            curve: FadeCurve::EqualPower,
+        };
+        self.crossfade_in = crossfade(in_frames);
+        self.crossfade_out = crossfade(out_frames);
+    }
+
+    /// Fade and crossfade gain `frame` frames after the region start.
+    pub fn fade_gain(&self, frame: usize) -> f32 {
+        let frames_left = self.frames().saturating_sub(frame + 1);
+        self.fade_in.gain_in(frame)
+            * self.fade_out.gain_in(frames_left)
+            * self.crossfade_in.gain_in(frame)
+            * self.crossfade_out.gain_in(frames_left)
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        self.gain_db = gain_db;
+    }
+
+    /// Polarity invert, for null tests against a reference.
+    pub fn inverted(&self) -> bool {
+        self.inverted
+    }
+
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    pub fn envelope(&self) -> &Envelope {
+        &self.envelope
+    }
+
+    pub fn envelope_mut(&mut self) -> &mut Envelope {
+        &mut self.envelope
+    }
+
+    /// True when the region plays its clip unchanged.
+    pub fn is_unprocessed(&self) -> bool {
+        self.gain_db == 0.0
+            && !self.inverted
+            && self.envelope.is_empty()
+            && self.fade_in.frames == 0
+            && self.fade_out.frames == 0
+            && self.crossfade_in.frames == 0
+            && self.crossfade_out.frames == 0
+    }
+
+    /// Gain of fades, envelope, region gain and polarity `frame` frames after the region start.
+    pub fn gain_at(&self, frame: usize) -> f32 {
+        let gain_db = self.gain_db + self.envelope.gain_db_at(self.clip_start_frame + frame);
+        let polarity = if self.inverted { -1.0 } else { 1.0 };
+        polarity * db_to_gain(gain_db) * self.fade_gain(frame)
+    }
+
+    /// The region gain by clip frame, for drawing and exporting the clip as the region plays it. Owns a copy
+    /// of the region, reads go through `read_channel` instead.
+    pub fn sample_gain(&self) -> SampleGain {
+        let region = self.clone();
+        Arc::new(move |clip_frame| region.gain_at(clip_frame.saturating_sub(region.clip_start_frame)))
+    }
+
+    /// Processed samples of `channel` for `start..end` frames after the region start.
+    pub fn read_channel(&self, channel: usize, start: usize, end: usize) -> AudioResult<Vec<f32>> {
+        let Some(waveform) = self.clip.channels().get(channel) else {
+            return Ok(Vec::new());
+        };
+        let end = end.min(self.frames());
+        let mut samples = waveform.read_frames(self.clip_start_frame + start, self.clip_start_frame + end)?;
+        if !self.is_unprocessed() {
+            apply_gain(&mut samples, start, |frame| self.gain_at(frame));
+        }
+        Ok(samples)
+    }
+
+    fn clamp_fades(&mut self) {
+        let frames = self.frames();
+        self.fade_in.frames = self.fade_in.frames.min(frames);
+        self.fade_out.frames = self.fade_out.frames.min(frames - self.fade_in.frames);
+        self.crossfade_in.frames = self.crossfade_in.frames.min(frames);
+        self.crossfade_out.frames = self.crossfade_out.frames.min(frames);
+    }
+
     pub fn frames(&self) -> usize {
         self.clip_end_frame.saturating_sub(self.clip_start_frame)
     }
 }
+
+// edits compare regions by identity, placement and processing, the clip itself never changes
+impl PartialEq for TrackRegion {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.clip_start_frame == other.clip_start_frame
+            && self.clip_end_frame == other.clip_end_frame
+            && self.track_start_frame == other.track_start_frame
+            && self.fade_in == other.fade_in
+            && self.fade_out == other.fade_out
+            && self.crossfade_in == other.crossfade_in
+            && self.crossfade_out == other.crossfade_out
+            && self.gain_db == other.gain_db
+            && self.inverted == other.inverted
+            && self.envelope == other.envelope
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::region::EnvelopePoint;
+    use crate::components::waveform::test_clip;
+
+    #[test]
+    fn test_read_channel_applies_gain_and_polarity() {
+        let clip = test_clip("region_gain", 100);
+        let mut region = TrackRegion::new(&clip, 10, 100, 0);
+        assert_eq!(region.read_channel(0, 0, 4).unwrap(), vec![0.25; 4]);
+
+        region.set_gain_db(-6.0206);
+        region.set_inverted(true);
+        for sample in region.read_channel(1, 0, 4).unwrap() {
+            assert!((sample + 0.125).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_envelope_follows_clip_frames() {
+        let clip = test_clip("region_envelope", 100);
+        let mut region = TrackRegion::new(&clip, 0, 100, 0);
+        region.envelope_mut().insert(EnvelopePoint {
+            frame: 50,
+            gain_db: -20.0,
+        });
+        region.envelope_mut().insert(EnvelopePoint {
+            frame: 60,
+            gain_db: 0.0,
+        });
+        assert!((region.gain_at(50) - 0.1).abs() < 1e-4);
+
+        // trimming the start keeps the point on the same audio
+        region.trim_start_to(40);
+        assert!((region.gain_at(10) - 0.1).abs() < 1e-4);
+        assert_eq!(region.gain_at(30), 1.0);
+    }
+}
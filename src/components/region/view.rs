@@ -1,18 +1,29 @@
 use crate::components::region::region::TrackRegion;
-use crate::components::waveform::WaveClipView;
+use crate::components::region::{Envelope, Fade};
+use crate::components::waveform::{SpectrogramSettings, WaveClipView, WaveFormAmplitude};
 use gpui::{
     AnyElement, App, AvailableSpace, BorderStyle, Bounds, ContentMask, Corners, Edges, Element, ElementId,
-    GlobalElementId, InspectorElementId, LayoutId, PaintQuad, Pixels, Refineable, Style, Window, point, px, size,
+    GlobalElementId, Hsla, InspectorElementId, LayoutId, PaintQuad, Path, PathBuilder, Pixels, Point, Refineable,
+    Style, Window, fill, hsla, point, px, size,
 };
 use gpui::{IntoElement, StyleRefinement, Styled};
 use gpui_component::ActiveTheme;
 use std::panic::Location;
 
+// segments used to draw a fade curve
+const FADE_CURVE_STEPS: usize = 32;
+const FADE_HANDLE_SIZE: f32 = 8.0;
+const ENVELOPE_POINT_SIZE: f32 = 6.0;
+
 pub struct TrackRegionView {
     region: TrackRegion,
     start_frame: usize,
     end_frame: usize,
     frames_per_px: f64,
+    selected: bool,
+    color: Option<Hsla>,
+    spectrogram: Option<SpectrogramSettings>,
+    amplitude: WaveFormAmplitude,
     style: StyleRefinement,
 }
 
@@ -23,9 +34,35 @@ impl TrackRegionView {
             start_frame,
             end_frame,
             frames_per_px,
+            selected: false,
+            color: None,
+            spectrogram: None,
+            amplitude: WaveFormAmplitude::default(),
             style: StyleRefinement::default(),
         }
     }
+
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Colour of the track, the theme primary colour when `None`.
+    pub fn color(mut self, color: Option<Hsla>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Draws the clip as a spectrogram instead of a waveform.
+    pub fn spectrogram(mut self, settings: Option<SpectrogramSettings>) -> Self {
+        self.spectrogram = settings;
+        self
+    }
+
+    pub fn amplitude(mut self, amplitude: WaveFormAmplitude) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
 }
 
 impl Styled for TrackRegionView {
@@ -46,6 +83,8 @@ pub struct TrackRegionPrepaintState {
     style: Style,
     region_quad: PaintQuad,
     clip: AnyElement,
+    overlay_paths: Vec<(Path<Pixels>, Hsla)>,
+    overlay_handles: Vec<PaintQuad>,
 }
 
 struct TrackRegionLayoutResponse {
@@ -93,11 +132,17 @@ impl Element for TrackRegionView {
         let mut style = Style::default();
         style.refine(&self.style);
         let layout = self.prepaint_items(bounds, &style, window, cx).unwrap();
+        let (mut overlay_paths, mut overlay_handles) = self.prepaint_fades(bounds, cx);
+        let (envelope_path, envelope_handles) = self.prepaint_envelope(bounds);
+        overlay_paths.extend(envelope_path);
+        overlay_handles.extend(envelope_handles);
 
         TrackRegionPrepaintState {
             style,
             region_quad: layout.region_quad,
             clip: layout.clip,
+            overlay_paths,
+            overlay_handles,
         }
     }
 
@@ -116,6 +161,12 @@ impl Element for TrackRegionView {
                 window.with_content_mask(Some(ContentMask { bounds }), |window| {
                     window.paint_quad(prepaint.region_quad.clone());
                     prepaint.clip.paint(window, cx);
+                    for (path, color) in prepaint.overlay_paths.drain(..) {
+                        window.paint_path(path, color);
+                    }
+                    for handle in &prepaint.overlay_handles {
+                        window.paint_quad(handle.clone());
+                    }
                 })
             })
         });
@@ -123,6 +174,115 @@ impl Element for TrackRegionView {
 }
 
 impl TrackRegionView {
+    fn frame_to_x(&self, bounds: Bounds<Pixels>, track_frame: usize) -> Pixels {
+        let offset = (track_frame as f64 - self.start_frame as f64) / self.frames_per_px;
+        bounds.left() + px(offset as f32)
+    }
+
+    /// Shaded fade and crossfade areas with their curves on top, and a handle where each fade ends.
+    fn prepaint_fades(&self, bounds: Bounds<Pixels>, cx: &App) -> (Vec<(Path<Pixels>, Hsla)>, Vec<PaintQuad>) {
+        let theme = cx.theme();
+        let shade = hsla(0.0, 0.0, 0.0, 0.35);
+        let track_start = self.region.track_start_frame();
+        let track_end = self.region.track_end_frame();
+
+        let fade_in = self.region.fade_in();
+        let fade_out = self.region.fade_out();
+        let fade_in_end = self.frame_to_x(bounds, track_start + fade_in.frames);
+        let fade_out_start = self.frame_to_x(bounds, track_end - fade_out.frames);
+
+        // crossfades follow the overlaps, they get no handles
+        let crossfade_in = self.region.crossfade_in();
+        let crossfade_out = self.region.crossfade_out();
+        let region_start = self.frame_to_x(bounds, track_start);
+        let region_end = self.frame_to_x(bounds, track_end);
+
+        let mut paths = Vec::new();
+        for (fade, start, end, rising) in [
+            (fade_in, region_start, fade_in_end, true),
+            (fade_out, fade_out_start, region_end, false),
+            (
+                crossfade_in,
+                region_start,
+                self.frame_to_x(bounds, track_start + crossfade_in.frames),
+                true,
+            ),
+            (
+                crossfade_out,
+                self.frame_to_x(bounds, track_end - crossfade_out.frames),
+                region_end,
+                false,
+            ),
+        ] {
+            if fade.frames == 0 || end < bounds.left() || start > bounds.right() {
+                continue;
+            }
+            let curve = fade_curve_points(bounds, fade, start, end, rising);
+            let mut area = PathBuilder::fill();
+            area.move_to(point(start, bounds.top()));
+            curve.iter().for_each(|p| area.line_to(*p));
+            area.line_to(point(end, bounds.top()));
+            area.close();
+            let mut line = PathBuilder::stroke(px(1.5));
+            line.move_to(curve[0]);
+            curve[1..].iter().for_each(|p| line.line_to(*p));
+
+            paths.extend(area.build().ok().map(|path| (path, shade)));
+            paths.extend(line.build().ok().map(|path| (path, theme.foreground)));
+        }
+
+        let handle_size = size(px(FADE_HANDLE_SIZE), px(FADE_HANDLE_SIZE));
+        let handles = [fade_in_end, fade_out_start]
+            .into_iter()
+            .map(|x| {
+                let origin = point(x - handle_size.width / 2.0, bounds.top() + px(2.0));
+                fill(Bounds::new(origin, handle_size), theme.foreground)
+            })
+            .collect();
+        (paths, handles)
+    }
+
+    /// Volume envelope line across the region with a square on every breakpoint.
+    fn prepaint_envelope(&self, bounds: Bounds<Pixels>) -> (Option<(Path<Pixels>, Hsla)>, Vec<PaintQuad>) {
+        let envelope = self.region.envelope();
+        if envelope.is_empty() {
+            return (None, Vec::new());
+        }
+        let color = hsla(0.14, 0.9, 0.6, 1.0);
+        let clip_start = self.region.clip_start_frame();
+        let clip_end = self.region.clip_end_frame();
+        let to_point = |clip_frame: usize| {
+            let track_frame = self.region.track_start_frame() + clip_frame - clip_start;
+            let height = Envelope::db_to_height(envelope.gain_db_at(clip_frame));
+            point(
+                self.frame_to_x(bounds, track_frame),
+                bounds.bottom() - bounds.size.height * height,
+            )
+        };
+
+        let inner: Vec<usize> = envelope
+            .points()
+            .iter()
+            .map(|p| p.frame)
+            .filter(|frame| (clip_start..clip_end).contains(frame))
+            .collect();
+        let mut line = PathBuilder::stroke(px(1.5));
+        line.move_to(to_point(clip_start));
+        inner.iter().for_each(|frame| line.line_to(to_point(*frame)));
+        line.line_to(to_point(clip_end));
+
+        let point_size = size(px(ENVELOPE_POINT_SIZE), px(ENVELOPE_POINT_SIZE));
+        let handles = inner
+            .iter()
+            .map(|frame| {
+                let center = to_point(*frame);
+                let origin = point(center.x - point_size.width / 2.0, center.y - point_size.height / 2.0);
+                fill(Bounds::new(origin, point_size), color)
+            })
+            .collect();
+        (line.build().ok().map(|path| (path, color)), handles)
+    }
+
     fn prepaint_items(
         &mut self,
         bounds: Bounds<Pixels>,
@@ -161,9 +321,10 @@ impl TrackRegionView {
 
         let clip_bounds = Bounds::from_corners(point(left, top), point(right, bottom));
 
-        let track_offset = self.region.track_start_frame() + self.region.clip_start_frame();
-        let clip_start_frame = self.start_frame.saturating_sub(track_offset);
-        let clip_end_frame = self.end_frame.saturating_sub(track_offset);
+        // visible track frames to clip frames
+        let track_start_frame = self.region.track_start_frame();
+        let clip_start_frame = self.start_frame.saturating_sub(track_start_frame) + self.region.clip_start_frame();
+        let clip_end_frame = self.end_frame.saturating_sub(track_start_frame) + self.region.clip_start_frame();
 
         let mut clip = WaveClipView::new(
             &self.region.clip(),
@@ -171,6 +332,9 @@ impl TrackRegionView {
             clip_end_frame,
             self.frames_per_px,
         )
+        .gain((!self.region.is_unprocessed()).then(|| self.region.sample_gain()))
+        .spectrogram(self.spectrogram)
+        .amplitude(self.amplitude)
         .h(clip_bounds.size.height)
         .w(clip_bounds.size.width)
         .gap(px(8.0))
@@ -179,12 +343,17 @@ impl TrackRegionView {
         clip.layout_as_root(available_item_space, window, cx);
 
         let theme = cx.theme();
+        let color = self.color.unwrap_or(theme.primary);
+        let (background, border_color) = match self.selected {
+            true => (color.alpha(0.8), theme.foreground),
+            false => (color.alpha(0.5), color),
+        };
         let mut region_quad = PaintQuad {
             bounds,
             corner_radii: Corners::all(px(8.0)),
-            background: theme.primary.alpha(0.5).into(),
+            background: background.into(),
             border_widths: Edges::all(px(1.0)),
-            border_color: theme.primary,
+            border_color,
             border_style: BorderStyle::Solid,
         };
 
@@ -208,3 +377,23 @@ impl TrackRegionView {
         }
     }
 }
+
+// gain 0 sits on the bottom edge, unity on the top edge
+fn fade_curve_points(
+    bounds: Bounds<Pixels>,
+    fade: Fade,
+    start: Pixels,
+    end: Pixels,
+    rising: bool,
+) -> Vec<Point<Pixels>> {
+    (0..=FADE_CURVE_STEPS)
+        .map(|step| {
+            let t = step as f32 / FADE_CURVE_STEPS as f32;
+            let gain = match rising {
+                true => fade.curve.gain(t),
+                false => fade.curve.gain(1.0 - t),
+            };
+            point(start + (end - start) * t, bounds.bottom() - bounds.size.height * gain)
+        })
+        .collect()
+}
@@ -0,0 +1,268 @@
+use crate::time::{Duration, TimeCode};
+
+/// Frame rate of the SMPTE ruler.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SmpteRate {
+    Fps24,
+    Fps25,
+    Fps2997,
+    Fps2997Drop,
+    Fps30,
+}
+
+impl SmpteRate {
+    pub const ALL: [SmpteRate; 5] = [
+        SmpteRate::Fps24,
+        SmpteRate::Fps25,
+        SmpteRate::Fps2997,
+        SmpteRate::Fps2997Drop,
+        SmpteRate::Fps30,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            SmpteRate::Fps24 => "24 fps",
+            SmpteRate::Fps25 => "25 fps",
+            SmpteRate::Fps2997 => "29.97 fps",
+            SmpteRate::Fps2997Drop => "29.97 fps drop-frame",
+            SmpteRate::Fps30 => "30 fps",
+        }
+    }
+
+    pub fn frames_per_second(&self) -> f64 {
+        match self {
+            SmpteRate::Fps24 => 24.0,
+            SmpteRate::Fps25 => 25.0,
+            SmpteRate::Fps2997 | SmpteRate::Fps2997Drop => 30000.0 / 1001.0,
+            SmpteRate::Fps30 => 30.0,
+        }
+    }
+
+    /// Frame count of one labelled second.
+    pub fn nominal(&self) -> u64 {
+        match self {
+            SmpteRate::Fps24 => 24,
+            SmpteRate::Fps25 => 25,
+            SmpteRate::Fps2997 | SmpteRate::Fps2997Drop | SmpteRate::Fps30 => 30,
+        }
+    }
+
+    /// `hh:mm:ss:ff` of video frame `frame`, drop-frame skips labels 00 and 01 on each minute but every tenth.
+    pub fn timecode(&self, frame: u64) -> String {
+        let nominal = self.nominal();
+        let (frame, separator) = match self {
+            SmpteRate::Fps2997Drop => {
+                let (tens, rest) = (frame / 17982, frame % 17982);
+                let skipped = 18 * tens + if rest > 1 { 2 * ((rest - 2) / 1798) } else { 0 };
+                (frame + skipped, ';')
+            }
+            _ => (frame, ':'),
+        };
+        let seconds = frame / nominal;
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            separator,
+            frame % nominal
+        )
+    }
+}
+
+/// Step of the zoom ladder of a format, in format units.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RulerStep {
+    pub primary: u64,
+    pub secondary: u64,
+}
+
+/// Time format of the ruler, each with its unit, zoom ladder and labels.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum RulerFormat {
+    /// h:m:s.ms, in milliseconds
+    #[default]
+    Time,
+    /// in samples
+    Samples,
+    /// SMPTE timecode, in video frames
+    Smpte(SmpteRate),
+    /// bar.beat from a tempo, in sixteenths
+    BarsBeats { bpm: f64, beats_per_bar: u32 },
+}
+
+// sixteenths per beat of the bars/beats unit
+const BEAT_UNITS: u64 = 4;
+
+impl RulerFormat {
+    pub fn title(&self) -> String {
+        match self {
+            RulerFormat::Time => "Time".to_string(),
+            RulerFormat::Samples => "Samples".to_string(),
+            RulerFormat::Smpte(rate) => format!("SMPTE {}", rate.title()),
+            RulerFormat::BarsBeats { bpm, beats_per_bar } => format!("Bars/beats {bpm} bpm {beats_per_bar}/4"),
+        }
+    }
+
+    /// Sample frames of one unit.
+    pub fn unit_frames(&self, sample_rate: f64) -> f64 {
+        match self {
+            RulerFormat::Time => sample_rate / 1000.0,
+            RulerFormat::Samples => 1.0,
+            RulerFormat::Smpte(rate) => sample_rate / rate.frames_per_second(),
+            RulerFormat::BarsBeats { bpm, .. } => sample_rate * 60.0 / bpm / BEAT_UNITS as f64,
+        }
+    }
+
+    /// Zoom ladder from the finest step up.
+    pub fn steps(&self) -> Vec<RulerStep> {
+        let step = |primary: u64, secondary: u64| RulerStep { primary, secondary };
+        match self {
+            RulerFormat::Time => {
+                let ms = |duration: Duration| duration.to_millis();
+                [
+                    (Duration::Millis(1), Duration::Millis(1)),
+                    (Duration::Millis(2), Duration::Millis(1)),
+                    (Duration::Millis(5), Duration::Millis(1)),
+                    (Duration::Millis(10), Duration::Millis(1)),
+                    (Duration::Millis(20), Duration::Millis(2)),
+                    (Duration::Millis(50), Duration::Millis(5)),
+                    (Duration::Millis(100), Duration::Millis(10)),
+                    (Duration::Millis(200), Duration::Millis(20)),
+                    (Duration::Millis(500), Duration::Millis(50)),
+                    (Duration::Seconds(1), Duration::Millis(100)),
+                    (Duration::Seconds(2), Duration::Millis(200)),
+                    (Duration::Seconds(5), Duration::Millis(500)),
+                    (Duration::Seconds(10), Duration::Seconds(1)),
+                    (Duration::Seconds(20), Duration::Seconds(2)),
+                    (Duration::Seconds(30), Duration::Seconds(3)),
+                    (Duration::Minutes(1), Duration::Seconds(10)),
+                    (Duration::Minutes(2), Duration::Seconds(20)),
+                    (Duration::Minutes(3), Duration::Seconds(30)),
+                    (Duration::Minutes(10), Duration::Minutes(1)),
+                    (Duration::Minutes(20), Duration::Minutes(2)),
+                    (Duration::Minutes(30), Duration::Minutes(3)),
+                    (Duration::Hours(1), Duration::Minutes(10)),
+                    (Duration::Hours(2), Duration::Minutes(20)),
+                    (Duration::Hours(3), Duration::Minutes(30)),
+                    (Duration::Hours(10), Duration::Hours(1)),
+                    (Duration::Hours(20), Duration::Hours(2)),
+                    (Duration::Hours(30), Duration::Hours(3)),
+                ]
+                .into_iter()
+                .map(|(primary, secondary)| step(ms(primary), ms(secondary)))
+                .collect()
+            }
+            // 1, 2, 5 decades with a tenth as secondary
+            RulerFormat::Samples => (0..9)
+                .flat_map(|exponent| [1, 2, 5].map(|mantissa| mantissa * 10u64.pow(exponent)))
+                .map(|primary| step(primary, (primary / 10).max(1)))
+                .collect(),
+            RulerFormat::Smpte(rate) => {
+                let second = rate.nominal();
+                let (minute, hour) = (60 * second, 3600 * second);
+                // a fifth or sixth of a second, whichever divides it
+                let part = if second % 5 == 0 { second / 5 } else { second / 6 };
+                vec![
+                    step(1, 1),
+                    step(2, 1),
+                    step(5, 1),
+                    step(10, 1),
+                    step(second, part),
+                    step(2 * second, second),
+                    step(5 * second, second),
+                    step(10 * second, second),
+                    step(30 * second, 10 * second),
+                    step(minute, 10 * second),
+                    step(2 * minute, 30 * second),
+                    step(5 * minute, minute),
+                    step(10 * minute, minute),
+                    step(30 * minute, 10 * minute),
+                    step(hour, 10 * minute),
+                ]
+            }
+            RulerFormat::BarsBeats { beats_per_bar, .. } => {
+                let bar = *beats_per_bar as u64 * BEAT_UNITS;
+                // beats up to two bars, then quarters of the span in bars
+                let bars = [1, 2, 4, 8, 16, 32, 64, 128]
+                    .map(|bars| step(bars * bar, if bars < 4 { BEAT_UNITS } else { bars / 4 * bar }));
+                std::iter::once(step(BEAT_UNITS, 1)).chain(bars).collect()
+            }
+        }
+    }
+
+    /// Label of the tick at `unit`.
+    pub fn label(&self, unit: u64) -> String {
+        match self {
+            RulerFormat::Time => TimeCode::from_millis(unit).to_string(),
+            RulerFormat::Samples => unit.to_string(),
+            RulerFormat::Smpte(rate) => rate.timecode(unit),
+            RulerFormat::BarsBeats { beats_per_bar, .. } => {
+                let beats = unit / BEAT_UNITS;
+                let (bar, beat) = (beats / *beats_per_bar as u64 + 1, beats % *beats_per_bar as u64 + 1);
+                match unit % BEAT_UNITS {
+                    0 => format!("{bar}.{beat}"),
+                    sixteenth => format!("{bar}.{beat}.{}", sixteenth + 1),
+                }
+            }
+        }
+    }
+
+    /// Label of the unit containing sample `frame`.
+    pub fn format_frame(&self, frame: usize, sample_rate: f64) -> String {
+        // tolerate rounding of unit boundaries
+        let unit = (frame as f64 / self.unit_frames(sample_rate) + 1e-6).floor() as u64;
+        self.label(unit)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_smpte_timecode() {
+        assert_eq!(SmpteRate::Fps25.timecode(25 * 61 + 3), "00:01:01:03");
+        assert_eq!(SmpteRate::Fps2997.timecode(1800), "00:01:00:00");
+        // the first minute of drop-frame ends at 00:00:59;29, the next label is 00:01:00;02
+        assert_eq!(SmpteRate::Fps2997Drop.timecode(1799), "00:00:59;29");
+        assert_eq!(SmpteRate::Fps2997Drop.timecode(1800), "00:01:00;02");
+        // every tenth minute keeps its first labels
+        assert_eq!(SmpteRate::Fps2997Drop.timecode(17982), "00:10:00;00");
+    }
+
+    #[test]
+    fn test_bars_beats_labels() {
+        let format = RulerFormat::BarsBeats {
+            bpm: 120.0,
+            beats_per_bar: 4,
+        };
+        assert_eq!(format.label(0), "1.1");
+        assert_eq!(format.label(4), "1.2");
+        assert_eq!(format.label(16), "2.1");
+        assert_eq!(format.label(18), "2.1.3");
+        // a beat at 120 bpm is half a second
+        assert_eq!(format.format_frame(48000, 48000.0), "1.3");
+    }
+
+    #[test]
+    fn test_steps_grow() {
+        let formats = [
+            RulerFormat::Time,
+            RulerFormat::Samples,
+            RulerFormat::Smpte(SmpteRate::Fps24),
+            RulerFormat::BarsBeats {
+                bpm: 90.0,
+                beats_per_bar: 3,
+            },
+        ];
+        for format in formats {
+            let steps = format.steps();
+            assert!(
+                steps.windows(2).all(|pair| pair[0].primary < pair[1].primary),
+                "{format:?}"
+            );
+            assert!(steps.iter().all(|step| step.secondary <= step.primary), "{format:?}");
+        }
+    }
+}
@@ -1,110 +1,70 @@
 use crate::components::grid::GridViewportHandle;
-use crate::components::tick::{GridTick, GridTickType};
-use crate::time::Duration;
+use crate::components::tick::{GridTick, GridTickType, RulerFormat, RulerStep};
 use gpui::{Pixels, px};
 
 #[derive(Clone)]
 pub struct GridTickGenerator {
-    generators: Vec<TimeSpecGridGenerator>,
+    format: RulerFormat,
+    steps: Vec<RulerStep>,
+    min_primary_width: Pixels,
 }
 
 impl GridTickGenerator {
     pub fn new(min_primary_width: Pixels) -> Self {
-        let generator = move |s: Duration, p: Duration| -> TimeSpecGridGenerator {
-            TimeSpecGridGenerator {
-                min_primary_width,
-                secondary_duration: s,
-                primary_duration: p,
-            }
-        };
-
+        let format = RulerFormat::default();
         Self {
-            generators: vec![
-                generator(Duration::Millis(1), Duration::Millis(1)),
-                generator(Duration::Millis(1), Duration::Millis(2)),
-                generator(Duration::Millis(1), Duration::Millis(5)),
-                //
-                generator(Duration::Millis(1), Duration::Millis(10)),
-                generator(Duration::Millis(2), Duration::Millis(20)),
-                generator(Duration::Millis(5), Duration::Millis(50)),
-                //
-                generator(Duration::Millis(10), Duration::Millis(100)),
-                generator(Duration::Millis(20), Duration::Millis(200)),
-                generator(Duration::Millis(50), Duration::Millis(500)),
-                //
-                generator(Duration::Millis(100), Duration::Seconds(1)),
-                generator(Duration::Millis(200), Duration::Seconds(2)),
-                generator(Duration::Millis(500), Duration::Seconds(5)),
-                generator(Duration::Seconds(1), Duration::Seconds(10)),
-                generator(Duration::Seconds(2), Duration::Seconds(20)),
-                generator(Duration::Seconds(3), Duration::Seconds(30)),
-                //
-                generator(Duration::Seconds(10), Duration::Minutes(1)),
-                generator(Duration::Seconds(20), Duration::Minutes(2)),
-                generator(Duration::Seconds(30), Duration::Minutes(3)),
-                //
-                generator(Duration::Minutes(1), Duration::Minutes(10)),
-                generator(Duration::Minutes(2), Duration::Minutes(20)),
-                generator(Duration::Minutes(3), Duration::Minutes(30)),
-                //
-                generator(Duration::Minutes(10), Duration::Hours(1)),
-                generator(Duration::Minutes(20), Duration::Hours(2)),
-                generator(Duration::Minutes(30), Duration::Hours(3)),
-                //
-                generator(Duration::Hours(1), Duration::Hours(10)),
-                generator(Duration::Hours(2), Duration::Hours(20)),
-                generator(Duration::Hours(3), Duration::Hours(30)),
-            ],
+            format,
+            steps: format.steps(),
+            min_primary_width,
         }
     }
 
-    pub fn generate<V: GridViewportHandle>(&self, viewport: &V) -> Vec<GridTick> {
-        for generator in &self.generators {
-            if generator.supports(viewport) {
-                return generator.generate(viewport);
-            }
-        }
-        Vec::new()
+    pub fn format(&self) -> RulerFormat {
+        self.format
     }
-}
 
-#[derive(Clone)]
-struct TimeSpecGridGenerator {
-    primary_duration: Duration,
-    secondary_duration: Duration,
-    min_primary_width: Pixels,
-}
+    pub fn set_format(&mut self, format: RulerFormat) {
+        self.format = format;
+        self.steps = format.steps();
+    }
 
-impl TimeSpecGridGenerator {
-    fn supports<V: GridViewportHandle>(&self, viewport: &V) -> bool {
-        viewport.duration_to_track_offset(self.primary_duration) >= self.min_primary_width
+    /// Ticks of the first step of the format ladder wide enough for its labels.
+    pub fn generate<V: GridViewportHandle>(&self, viewport: &V) -> Vec<GridTick> {
+        let unit_frames = self.format.unit_frames(viewport.sample_rate());
+        let unit_px = unit_frames / viewport.frames_per_px();
+        let step = self
+            .steps
+            .iter()
+            .find(|step| px((step.primary as f64 * unit_px) as f32) >= self.min_primary_width);
+        match step {
+            Some(step) => self.generate_step(viewport, *step, unit_frames),
+            None => Vec::new(),
+        }
     }
 
-    fn generate<V: GridViewportHandle>(&self, viewport: &V) -> Vec<GridTick> {
+    fn generate_step<V: GridViewportHandle>(&self, viewport: &V, step: RulerStep, unit_frames: f64) -> Vec<GridTick> {
         let mut ticks: Vec<GridTick> = Vec::new();
 
-        // padding produces time truncation, so it's required to additional conversion
-        let start_time = viewport.scroll_offset_to_time(px(0.0));
-        let start_px = viewport.time_to_scroll_offset(start_time);
-        let end_px = start_px + viewport.viewport_size().width;
+        let offset = |unit: u64| viewport.frame_to_scroll_offset((unit as f64 * unit_frames).round() as usize);
+        let end_px = viewport.viewport_size().width;
 
-        let mut primary = start_time.truncate(self.primary_duration);
+        let start_unit = (viewport.scroll_offset_to_frame(px(0.0)) as f64 / unit_frames) as u64;
+        let mut primary = start_unit - start_unit % step.primary;
         loop {
-            let offset_x = viewport.time_to_scroll_offset(primary);
+            let offset_x = offset(primary);
             if offset_x >= end_px {
                 break;
             }
-            let label = Some(primary.to_string().into());
             ticks.push(GridTick {
                 tick_type: GridTickType::PRIMARY,
                 offset_x,
-                label,
+                label: Some(self.format.label(primary).into()),
             });
 
-            let next_primary = primary + self.primary_duration;
-            let mut secondary = primary + self.secondary_duration;
+            let next_primary = primary + step.primary;
+            let mut secondary = primary + step.secondary;
             while secondary < next_primary {
-                let offset_x = viewport.time_to_scroll_offset(secondary);
+                let offset_x = offset(secondary);
                 if offset_x >= end_px {
                     break;
                 }
@@ -113,7 +73,7 @@ impl TimeSpecGridGenerator {
                     offset_x,
                     label: None,
                 });
-                secondary += self.secondary_duration;
+                secondary += step.secondary;
             }
             primary = next_primary;
         }
@@ -121,3 +81,42 @@ impl TimeSpecGridGenerator {
         ticks
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::grid::GridViewport;
+    use crate::components::tick::SmpteRate;
+    use crate::time::SampleRate;
+
+    fn labels(viewport: &GridViewport) -> Vec<String> {
+        viewport
+            .ticks()
+            .into_iter()
+            .filter_map(|tick| tick.label.map(|label| label.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_ticks_follow_the_format() {
+        // 10 seconds over 1000 px
+        let viewport = GridViewport::new(SampleRate::Hz48000);
+        viewport.set_viewport_padding(px(0.0));
+        viewport.set_viewport_width(px(1000.0));
+        viewport.set_total_frames(480_000);
+
+        assert_eq!(labels(&viewport)[..3], ["0:00", "0:01", "0:02"]);
+
+        viewport.set_ruler_format(RulerFormat::Samples);
+        assert_eq!(labels(&viewport)[..3], ["0", "50000", "100000"]);
+
+        viewport.set_ruler_format(RulerFormat::Smpte(SmpteRate::Fps25));
+        assert_eq!(labels(&viewport)[..3], ["00:00:00:00", "00:00:01:00", "00:00:02:00"]);
+
+        viewport.set_ruler_format(RulerFormat::BarsBeats {
+            bpm: 120.0,
+            beats_per_bar: 4,
+        });
+        assert_eq!(labels(&viewport)[..3], ["1.1", "2.1", "3.1"]);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::components::grid::{GridViewport, GridViewportHandle, TimelineMarker};
+use gpui::{
+    App, Div, IntoElement, ParentElement, RenderOnce, StyleRefinement, Styled, Window, div, px, rems, rgb, rgba,
+};
+use gpui_component::StyledExt;
+
+/// Ruler lane with the markers as flags, named ranges as bands and the loop range along the top.
+#[derive(IntoElement)]
+pub struct GridMarkerView {
+    markers: Vec<TimelineMarker>,
+    loop_range: Option<(usize, usize)>,
+    viewport: GridViewport,
+    style: StyleRefinement,
+}
+
+impl GridMarkerView {
+    pub fn new(markers: Vec<TimelineMarker>, loop_range: Option<(usize, usize)>, viewport: &GridViewport) -> Self {
+        Self {
+            markers,
+            loop_range,
+            viewport: viewport.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+}
+
+impl Styled for GridMarkerView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+fn marker_label(marker: &TimelineMarker) -> Div {
+    div()
+        .text_size(rems(0.75))
+        .line_height(rems(1.0))
+        .text_color(rgb(0xF5F5F7))
+        .pl_1()
+        .whitespace_nowrap()
+        .child(marker.name.clone())
+}
+
+impl RenderOnce for GridMarkerView {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let width = self.viewport.viewport_size().width;
+        let offset = |frame: usize| self.viewport.frame_to_scroll_offset(frame);
+
+        let loop_band = self.loop_range.map(|(start, end)| {
+            div()
+                .absolute()
+                .top_0()
+                .left(offset(start))
+                .w(offset(end) - offset(start))
+                .h(px(3.0))
+                .bg(rgb(0x46A758))
+        });
+
+        let markers: Vec<Div> = self
+            .markers
+            .iter()
+            .filter(|marker| {
+                let end = marker.end_frame.unwrap_or(marker.frame);
+                offset(end) >= px(0.0) && offset(marker.frame) <= width
+            })
+            .map(|marker| {
+                let left = offset(marker.frame);
+                let element = div()
+                    .absolute()
+                    .top(px(1.0))
+                    .left(left)
+                    .border_l_1()
+                    .child(marker_label(marker));
+                match marker.end_frame {
+                    Some(end) => element
+                        .w(offset(end) - left)
+                        .overflow_hidden()
+                        .bg(rgba(0x6E8FB540))
+                        .border_r_1()
+                        .border_color(rgb(0x6E8FB5)),
+                    None => element.border_color(rgb(0xE2B93B)),
+                }
+            })
+            .collect();
+
+        div()
+            .bg(rgb(0x333333))
+            .border_b(px(1.0))
+            .border_color(rgb(0x1F1F1F))
+            .w_full()
+            .h(px(18.0))
+            .overflow_hidden()
+            .refine_style(&self.style)
+            .child(div().size_full().relative().children(markers).children(loop_band))
+    }
+}
@@ -1,13 +1,17 @@
 use gpui::{Pixels, SharedString};
 
+mod format;
 mod generator;
 mod label_view;
+mod marker_view;
 mod view;
 
+pub use format::*;
 #[allow(unused_imports)]
 pub use generator::*;
 #[allow(unused_imports)]
 pub use label_view::*;
+pub use marker_view::*;
 #[allow(unused_imports)]
 pub use view::*;
 
@@ -0,0 +1,122 @@
+use gpui::{
+    App, Bounds, DispatchPhase, Hsla, IntoElement, MouseButton, MouseDownEvent, MouseMoveEvent, ParentElement, Pixels,
+    RenderOnce, SharedString, StyleRefinement, Styled, Window, canvas, div, fill, point, px, rems, rgb, size,
+};
+use gpui_component::StyledExt;
+use std::rc::Rc;
+
+type ChangeHandler = Rc<dyn Fn(f32, &mut Window, &mut App)>;
+
+/// Horizontal bar for a `0..=1` value, click or drag to set it and double click to reset.
+#[derive(IntoElement)]
+pub struct TrackFaderView {
+    value: f32,
+    default_value: f32,
+    // fill from the middle, for balance
+    centered: bool,
+    label: SharedString,
+    on_change: Option<ChangeHandler>,
+    style: StyleRefinement,
+    color: Hsla,
+}
+
+impl Styled for TrackFaderView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl TrackFaderView {
+    pub fn new(value: f32, default_value: f32, label: impl Into<SharedString>) -> Self {
+        Self {
+            value: value.clamp(0.0, 1.0),
+            default_value,
+            centered: false,
+            label: label.into(),
+            on_change: None,
+            style: StyleRefinement::default(),
+            color: rgb(0x6E8FB5).into(),
+        }
+    }
+
+    pub fn centered(mut self) -> Self {
+        self.centered = true;
+        self
+    }
+
+    pub fn on_change(mut self, handler: impl Fn(f32, &mut Window, &mut App) + 'static) -> Self {
+        self.on_change = Some(Rc::new(handler));
+        self
+    }
+}
+
+fn bar_value(bounds: Bounds<Pixels>, position_x: Pixels) -> f32 {
+    ((position_x - bounds.origin.x) / bounds.size.width).clamp(0.0, 1.0)
+}
+
+impl RenderOnce for TrackFaderView {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let (value, default_value, centered, color) = (self.value, self.default_value, self.centered, self.color);
+        let on_change = self.on_change;
+        let bar = canvas(
+            |_, _, _| {},
+            move |bounds, _, window, _| {
+                let (from, to) = match centered {
+                    true => (value.min(0.5), value.max(0.5)),
+                    false => (0.0, value),
+                };
+                let left = bounds.origin.x + bounds.size.width * from;
+                let width = (bounds.size.width * (to - from)).max(px(1.0));
+                window.paint_quad(fill(
+                    Bounds::new(point(left, bounds.origin.y), size(width, bounds.size.height)),
+                    color,
+                ));
+
+                let Some(on_change) = on_change.clone() else {
+                    return;
+                };
+                let on_press = on_change.clone();
+                window.on_mouse_event(move |event: &MouseDownEvent, phase, window, cx| {
+                    if phase == DispatchPhase::Bubble
+                        && event.button == MouseButton::Left
+                        && bounds.contains(&event.position)
+                    {
+                        match event.click_count {
+                            1 => on_press(bar_value(bounds, event.position.x), window, cx),
+                            _ => on_press(default_value, window, cx),
+                        }
+                    }
+                });
+                window.on_mouse_event(move |event: &MouseMoveEvent, phase, window, cx| {
+                    if phase == DispatchPhase::Bubble
+                        && event.pressed_button == Some(MouseButton::Left)
+                        && bounds.contains(&event.position)
+                    {
+                        on_change(bar_value(bounds, event.position.x), window, cx);
+                    }
+                });
+            },
+        )
+        .absolute()
+        .size_full();
+
+        div()
+            .relative()
+            .h(px(16.0))
+            .rounded_sm()
+            .overflow_hidden()
+            .bg(rgb(0x3E3E3E))
+            .refine_style(&self.style)
+            .child(bar)
+            .child(
+                div()
+                    .relative()
+                    .size_full()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .text_size(rems(0.625))
+                    .child(self.label),
+            )
+    }
+}
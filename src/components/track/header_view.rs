@@ -1,10 +1,15 @@
-use crate::components::track::Track;
-use gpui::{App, IntoElement, ParentElement, RenderOnce, StyleRefinement, Styled, Window, div, px, rems, rgb};
-use gpui_component::{StyledExt, gray_400};
+use crate::components::track::{Track, TrackFaderView, TrackMeterView, TrackMixer};
+use gpui::{
+    AnyElement, App, IntoElement, ParentElement, RenderOnce, StyleRefinement, Styled, Window, div, px, rems, rgb,
+};
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::{Sizable, StyledExt};
 
 #[derive(IntoElement)]
 pub struct TrackHeaderView {
     track: Track,
+    meter_frame: Option<usize>,
+    title: Option<AnyElement>,
     style: StyleRefinement,
 }
 
@@ -12,9 +17,42 @@ impl TrackHeaderView {
     pub fn new(track: &Track) -> Self {
         Self {
             track: track.clone(),
+            meter_frame: None,
+            title: None,
             style: StyleRefinement::default(),
         }
     }
+
+    /// Shows the track level at the meter `frame` of the transport while playing.
+    pub fn meter_frame(mut self, frame: Option<usize>) -> Self {
+        self.meter_frame = frame;
+        self
+    }
+
+    /// Replaces the title text, e.g. with a drag handle or an input while renaming.
+    pub fn title(mut self, title: impl IntoElement) -> Self {
+        self.title = Some(title.into_any_element());
+        self
+    }
+
+    fn toggle_button(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        on: bool,
+        variant: ButtonVariant,
+        toggle: fn(&mut TrackMixer),
+    ) -> Button {
+        let track = self.track.clone();
+        Button::new(id)
+            .label(label)
+            .xsmall()
+            .with_variant(if on { variant } else { ButtonVariant::Ghost })
+            .on_click(move |_, window, _| {
+                track.update_mixer(toggle);
+                window.refresh();
+            })
+    }
 }
 
 impl Styled for TrackHeaderView {
@@ -24,8 +62,35 @@ impl Styled for TrackHeaderView {
 }
 
 impl RenderOnce for TrackHeaderView {
-    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
-        let title = self.track.title();
+    fn render(mut self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let color = self.track.color().map(rgb).unwrap_or(rgb(0x8A8A8A));
+        let title = self
+            .title
+            .take()
+            .unwrap_or_else(|| self.track.title().into_any_element());
+        let mixer = self.track.mixer();
+        let peaks = match self.meter_frame {
+            Some(frame) => self.track.meter().peaks_at(frame),
+            None => [0.0; 2],
+        };
+        let default_fader = TrackMixer::default().fader();
+
+        let (gain_track, pan_track) = (self.track.clone(), self.track.clone());
+        let gain = TrackFaderView::new(mixer.fader(), default_fader, mixer.gain_label())
+            .on_change(move |value, window, _| {
+                gain_track.update_mixer(|mixer| mixer.set_fader(value));
+                window.refresh();
+            })
+            .flex_1();
+        let pan = TrackFaderView::new((mixer.pan + 1.0) / 2.0, 0.5, mixer.pan_label())
+            .centered()
+            .on_change(move |value, window, _| {
+                // snap to centre within a percent
+                let pan = ((value * 2.0 - 1.0) * 100.0).round() / 100.0;
+                pan_track.update_mixer(|mixer| mixer.pan = pan);
+                window.refresh();
+            })
+            .w(px(44.0));
 
         div()
             .border_r(px(1.0))
@@ -33,15 +98,41 @@ impl RenderOnce for TrackHeaderView {
             .border_color(rgb(0x454545))
             .px_3()
             .py_1()
+            .flex()
+            .flex_col()
+            .gap_1()
             .bg(rgb(0x575757))
             .refine_style(&self.style)
-            .child(div().text_size(rems(0.875)).line_height(rems(1.25)).child(title))
             .child(
                 div()
-                    .text_size(rems(0.75))
-                    .line_height(rems(1.0))
-                    .text_color(gray_400())
-                    .child("0 LUFS / 0 dBP"),
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .text_size(rems(0.875))
+                    .line_height(rems(1.25))
+                    .child(div().flex_none().size_2().rounded_full().bg(color))
+                    .child(div().flex_1().overflow_hidden().child(title)),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_1()
+                    .child(
+                        self.toggle_button("track-mute", "M", mixer.muted, ButtonVariant::Danger, |mixer| {
+                            mixer.muted = !mixer.muted
+                        }),
+                    )
+                    .child(
+                        self.toggle_button("track-solo", "S", mixer.soloed, ButtonVariant::Primary, |mixer| {
+                            mixer.soloed = !mixer.soloed
+                        }),
+                    )
+                    .child(gain)
+                    .child(pan),
             )
+            .child(TrackMeterView::new(peaks))
     }
 }
@@ -0,0 +1,50 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+struct MeterBlock {
+    start_frame: usize,
+    peaks: [f32; 2],
+}
+
+/// Peak levels of a track, filled ahead by the mixer and read back as the device plays them.
+#[derive(Clone, Default)]
+pub struct TrackMeter {
+    blocks: Arc<Mutex<VecDeque<MeterBlock>>>,
+}
+
+impl TrackMeter {
+    pub fn push(&self, start_frame: usize, peaks: [f32; 2]) {
+        self.blocks.lock().unwrap().push_back(MeterBlock { start_frame, peaks });
+    }
+
+    pub fn clear(&self) {
+        self.blocks.lock().unwrap().clear();
+    }
+
+    /// Peaks of the block under `frame`, blocks before it are dropped.
+    pub fn peaks_at(&self, frame: usize) -> [f32; 2] {
+        let mut blocks = self.blocks.lock().unwrap();
+        while blocks.len() > 1 && blocks[1].start_frame <= frame {
+            blocks.pop_front();
+        }
+        match blocks.front() {
+            Some(block) if block.start_frame <= frame => block.peaks,
+            _ => [0.0; 2],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_peaks_at_follows_playhead() {
+        let meter = TrackMeter::default();
+        meter.push(100, [0.5, 0.25]);
+        meter.push(200, [1.0, 0.75]);
+        assert_eq!(meter.peaks_at(50), [0.0, 0.0]);
+        assert_eq!(meter.peaks_at(150), [0.5, 0.25]);
+        assert_eq!(meter.peaks_at(250), [1.0, 0.75]);
+    }
+}
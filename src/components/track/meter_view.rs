@@ -0,0 +1,63 @@
+use gpui::{
+    App, Bounds, IntoElement, ParentElement, RenderOnce, StyleRefinement, Styled, Window, canvas, div, fill, point, px,
+    rgb, size,
+};
+use gpui_component::StyledExt;
+
+// bottom of the meter scale
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Left and right peak bars on a dBFS scale, red when a channel reaches full scale.
+#[derive(IntoElement)]
+pub struct TrackMeterView {
+    peaks: [f32; 2],
+    style: StyleRefinement,
+}
+
+impl Styled for TrackMeterView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl TrackMeterView {
+    pub fn new(peaks: [f32; 2]) -> Self {
+        Self {
+            peaks,
+            style: StyleRefinement::default(),
+        }
+    }
+}
+
+fn meter_fraction(peak: f32) -> f32 {
+    let db = 20.0 * peak.max(f32::MIN_POSITIVE).log10();
+    ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+impl RenderOnce for TrackMeterView {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let peaks = self.peaks;
+        let bars = canvas(
+            |_, _, _| {},
+            move |bounds, _, window, _| {
+                let bar_height = (bounds.size.height - px(1.0)) / 2.0;
+                for (index, peak) in peaks.into_iter().enumerate() {
+                    let color = match peak {
+                        p if p >= 1.0 => rgb(0xE5484D),
+                        p if p >= 0.5 => rgb(0xE2B93B),
+                        _ => rgb(0x46A758),
+                    };
+                    let top = bounds.origin.y + (bar_height + px(1.0)) * index as f32;
+                    let width = bounds.size.width * meter_fraction(peak);
+                    window.paint_quad(fill(
+                        Bounds::new(point(bounds.origin.x, top), size(width, bar_height)),
+                        color,
+                    ));
+                }
+            },
+        )
+        .size_full();
+
+        div().h(px(7.0)).bg(rgb(0x3E3E3E)).refine_style(&self.style).child(bars)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::components::waveform::db_to_gain;
+
+/// Channel strip of a track: mute, solo, fader and balance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrackMixer {
+    pub muted: bool,
+    pub soloed: bool,
+    pub gain_db: f32,
+    // -1 is hard left, 1 hard right
+    pub pan: f32,
+}
+
+impl Default for TrackMixer {
+    fn default() -> Self {
+        Self {
+            muted: false,
+            soloed: false,
+            gain_db: 0.0,
+            pan: 0.0,
+        }
+    }
+}
+
+impl TrackMixer {
+    pub const MIN_DB: f32 = -48.0;
+    pub const MAX_DB: f32 = 12.0;
+
+    /// Muted tracks are silent, with any track soloed only the soloed ones play.
+    pub fn is_audible(&self, any_soloed: bool) -> bool {
+        !self.muted && (self.soloed || !any_soloed)
+    }
+
+    /// Left and right gains, balance attenuates the opposite side and keeps the centre at unity.
+    pub fn channel_gains(&self) -> [f32; 2] {
+        let gain = db_to_gain(self.gain_db);
+        let pan = self.pan.clamp(-1.0, 1.0);
+        [gain * (1.0 - pan.max(0.0)), gain * (1.0 + pan.min(0.0))]
+    }
+
+    /// Fader position in `0..=1` over the dB range.
+    pub fn fader(&self) -> f32 {
+        (self.gain_db - Self::MIN_DB) / (Self::MAX_DB - Self::MIN_DB)
+    }
+
+    pub fn set_fader(&mut self, fader: f32) {
+        let gain_db = Self::MIN_DB + fader.clamp(0.0, 1.0) * (Self::MAX_DB - Self::MIN_DB);
+        // round to the 0.1 dB the header shows
+        self.gain_db = (gain_db * 10.0).round() / 10.0;
+    }
+
+    pub fn gain_label(&self) -> String {
+        format!("{:+.1} dB", self.gain_db)
+    }
+
+    pub fn pan_label(&self) -> String {
+        let percent = (self.pan * 100.0).round() as i32;
+        match percent {
+            0 => "C".to_string(),
+            p if p < 0 => format!("L{}", -p),
+            p => format!("R{p}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_audible_with_solo() {
+        let plain = TrackMixer::default();
+        let soloed = TrackMixer {
+            soloed: true,
+            ..TrackMixer::default()
+        };
+        let muted = TrackMixer {
+            muted: true,
+            soloed: true,
+            ..TrackMixer::default()
+        };
+        assert!(plain.is_audible(false));
+        assert!(!plain.is_audible(true));
+        assert!(soloed.is_audible(true));
+        assert!(!muted.is_audible(true));
+    }
+
+    #[test]
+    fn test_channel_gains_balance() {
+        let mut mixer = TrackMixer::default();
+        assert_eq!(mixer.channel_gains(), [1.0, 1.0]);
+        mixer.pan = 0.5;
+        assert_eq!(mixer.channel_gains(), [0.5, 1.0]);
+        mixer.pan = -1.0;
+        assert_eq!(mixer.channel_gains(), [1.0, 0.0]);
+        assert_eq!(mixer.pan_label(), "L100");
+    }
+
+    #[test]
+    fn test_fader_round_trip() {
+        let mut mixer = TrackMixer::default();
+        assert_eq!(mixer.fader(), 0.8);
+        mixer.set_fader(0.0);
+        assert_eq!(mixer.gain_db, TrackMixer::MIN_DB);
+        mixer.set_fader(0.8);
+        assert_eq!(mixer.gain_db, 0.0);
+    }
+}
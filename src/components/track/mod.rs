@@ -1,9 +1,17 @@
+mod fader_view;
 mod header_view;
+mod meter;
+mod meter_view;
+mod mixer;
 mod track;
 mod track_view;
 
+pub use fader_view::*;
 #[allow(unused_imports)]
 pub use header_view::*;
+pub use meter::*;
+pub use meter_view::*;
+pub use mixer::*;
 #[allow(unused_imports)]
 pub use track::*;
 #[allow(unused_imports)]
@@ -1,31 +1,164 @@
-use crate::components::region::TrackRegion;
-use crate::components::waveform::WaveClip;
-use gpui::SharedString;
+use crate::components::region::{RegionId, TrackRegion};
+use crate::components::track::{TrackMeter, TrackMixer};
+use crate::components::waveform::{SpectrogramSettings, WaveClip, WaveFormAmplitude};
+use gpui::{Pixels, SharedString};
 use std::cmp::Ordering;
 use std::sync::{Arc, Mutex};
 
+/// Colours offered for tracks, the regions of a track without one use the theme colour.
+pub const TRACK_COLORS: [(&str, u32); 8] = [
+    ("Red", 0xE5484D),
+    ("Orange", 0xF76B15),
+    ("Yellow", 0xE2B93B),
+    ("Green", 0x46A758),
+    ("Teal", 0x12A594),
+    ("Blue", 0x3E63DD),
+    ("Purple", 0x8E4EC6),
+    ("Pink", 0xD6409F),
+];
+
+/// How the regions of a track draw their audio.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TrackDisplay {
+    #[default]
+    Waveform,
+    Spectrogram,
+}
+
+/// Title, colour and row height of a track, the part of its look kept in the edit history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackHeading {
+    pub title: SharedString,
+    pub color: Option<u32>,
+    pub height: Option<Pixels>,
+}
+
+#[derive(Clone, Default)]
+struct TrackLook {
+    title: SharedString,
+    color: Option<u32>,
+    height: Option<Pixels>,
+    display: TrackDisplay,
+    spectrogram: SpectrogramSettings,
+    amplitude: WaveFormAmplitude,
+}
+
 #[derive(Clone)]
 pub struct Track {
-    title: SharedString,
+    look: Arc<Mutex<TrackLook>>,
     regions: Arc<Mutex<Vec<TrackRegion>>>,
+    mixer: Arc<Mutex<TrackMixer>>,
+    meter: TrackMeter,
 }
 
 impl Track {
     pub fn new<T: Into<SharedString>>(title: T) -> Self {
         Self {
-            title: title.into(),
+            look: Arc::new(Mutex::new(TrackLook {
+                title: title.into(),
+                ..TrackLook::default()
+            })),
             regions: Arc::new(Mutex::new(Vec::new())),
+            mixer: Arc::new(Mutex::new(TrackMixer::default())),
+            meter: TrackMeter::default(),
         }
     }
 
     pub fn title(&self) -> SharedString {
-        self.title.clone()
+        self.look.lock().unwrap().title.clone()
+    }
+
+    /// Title, colour and height changes are recorded by the grid edits that make them.
+    pub fn set_title(&self, title: impl Into<SharedString>) {
+        self.look.lock().unwrap().title = title.into();
+    }
+
+    /// Rgb colour of the track, `None` for the theme colour.
+    pub fn color(&self) -> Option<u32> {
+        self.look.lock().unwrap().color
+    }
+
+    pub fn set_color(&self, color: Option<u32>) {
+        self.look.lock().unwrap().color = color;
+    }
+
+    /// Row height of the track, `None` follows the default height of the timeline.
+    pub fn height(&self) -> Option<Pixels> {
+        self.look.lock().unwrap().height
+    }
+
+    pub fn set_height(&self, height: Option<Pixels>) {
+        self.look.lock().unwrap().height = height;
+    }
+
+    pub fn heading(&self) -> TrackHeading {
+        let look = self.look.lock().unwrap();
+        TrackHeading {
+            title: look.title.clone(),
+            color: look.color,
+            height: look.height,
+        }
+    }
+
+    pub fn set_heading(&self, heading: TrackHeading) {
+        let mut look = self.look.lock().unwrap();
+        look.title = heading.title;
+        look.color = heading.color;
+        look.height = heading.height;
+    }
+
+    pub fn display(&self) -> TrackDisplay {
+        self.look.lock().unwrap().display
+    }
+
+    pub fn set_display(&self, display: TrackDisplay) {
+        self.look.lock().unwrap().display = display;
+    }
+
+    /// Spectrogram settings, kept while the track shows its waveform.
+    pub fn spectrogram(&self) -> SpectrogramSettings {
+        self.look.lock().unwrap().spectrogram
+    }
+
+    pub fn set_spectrogram(&self, settings: SpectrogramSettings) {
+        self.look.lock().unwrap().spectrogram = settings;
+    }
+
+    /// Vertical zoom and amplitude scale of the waveforms.
+    pub fn amplitude(&self) -> WaveFormAmplitude {
+        self.look.lock().unwrap().amplitude
+    }
+
+    pub fn set_amplitude(&self, amplitude: WaveFormAmplitude) {
+        self.look.lock().unwrap().amplitude = amplitude;
+    }
+
+    pub fn mixer(&self) -> TrackMixer {
+        *self.mixer.lock().unwrap()
+    }
+
+    /// Mixer changes apply to playback right away and are not part of the edit history.
+    pub fn update_mixer(&self, updater: impl FnOnce(&mut TrackMixer)) {
+        updater(&mut self.mixer.lock().unwrap());
+    }
+
+    pub fn meter(&self) -> &TrackMeter {
+        &self.meter
     }
 
     pub fn regions(&self) -> Vec<TrackRegion> {
         self.regions.lock().unwrap().clone()
     }
 
+    /// True when both handles share the same regions.
+    pub fn ptr_eq(&self, other: &Track) -> bool {
+        Arc::ptr_eq(&self.regions, &other.regions)
+    }
+
+    pub fn set_regions(&self, regions: Vec<TrackRegion>) {
+        *self.regions.lock().unwrap() = regions;
+    }
+
     pub fn add_clip(&self, clip: &WaveClip, track_offset: usize) {
         let region = TrackRegion::new(clip, 0, clip.frame_count(), track_offset);
         self.add_region(region);
@@ -36,7 +169,34 @@ impl Track {
         self.clean_regions();
     }
 
-    fn clean_regions(&self) {
+    pub fn region(&self, id: RegionId) -> Option<TrackRegion> {
+        let regions = self.regions.lock().unwrap();
+        regions.iter().find(|region| region.id() == id).cloned()
+    }
+
+    pub fn take_region(&self, id: RegionId) -> Option<TrackRegion> {
+        let mut regions = self.regions.lock().unwrap();
+        let index = regions.iter().position(|region| region.id() == id)?;
+        Some(regions.remove(index))
+    }
+
+    /// Adds a region without resolving overlaps, used while a drag is in progress.
+    pub fn insert_region(&self, region: TrackRegion) {
+        self.regions.lock().unwrap().push(region);
+    }
+
+    pub fn update_region(&self, id: RegionId, updater: impl FnOnce(&mut TrackRegion)) -> bool {
+        let mut regions = self.regions.lock().unwrap();
+        match regions.iter_mut().find(|region| region.id() == id) {
+            Some(region) => {
+                updater(region);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clean_regions(&self) {
         let mut regions = self.regions.lock().unwrap();
         regions.retain(|region| region.frames() > 0);
         regions.sort_by(|a, b| {
@@ -46,16 +206,36 @@ impl Track {
             }
             ordering
         });
-        let mut i = 0;
-        while i + 1 < regions.len() {
-            let curr_end_frame = regions[i].track_end_frame();
-            let next_start_frame = regions[i + 1].track_start_frame();
-            if curr_end_frame > next_start_frame {
-                regions[i].set_track_end_frame(next_start_frame);
+        // a later region ending inside an earlier one cuts the tail of the earlier one
+        for i in 0..regions.len() {
+            let end_frame = regions[i].track_end_frame();
+            let covered = regions[i + 1..]
+                .iter()
+                .take_while(|next| next.track_start_frame() < end_frame)
+                .find(|next| next.track_end_frame() <= end_frame)
+                .map(TrackRegion::track_start_frame);
+            if let Some(start_frame) = covered {
+                regions[i].set_track_end_frame(start_frame);
             }
-            i += 1;
         }
         regions.retain(|region| region.frames() > 0);
+
+        // the remaining overlaps are partial, each pass crossfades across them and drops stale crossfades
+        let mut crossfades = vec![(0, 0); regions.len()];
+        for i in 0..regions.len() {
+            let end_frame = regions[i].track_end_frame();
+            for j in i + 1..regions.len() {
+                let start_frame = regions[j].track_start_frame();
+                if start_frame >= end_frame {
+                    break;
+                }
+                crossfades[i].1 = crossfades[i].1.max(end_frame - start_frame);
+                crossfades[j].0 = crossfades[j].0.max(end_frame - start_frame);
+            }
+        }
+        for (region, (in_frames, out_frames)) in regions.iter_mut().zip(crossfades) {
+            region.set_crossfades(in_frames, out_frames);
+        }
     }
 
     pub fn frames(&self) -> usize {
@@ -66,3 +246,81 @@ impl Track {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::region::{Fade, FadeCurve};
+    use crate::components::waveform::test_clip;
+
+    #[test]
+    fn test_clean_regions_crossfades_overlap() {
+        let clip = test_clip("crossfade_overlap", 1000);
+        let track = Track::new("a");
+        track.add_clip(&clip, 0);
+        track.add_clip(&clip, 800);
+
+        let regions = track.regions();
+        assert_eq!(regions[0].track_end_frame(), 1000);
+        assert_eq!(regions[0].crossfade_out().frames, 200);
+        assert_eq!(regions[1].crossfade_in().frames, 200);
+        assert_eq!(regions[1].crossfade_in().curve, FadeCurve::EqualPower);
+        assert_eq!(regions[0].fade_out(), Fade::NONE);
+        assert_eq!(regions[1].fade_in(), Fade::NONE);
+    }
+
+    #[test]
+    fn test_clean_regions_keeps_user_fades() {
+        let clip = test_clip("crossfade_user_fades", 1000);
+        let fade = Fade {
+            frames: 50,
+            curve: FadeCurve::Linear,
+        };
+        let mut region = TrackRegion::new(&clip, 0, 1000, 800);
+        region.set_fade_in(fade);
+        let id = region.id();
+        let track = Track::new("a");
+        track.add_clip(&clip, 0);
+        track.add_region(region);
+        assert_eq!(track.region(id).unwrap().fade_in(), fade);
+        assert_eq!(track.region(id).unwrap().crossfade_in().frames, 200);
+
+        // moved apart the crossfade goes away, the user fade stays
+        track.update_region(id, |region| region.set_track_start_frame(2000));
+        track.clean_regions();
+        assert_eq!(track.region(id).unwrap().fade_in(), fade);
+        assert_eq!(track.region(id).unwrap().crossfade_in(), Fade::NONE);
+        assert_eq!(track.regions()[0].crossfade_out(), Fade::NONE);
+    }
+
+    #[test]
+    fn test_clean_regions_checks_non_adjacent_regions() {
+        let clip = test_clip("non_adjacent", 1000);
+        let short = test_clip("non_adjacent_short", 100);
+        let track = Track::new("a");
+        track.add_clip(&clip, 0);
+        track.add_clip(&clip, 500);
+        track.add_clip(&short, 600);
+
+        // the short region ends inside both earlier ones and cuts them
+        let regions = track.regions();
+        assert_eq!(regions[0].track_end_frame(), 600);
+        assert_eq!(regions[1].track_end_frame(), 600);
+        assert_eq!(regions[0].crossfade_out().frames, 100);
+        assert_eq!(regions[1].crossfade_in().frames, 100);
+        assert_eq!(regions[2].crossfade_in(), Fade::NONE);
+    }
+
+    #[test]
+    fn test_clean_regions_truncates_covered_tail() {
+        let clip = test_clip("covered_tail", 1000);
+        let short = test_clip("covered_tail_short", 100);
+        let track = Track::new("a");
+        track.add_clip(&clip, 0);
+        track.add_clip(&short, 300);
+
+        let regions = track.regions();
+        assert_eq!(regions[0].track_end_frame(), 300);
+        assert_eq!(regions[0].crossfade_out(), Fade::NONE);
+    }
+}
@@ -1,23 +1,25 @@
-use crate::components::grid::{GridViewport, GridViewportHandle};
+use crate::components::grid::{GridSelection, GridViewport, GridViewportHandle};
 use crate::components::region::TrackRegionView;
-use crate::components::track::Track;
+use crate::components::track::{Track, TrackDisplay};
 use gpui::{
     AnyElement, App, AvailableSpace, Bounds, ContentMask, Element, ElementId, GlobalElementId, InspectorElementId,
-    IntoElement, LayoutId, Pixels, Refineable, Style, StyleRefinement, Styled, Window, point, px, size,
+    IntoElement, LayoutId, Pixels, Refineable, Style, StyleRefinement, Styled, Window, point, px, rgb, size,
 };
 use std::collections::VecDeque;
 use std::panic::Location;
 
 pub struct TrackView {
     viewport: GridViewport,
+    selection: GridSelection,
     track: Track,
     style: StyleRefinement,
 }
 
 impl TrackView {
-    pub fn new(viewport: &GridViewport, track: &Track) -> Self {
+    pub fn new(viewport: &GridViewport, selection: &GridSelection, track: &Track) -> Self {
         Self {
             viewport: viewport.clone(),
+            selection: selection.clone(),
             track: track.clone(),
             style: StyleRefinement::default(),
         }
@@ -126,6 +128,9 @@ impl TrackView {
         let top_px = px(4.0);
         let height_px = bounds.size.height - (top_px * 2);
 
+        let color = self.track.color().map(|color| rgb(color).into());
+        let spectrogram = (self.track.display() == TrackDisplay::Spectrogram).then(|| self.track.spectrogram());
+        let amplitude = self.track.amplitude();
         let mut layouts = VecDeque::new();
         for region in self.track.regions() {
             let left_px = self.viewport.frame_to_scroll_offset(region.track_start_frame());
@@ -159,6 +164,10 @@ impl TrackView {
             let mut element = TrackRegionView::new(&region, start_frame, end_frame + 1, self.viewport.frames_per_px())
                 .w(region_bounds.size.width)
                 .h(region_bounds.size.height)
+                .selected(self.selection.is_selected(region.id()))
+                .color(color)
+                .spectrogram(spectrogram)
+                .amplitude(amplitude)
                 .py(px(8.0))
                 .into_any_element();
 
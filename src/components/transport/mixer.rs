@@ -0,0 +1,99 @@
+use crate::audio::{AudioResult, PLAYBACK_CHANNELS};
+use crate::components::track::Track;
+
+/// Interleaved stereo mix and the post-fader peak of every track.
+pub struct MixBlock {
+    pub samples: Vec<f32>,
+    pub track_peaks: Vec<[f32; PLAYBACK_CHANNELS]>,
+}
+
+/// Mix of every audible track over `start_frame..start_frame + frames`, regions play with their
+/// processing, tracks through their mixer, and mono clips play on both channels.
+pub fn mix_tracks(tracks: &[Track], start_frame: usize, frames: usize) -> AudioResult<MixBlock> {
+    let end_frame = start_frame + frames;
+    let any_soloed = tracks.iter().any(|track| track.mixer().soloed);
+    let mut samples = vec![0.0; frames * PLAYBACK_CHANNELS];
+    let mut track_peaks = Vec::with_capacity(tracks.len());
+    for track in tracks {
+        let mixer = track.mixer();
+        if !mixer.is_audible(any_soloed) {
+            track_peaks.push([0.0; PLAYBACK_CHANNELS]);
+            continue;
+        }
+        let channel_gains = mixer.channel_gains();
+        let mut peaks = [0.0f32; PLAYBACK_CHANNELS];
+        for region in track.regions() {
+            let from = region.track_start_frame().max(start_frame);
+            let to = region.track_end_frame().min(end_frame);
+            let clip_channels = region.clip().channel_count();
+            if from >= to || clip_channels == 0 {
+                continue;
+            }
+            let offset = from - start_frame;
+            for channel in 0..PLAYBACK_CHANNELS {
+                let region_samples = region.read_channel(
+                    channel.min(clip_channels - 1),
+                    from - region.track_start_frame(),
+                    to - region.track_start_frame(),
+                )?;
+                for (index, sample) in region_samples.into_iter().enumerate() {
+                    let sample = sample * channel_gains[channel];
+                    peaks[channel] = peaks[channel].max(sample.abs());
+                    samples[(offset + index) * PLAYBACK_CHANNELS + channel] += sample;
+                }
+            }
+        }
+        track_peaks.push(peaks);
+    }
+    Ok(MixBlock { samples, track_peaks })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::region::TrackRegion;
+    use crate::components::waveform::test_clip;
+
+    #[test]
+    fn test_mix_tracks_sums_overlapping_tracks() {
+        let clip = test_clip("mix_overlap", 100);
+        let tracks = vec![Track::new("a"), Track::new("b")];
+        tracks[0].add_clip(&clip, 0);
+        tracks[1].add_clip(&clip, 50);
+
+        let mix = mix_tracks(&tracks, 40, 20).unwrap().samples;
+        assert_eq!(mix.len(), 40);
+        assert_eq!(mix[0..2], [0.25, 0.25]);
+        assert_eq!(mix[20..22], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_mix_tracks_reads_from_clip_offsets() {
+        let clip = test_clip("mix_offsets", 100);
+        let tracks = vec![Track::new("a")];
+        tracks[0].add_region(TrackRegion::new(&clip, 30, 60, 10));
+
+        let mix = mix_tracks(&tracks, 0, 50).unwrap().samples;
+        let left: Vec<f32> = mix.iter().step_by(PLAYBACK_CHANNELS).copied().collect();
+        assert_eq!(left[9], 0.0);
+        assert_eq!(left[10], 0.25);
+        assert_eq!(left[39], 0.25);
+        assert_eq!(left[40], 0.0);
+    }
+
+    #[test]
+    fn test_mix_tracks_applies_solo_and_mixer() {
+        let clip = test_clip("mix_solo", 100);
+        let tracks = vec![Track::new("a"), Track::new("b"), Track::new("c")];
+        tracks.iter().for_each(|track| track.add_clip(&clip, 0));
+        tracks[0].update_mixer(|mixer| mixer.soloed = true);
+        tracks[1].update_mixer(|mixer| {
+            mixer.soloed = true;
+            mixer.pan = 1.0;
+        });
+
+        let mix = mix_tracks(&tracks, 0, 10).unwrap();
+        assert_eq!(mix.samples[0..2], [0.25, 0.5]);
+        assert_eq!(mix.track_peaks, vec![[0.25, 0.25], [0.0, 0.25], [0.0, 0.0]]);
+    }
+}
@@ -0,0 +1,9 @@
+mod bounce;
+mod mixer;
+mod transport;
+mod view;
+
+pub use bounce::*;
+pub use mixer::*;
+pub use transport::*;
+pub use view::*;
@@ -0,0 +1,268 @@
+use crate::audio::{AudioError, PLAYBACK_CHANNELS, PlaybackStream};
+use crate::components::track::Track;
+use crate::components::transport::mix_tracks;
+use anyhow::{Result, anyhow};
+use log::error;
+use objc2_core_audio::AudioObjectID;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// mixed ahead of the device so slow clip reads do not underrun
+const MIX_AHEAD_FRAMES: usize = 16 * 1024;
+const MIX_BLOCK_FRAMES: usize = 2048;
+const MIX_IDLE: Duration = Duration::from_millis(5);
+
+// state shared with the mixing thread and the device callback
+struct TransportShared {
+    tracks: Arc<Mutex<Vec<Track>>>,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    played_frames: Arc<AtomicUsize>,
+    // bumped under the queue lock to retire the running mixing thread
+    generation: AtomicUsize,
+    // read error that ended the mixing thread
+    failure: Mutex<Option<AudioError>>,
+}
+
+struct TransportInner {
+    sample_rate: f64,
+    device_id: Option<AudioObjectID>,
+    stream: Option<PlaybackStream>,
+    // where playback started or was located to, the device adds the frames it played
+    start_frame: usize,
+    loop_range: Option<(usize, usize)>,
+}
+
+/// Plays the tracks from the playhead to the selected output device.
+#[derive(Clone)]
+pub struct Transport {
+    shared: Arc<TransportShared>,
+    inner: Rc<RefCell<TransportInner>>,
+}
+
+impl Transport {
+    pub fn new(tracks: &Arc<Mutex<Vec<Track>>>, sample_rate: f64) -> Self {
+        Self {
+            shared: Arc::new(TransportShared {
+                tracks: tracks.clone(),
+                // sized for the mix ahead so extending never allocates under the lock
+                queue: Arc::new(Mutex::new(VecDeque::with_capacity(
+                    (MIX_AHEAD_FRAMES + MIX_BLOCK_FRAMES) * PLAYBACK_CHANNELS,
+                ))),
+                played_frames: Arc::new(AtomicUsize::new(0)),
+                generation: AtomicUsize::new(0),
+                failure: Mutex::new(None),
+            }),
+            inner: Rc::new(RefCell::new(TransportInner {
+                sample_rate,
+                device_id: None,
+                stream: None,
+                start_frame: 0,
+                loop_range: None,
+            })),
+        }
+    }
+
+    /// Switches the output device, playback stops when it changes.
+    pub fn set_device(&self, device_id: Option<AudioObjectID>) {
+        if self.inner.borrow().device_id != device_id {
+            self.stop();
+            self.inner.borrow_mut().device_id = device_id;
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.inner.borrow().sample_rate
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.inner.borrow().stream.is_some()
+    }
+
+    /// Playhead frame, advanced by what the device has actually played.
+    pub fn position(&self) -> usize {
+        let inner = self.inner.borrow();
+        match inner.stream.is_some() {
+            true => loop_position(
+                inner.start_frame,
+                self.shared.played_frames.load(Ordering::Relaxed),
+                inner.loop_range,
+            ),
+            false => inner.start_frame,
+        }
+    }
+
+    /// Frames the device played since playback started or was located, where the track meters are read.
+    pub fn meter_frame(&self) -> Option<usize> {
+        self.is_playing()
+            .then(|| self.shared.played_frames.load(Ordering::Relaxed))
+    }
+
+    /// Read error that stopped the mixing, playback should stop and report it.
+    pub fn take_failure(&self) -> Option<AudioError> {
+        self.shared.failure.lock().unwrap().take()
+    }
+
+    /// Playback starting before the end of the loop wraps around it.
+    pub fn set_loop_range(&self, loop_range: Option<(usize, usize)>) {
+        if self.inner.borrow().loop_range == loop_range {
+            return;
+        }
+        let position = self.position();
+        let mut inner = self.inner.borrow_mut();
+        inner.loop_range = loop_range;
+        inner.start_frame = position;
+        if inner.stream.is_some() {
+            self.restart_mixer(position, loop_range);
+        }
+    }
+
+    pub fn play(&self) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.stream.is_some() {
+            return Ok(());
+        }
+        let device_id = inner.device_id.ok_or(anyhow!("no output device selected"))?;
+        let mut stream = PlaybackStream::open(
+            device_id,
+            inner.sample_rate,
+            self.shared.queue.clone(),
+            self.shared.played_frames.clone(),
+        )?;
+        self.restart_mixer(inner.start_frame, inner.loop_range);
+        if let Err(error) = stream.start() {
+            self.halt_mixer();
+            return Err(error);
+        }
+        inner.stream = Some(stream);
+        Ok(())
+    }
+
+    /// Stops playback, the playhead stays where the device stopped.
+    pub fn stop(&self) {
+        let position = self.position();
+        let mut inner = self.inner.borrow_mut();
+        let Some(mut stream) = inner.stream.take() else {
+            return;
+        };
+        if let Err(error) = stream.stop() {
+            error!("failed to stop playback: {error}");
+        }
+        self.halt_mixer();
+        inner.start_frame = position;
+    }
+
+    /// Moves the playhead, playback continues from there.
+    pub fn locate(&self, frame: usize) {
+        let mut inner = self.inner.borrow_mut();
+        inner.start_frame = frame;
+        if inner.stream.is_some() {
+            self.restart_mixer(frame, inner.loop_range);
+        }
+    }
+
+    fn halt_mixer(&self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        self.shared.generation.fetch_add(1, Ordering::AcqRel);
+        queue.clear();
+        self.clear_meters();
+    }
+
+    fn clear_meters(&self) {
+        for track in self.shared.tracks.lock().unwrap().iter() {
+            track.meter().clear();
+        }
+    }
+
+    fn restart_mixer(&self, start_frame: usize, loop_range: Option<(usize, usize)>) {
+        let generation = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            queue.clear();
+            self.shared.played_frames.store(0, Ordering::Relaxed);
+            self.shared.generation.fetch_add(1, Ordering::AcqRel) + 1
+        };
+        self.shared.failure.lock().unwrap().take();
+        self.clear_meters();
+
+        let shared = self.shared.clone();
+        std::thread::spawn(move || {
+            let mut frame = start_frame;
+            // meters are keyed by the output stream, which keeps counting across loop wraps
+            let mut mixed_frames = 0;
+            loop {
+                let queued = shared.queue.lock().unwrap().len() / PLAYBACK_CHANNELS;
+                if queued >= MIX_AHEAD_FRAMES {
+                    if shared.generation.load(Ordering::Acquire) != generation {
+                        return;
+                    }
+                    std::thread::sleep(MIX_IDLE);
+                    continue;
+                }
+                // blocks end on the loop end so the wrap is sample accurate
+                let frames = match loop_range {
+                    Some((_, loop_end)) if frame < loop_end => MIX_BLOCK_FRAMES.min(loop_end - frame),
+                    _ => MIX_BLOCK_FRAMES,
+                };
+                let tracks = shared.tracks.lock().unwrap().clone();
+                let block = match mix_tracks(&tracks, frame, frames) {
+                    Ok(block) => block,
+                    Err(error) => {
+                        error!("playback mixing stopped: {error}");
+                        *shared.failure.lock().unwrap() = Some(error);
+                        return;
+                    }
+                };
+
+                {
+                    // the device callback skips a period rather than wait, so only the copy happens under the lock
+                    let mut queue = shared.queue.lock().unwrap();
+                    // located or stopped while mixing, the block belongs to the old position
+                    if shared.generation.load(Ordering::Acquire) != generation {
+                        return;
+                    }
+                    queue.extend(block.samples);
+                }
+                for (track, peaks) in tracks.iter().zip(block.track_peaks) {
+                    track.meter().push(mixed_frames, peaks);
+                }
+                frame += frames;
+                mixed_frames += frames;
+                if let Some((loop_start, loop_end)) = loop_range {
+                    if frame == loop_end {
+                        frame = loop_start;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Frame reached after playing `played` frames from `start_frame`, wrapping in the loop when it started before its end.
+fn loop_position(start_frame: usize, played: usize, loop_range: Option<(usize, usize)>) -> usize {
+    let frame = start_frame + played;
+    match loop_range {
+        Some((loop_start, loop_end)) if start_frame < loop_end && frame >= loop_end => {
+            loop_start + (frame - loop_end) % (loop_end - loop_start)
+        }
+        _ => frame,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_loop_position_wraps() {
+        let loop_range = Some((100, 200));
+        assert_eq!(loop_position(150, 20, loop_range), 170);
+        assert_eq!(loop_position(150, 50, loop_range), 100);
+        assert_eq!(loop_position(150, 270, loop_range), 120);
+        assert_eq!(loop_position(0, 250, loop_range), 150);
+        assert_eq!(loop_position(300, 50, loop_range), 350);
+        assert_eq!(loop_position(150, 100, None), 250);
+    }
+}
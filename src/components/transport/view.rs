@@ -0,0 +1,84 @@
+use gpui::{
+    App, Bounds, DispatchPhase, Hsla, IntoElement, MouseButton, MouseDownEvent, ParentElement, PathBuilder, Pixels,
+    RenderOnce, StyleRefinement, Styled, Window, canvas, div, fill, point, px, rgb, size,
+};
+use gpui_component::StyledExt;
+use std::rc::Rc;
+
+const HEAD_WIDTH_PX: f32 = 9.0;
+
+type SeekHandler = Rc<dyn Fn(Pixels, &mut Window, &mut App)>;
+
+/// Playhead line over the ruler, clicking the ruler seeks to the pointer.
+#[derive(IntoElement)]
+pub struct PlayheadView {
+    offset_x: Option<Pixels>,
+    on_seek: Option<SeekHandler>,
+    style: StyleRefinement,
+    color: Hsla,
+}
+
+impl Styled for PlayheadView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl PlayheadView {
+    /// `offset_x` is the playhead from the left of the element, `None` when scrolled out of view.
+    pub fn new(offset_x: Option<Pixels>) -> Self {
+        Self {
+            offset_x,
+            on_seek: None,
+            style: StyleRefinement::default(),
+            color: rgb(0x4FB3FF).into(),
+        }
+    }
+
+    /// Called with the clicked offset from the left of the element.
+    pub fn on_seek(mut self, handler: impl Fn(Pixels, &mut Window, &mut App) + 'static) -> Self {
+        self.on_seek = Some(Rc::new(handler));
+        self
+    }
+}
+
+impl RenderOnce for PlayheadView {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let (offset_x, on_seek, color) = (self.offset_x, self.on_seek, self.color);
+        let playhead = canvas(
+            |_, _, _| {},
+            move |bounds, _, window, _| {
+                if let Some(offset_x) = offset_x {
+                    let x = bounds.origin.x + offset_x;
+                    let line = Bounds::new(point(x, bounds.origin.y), size(px(1.0), bounds.size.height));
+                    window.paint_quad(fill(line, color));
+
+                    let half = px(HEAD_WIDTH_PX / 2.0);
+                    let mut head = PathBuilder::fill();
+                    head.move_to(point(x - half, bounds.origin.y));
+                    head.line_to(point(x + half + px(1.0), bounds.origin.y));
+                    head.line_to(point(x + px(0.5), bounds.origin.y + half));
+                    head.close();
+                    if let Ok(path) = head.build() {
+                        window.paint_path(path, color);
+                    }
+                }
+
+                let Some(on_seek) = on_seek.clone() else {
+                    return;
+                };
+                window.on_mouse_event(move |event: &MouseDownEvent, phase, window, cx| {
+                    if phase == DispatchPhase::Bubble
+                        && event.button == MouseButton::Left
+                        && bounds.contains(&event.position)
+                    {
+                        on_seek(event.position.x - bounds.origin.x, window, cx);
+                    }
+                });
+            },
+        )
+        .size_full();
+
+        div().refine_style(&self.style).child(playhead)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::components::waveform::Fft;
+
+/// Frames `other` lags behind `reference`, from the strongest cross-correlation within ±`max_lag`.
+/// Inverted polarity correlates as strongly, `None` when either signal is silent.
+pub fn measure_lag(reference: &[f32], other: &[f32], max_lag: usize) -> Option<isize> {
+    if reference.is_empty() || other.is_empty() {
+        return None;
+    }
+    // zero padded so the circular correlation does not wrap
+    let n = (reference.len() + other.len()).next_power_of_two();
+    let fft = Fft::new(n);
+    let spectrum = |samples: &[f32]| {
+        let mut re = samples.to_vec();
+        re.resize(n, 0.0);
+        let mut im = vec![0.0; n];
+        fft.process(&mut re, &mut im);
+        (re, im)
+    };
+    let (a_re, a_im) = spectrum(reference);
+    let (b_re, b_im) = spectrum(other);
+
+    // conj(A) * B, transformed back through the conjugate, the real part is all that is needed
+    let mut re: Vec<f32> = (0..n).map(|k| a_re[k] * b_re[k] + a_im[k] * b_im[k]).collect();
+    let mut im: Vec<f32> = (0..n).map(|k| -(a_re[k] * b_im[k] - a_im[k] * b_re[k])).collect();
+    fft.process(&mut re, &mut im);
+
+    let max_lag = max_lag as isize;
+    let lags = (-max_lag.min(reference.len() as isize - 1))..=max_lag.min(other.len() as isize - 1);
+    let (lag, peak) = lags
+        .map(|lag| (lag, re[lag.rem_euclid(n as isize) as usize].abs()))
+        .fold(
+            (0, 0.0),
+            |best, (lag, value)| if value > best.1 { (lag, value) } else { best },
+        );
+    (peak > 0.0).then_some(lag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn noise(len: usize) -> Vec<f32> {
+        let mut state = 0x2545_F491_u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 24) as f32 - 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_measure_lag() {
+        let reference = noise(4096);
+        let delayed: Vec<f32> = [vec![0.0; 37], reference.clone()].concat();
+        let early = reference[20..].to_vec();
+
+        assert_eq!(measure_lag(&reference, &reference, 256), Some(0));
+        assert_eq!(measure_lag(&reference, &delayed, 256), Some(37));
+        assert_eq!(measure_lag(&reference, &early, 256), Some(-20));
+        // inverted polarity aligns the same way
+        let inverted: Vec<f32> = delayed.iter().map(|s| -s).collect();
+        assert_eq!(measure_lag(&reference, &inverted, 256), Some(37));
+        // out of reach
+        assert_ne!(measure_lag(&reference, &delayed, 16), Some(37));
+        assert_eq!(measure_lag(&reference, &[0.0; 512], 256), None);
+    }
+}
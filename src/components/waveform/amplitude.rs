@@ -0,0 +1,102 @@
+// level drawn on the centre line of the dB scale
+pub const AMPLITUDE_FLOOR_DB: f32 = -120.0;
+pub const AMPLITUDE_ZOOM_MIN: f32 = 1.0;
+// +60 dB, enough to read residuals near the floor at full height
+pub const AMPLITUDE_ZOOM_MAX: f32 = 1000.0;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AmplitudeScale {
+    #[default]
+    Linear,
+    Decibels,
+}
+
+impl AmplitudeScale {
+    pub const ALL: [AmplitudeScale; 2] = [AmplitudeScale::Linear, AmplitudeScale::Decibels];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AmplitudeScale::Linear => "Linear",
+            AmplitudeScale::Decibels => "dB",
+        }
+    }
+}
+
+/// How sample values map to waveform heights, `zoom` scales the samples before the scale is applied.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WaveFormAmplitude {
+    pub scale: AmplitudeScale,
+    pub zoom: f32,
+}
+
+impl Default for WaveFormAmplitude {
+    fn default() -> Self {
+        Self {
+            scale: AmplitudeScale::default(),
+            zoom: AMPLITUDE_ZOOM_MIN,
+        }
+    }
+}
+
+impl WaveFormAmplitude {
+    /// Height of `sample` as a signed fraction of the half height, in -1.0 ..= 1.0.
+    pub fn height(&self, sample: f32) -> f32 {
+        let sample = sample * self.zoom;
+        match self.scale {
+            AmplitudeScale::Linear => sample.clamp(-1.0, 1.0),
+            AmplitudeScale::Decibels => {
+                let db = 20.0 * sample.abs().max(f32::MIN_POSITIVE).log10();
+                let height = ((db - AMPLITUDE_FLOOR_DB) / -AMPLITUDE_FLOOR_DB).clamp(0.0, 1.0);
+                height.copysign(sample)
+            }
+        }
+    }
+
+    pub fn zoomed(self, factor: f32) -> Self {
+        Self {
+            zoom: (self.zoom * factor).clamp(AMPLITUDE_ZOOM_MIN, AMPLITUDE_ZOOM_MAX),
+            ..self
+        }
+    }
+}
+
+/// True for samples at or above full scale.
+pub fn is_clipped(sample: f32) -> bool {
+    sample.abs() >= 1.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::waveform::db_to_gain;
+
+    #[test]
+    fn test_linear_height() {
+        let amplitude = WaveFormAmplitude::default();
+        assert_eq!(amplitude.height(0.5), 0.5);
+        assert_eq!(amplitude.height(-2.0), -1.0);
+        assert_eq!(amplitude.zoomed(4.0).height(0.125), 0.5);
+        assert_eq!(amplitude.zoomed(1e6).zoom, AMPLITUDE_ZOOM_MAX);
+        assert_eq!(amplitude.zoomed(0.1).zoom, AMPLITUDE_ZOOM_MIN);
+    }
+
+    #[test]
+    fn test_decibel_height() {
+        let amplitude = WaveFormAmplitude {
+            scale: AmplitudeScale::Decibels,
+            zoom: 1.0,
+        };
+        assert_eq!(amplitude.height(1.0), 1.0);
+        assert_eq!(amplitude.height(0.0), 0.0);
+        // a -90 dB residual is a quarter of the height, at +60 dB zoom most of it
+        assert!((amplitude.height(-db_to_gain(-90.0)) + 0.25).abs() < 1e-4);
+        assert!((amplitude.zoomed(1000.0).height(db_to_gain(-90.0)) - 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_clipped() {
+        assert!(is_clipped(1.0));
+        assert!(is_clipped(-1.5));
+        assert!(!is_clipped(0.999));
+    }
+}
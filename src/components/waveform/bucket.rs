@@ -2,21 +2,46 @@
 pub struct WaveFormBucket {
     pub min: f32,
     pub max: f32,
+    // sum of squared samples, RMS adds up across levels from these, the level knows the frame counts
+    pub sum_squares: f32,
 }
 
 const MIN: f32 = f32::INFINITY;
 const MAX: f32 = f32::NEG_INFINITY;
 
 impl WaveFormBucket {
+    pub fn empty() -> Self {
+        Self {
+            min: MIN,
+            max: MAX,
+            sum_squares: 0.0,
+        }
+    }
+
+    pub fn push_sample(&mut self, sample: f32) {
+        self.min = f32::min(self.min, sample);
+        self.max = f32::max(self.max, sample);
+        self.sum_squares += sample * sample;
+    }
+
     pub fn from_buckets(chunk: &[WaveFormBucket]) -> Self {
         let min = chunk.iter().fold(MIN, |m, b| f32::min(m, b.min));
         let max = chunk.iter().fold(MAX, |m, b| f32::max(m, b.max));
-        Self { min, max }
+        let sum_squares = chunk.iter().map(|b| b.sum_squares).sum();
+        Self { min, max, sum_squares }
     }
 
     pub fn from_samples(chunk: &[f32]) -> Self {
-        let min = chunk.iter().fold(MIN, |m, b| f32::min(m, *b));
-        let max = chunk.iter().fold(MAX, |m, b| f32::max(m, *b));
-        Self { min, max }
+        let mut bucket = Self::empty();
+        chunk.iter().for_each(|s| bucket.push_sample(*s));
+        bucket
+    }
+}
+
+/// Root mean square of `frames` samples whose squares add up to `sum_squares`, 0 for no samples.
+pub fn rms(sum_squares: f64, frames: usize) -> f32 {
+    if frames == 0 {
+        return 0.0;
     }
+    (sum_squares / frames as f64).sqrt() as f32
 }
@@ -1,32 +1,97 @@
-use crate::audio::read_file;
+use crate::audio::{AudioError, WavStream};
 use crate::components::waveform::form::WaveForm;
-use crate::components::waveform::meta::WaveClipMetadata;
+use crate::components::waveform::meta::{WaveClipMetadata, WaveClipMetadataBuilder};
+use crate::components::waveform::mipmap::{WaveFormMipMap, WaveFormMipMapBuilder};
+use crate::components::waveform::peak_cache::{PeakCache, PeakCacheEntry, PeakCacheKey};
 use anyhow::Result;
+use log::warn;
 use std::path::Path;
+use std::sync::Arc;
+
+const LOAD_CHUNK_FRAMES: usize = 64 * 1024;
 
 #[derive(Clone)]
 pub struct WaveClip {
     metadata: WaveClipMetadata,
     channels: Vec<WaveForm>,
+    stream: Arc<WavStream>,
 }
 
 impl WaveClip {
+    /// Opens the clip with the peaks cached for the file, see `PeakCache::from_env`.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_cache(path, PeakCache::from_env().as_ref())
+    }
+
+    pub fn open_with_cache<P: AsRef<Path>>(path: P, cache: Option<&PeakCache>) -> Result<Self> {
         let path = path.as_ref();
-        let (spec, samples) = read_file(path)?;
+        let stream = Arc::new(WavStream::open(path)?);
+        let spec = stream.spec();
         let channel_count = spec.channels as usize;
 
-        let metadata = WaveClipMetadata::new(path, spec, &samples)?;
+        let cached = cache.and_then(|cache| match PeakCacheKey::of(path) {
+            Ok(key) => Some((cache, key)),
+            Err(err) => {
+                warn!("peak cache skipped for {path:?}: {err}");
+                None
+            }
+        });
+        let entry = cached.as_ref().and_then(|(cache, key)| cache.load(key));
+        // a file truncated differently since it was cached is scanned again
+        let entry = entry.filter(|entry| {
+            entry.levels.len() == channel_count && entry.statistics.sample_count == stream.frame_count() * channel_count
+        });
 
-        let mut channel_samples = vec![Vec::with_capacity(metadata.frame_count()); channel_count];
+        let (metadata, levels) = match entry {
+            Some(entry) => {
+                let metadata = WaveClipMetadata::from_statistics(path, spec, entry.statistics)?;
+                (metadata, entry.levels)
+            }
+            None => {
+                let (metadata, levels) = Self::scan(path, &stream)?;
+                if let Some((cache, key)) = &cached {
+                    let entry = PeakCacheEntry {
+                        statistics: metadata.statistics(),
+                        levels,
+                    };
+                    if let Err(err) = cache.store(key, &entry) {
+                        warn!("peak cache not written for {path:?}: {err}");
+                    }
+                    (metadata, entry.levels)
+                } else {
+                    (metadata, levels)
+                }
+            }
+        };
 
-        for (index, sample) in samples.into_iter().enumerate() {
-            channel_samples[index % channel_count].push(sample);
-        }
+        let channels: Vec<WaveForm> = levels
+            .into_iter()
+            .enumerate()
+            .map(|(channel, level)| WaveForm::from_stream(&stream, channel, level))
+            .collect();
 
-        let channels: Vec<WaveForm> = channel_samples.into_iter().map(WaveForm::from).collect();
+        Ok(Self {
+            metadata,
+            channels,
+            stream,
+        })
+    }
 
-        Ok(Self { metadata, channels })
+    // metadata and mipmaps are built in a single pass, raw samples are read on demand later
+    fn scan(path: &Path, stream: &WavStream) -> Result<(WaveClipMetadata, Vec<WaveFormMipMap>)> {
+        let channel_count = stream.spec().channels as usize;
+        let mut metadata = WaveClipMetadataBuilder::new(stream.spec());
+        let mut levels: Vec<WaveFormMipMapBuilder> = (0..channel_count).map(|_| WaveForm::mip_map_builder()).collect();
+        stream.for_each_chunk(LOAD_CHUNK_FRAMES, |chunk| {
+            metadata.push(chunk);
+            for frame in chunk.chunks_exact(channel_count) {
+                for (level, sample) in levels.iter_mut().zip(frame) {
+                    level.push(*sample);
+                }
+            }
+        })?;
+        let levels = levels.into_iter().map(WaveFormMipMapBuilder::finish).collect();
+        Ok((metadata.build(path)?, levels))
     }
 
     pub fn channels(&self) -> &[WaveForm] {
@@ -44,4 +109,52 @@ impl WaveClip {
     pub fn metadata(&self) -> &WaveClipMetadata {
         &self.metadata
     }
+
+    /// Set when only the valid prefix of a truncated file was loaded.
+    pub fn truncation(&self) -> Option<AudioError> {
+        self.stream.truncation()
+    }
+}
+
+/// Short constant stereo clip written to a temporary file, for tests that need real regions.
+#[cfg(test)]
+pub fn test_clip(name: &str, frames: usize) -> WaveClip {
+    let path = std::env::temp_dir().join(format!("unrecord_test_{name}.wav"));
+    crate::audio::write_file(&path, 44100.0, &vec![0.25; frames * 2]).unwrap();
+    let clip = WaveClip::open_with_cache(&path, None).unwrap();
+    std::fs::remove_file(&path).ok();
+    clip
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_from_peak_cache() {
+        let dir = std::env::temp_dir().join("unrecord_test_clip_peak_cache");
+        let path = dir.join("clip.wav");
+        std::fs::create_dir_all(&dir).unwrap();
+        let samples: Vec<f32> = (0..4096).map(|i| if i % 4 < 2 { 0.5 } else { -0.25 }).collect();
+        crate::audio::write_file(&path, 44100.0, &samples).unwrap();
+        let cache = PeakCache::new(dir.join("peaks"));
+
+        let scanned = WaveClip::open_with_cache(&path, Some(&cache)).unwrap();
+        let key = PeakCacheKey::of(&path).unwrap();
+        assert!(cache.load(&key).is_some());
+        let cached = WaveClip::open_with_cache(&path, Some(&cache)).unwrap();
+
+        assert_eq!(cached.frame_count(), scanned.frame_count());
+        assert_eq!(cached.metadata().statistics(), scanned.metadata().statistics());
+        for (a, b) in cached.channels().iter().zip(scanned.channels()) {
+            assert_eq!(a.mip_map_levels().len(), b.mip_map_levels().len());
+            assert_eq!(
+                a.min_max_for_frames(0, 2048, 512.0),
+                b.min_max_for_frames(0, 2048, 512.0)
+            );
+            assert_eq!(a.rms_for_frames(0, 2048, 512.0), b.rms_for_frames(0, 2048, 512.0));
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
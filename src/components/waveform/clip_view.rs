@@ -1,4 +1,6 @@
-use crate::components::waveform::{WaveClip, WaveFormView};
+use crate::components::waveform::{
+    SampleGain, SpectrogramSettings, SpectrogramView, WaveClip, WaveFormAmplitude, WaveFormView,
+};
 use gpui::{
     AnyElement, App, AvailableSpace, Bounds, ContentMask, Element, ElementId, GlobalElementId, InspectorElementId,
     IntoElement, LayoutId, Pixels, Refineable, Style, StyleRefinement, Styled, Window, point, px, size,
@@ -12,6 +14,9 @@ pub struct WaveClipView {
     start_frame: usize,
     end_frame: usize,
     frames_per_px: f64,
+    gain: Option<SampleGain>,
+    spectrogram: Option<SpectrogramSettings>,
+    amplitude: WaveFormAmplitude,
     style: StyleRefinement,
 }
 
@@ -22,9 +27,28 @@ impl WaveClipView {
             start_frame,
             end_frame,
             frames_per_px,
+            gain: None,
+            spectrogram: None,
+            amplitude: WaveFormAmplitude::default(),
             style: StyleRefinement::default(),
         }
     }
+
+    pub fn gain(mut self, gain: Option<SampleGain>) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Draws each channel as a spectrogram, the region gain is not applied to it.
+    pub fn spectrogram(mut self, settings: Option<SpectrogramSettings>) -> Self {
+        self.spectrogram = settings;
+        self
+    }
+
+    pub fn amplitude(mut self, amplitude: WaveFormAmplitude) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
 }
 
 impl Styled for WaveClipView {
@@ -158,10 +182,20 @@ impl WaveClipView {
 
         let mut layouts = VecDeque::with_capacity(self.clip.channels().len());
         for (waveform, channel_bounds) in channels_iter {
-            let mut element = WaveFormView::new(waveform, self.start_frame, self.end_frame, self.frames_per_px)
-                .h(channel_bounds.size.height)
-                .w(channel_bounds.size.width)
-                .into_any_element();
+            let mut element = match self.spectrogram {
+                Some(settings) => {
+                    SpectrogramView::new(waveform, self.start_frame, self.end_frame, self.frames_per_px, settings)
+                        .h(channel_bounds.size.height)
+                        .w(channel_bounds.size.width)
+                        .into_any_element()
+                }
+                None => WaveFormView::new(waveform, self.start_frame, self.end_frame, self.frames_per_px)
+                    .gain(self.gain.clone())
+                    .amplitude(self.amplitude)
+                    .h(channel_bounds.size.height)
+                    .w(channel_bounds.size.width)
+                    .into_any_element(),
+            };
 
             element.layout_as_root(available_item_space, window, cx);
             layouts.push_back(WaveFormLayout {
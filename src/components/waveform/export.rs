@@ -0,0 +1,483 @@
+use crate::audio::{AudioResult, NpzWriter, write_npy, write_raw_f32};
+use crate::components::waveform::clip::WaveClip;
+use crate::components::waveform::gain::{SampleGain, apply_gain};
+use crate::components::waveform::measure_lag;
+use anyhow::{Result, anyhow};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+// analysis series use the same 100 ms hop as the loudness blocks
+const ANALYSIS_HOP_SECONDS: f64 = 0.1;
+// frames from the start of the range correlated to align the reference, and the furthest lag looked for
+const ALIGN_WINDOW_FRAMES: usize = 1 << 15;
+const MAX_ALIGN_LAG_FRAMES: usize = 1 << 14;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClipExportFormat {
+    Npy,
+    Npz,
+    Raw,
+}
+
+impl ClipExportFormat {
+    pub const ALL: [ClipExportFormat; 3] = [Self::Npy, Self::Npz, Self::Raw];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "npy" => Some(ClipExportFormat::Npy),
+            "npz" => Some(ClipExportFormat::Npz),
+            "raw" | "pcm" | "f32" => Some(ClipExportFormat::Raw),
+            _ => None,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ClipExportFormat::Npy => "NumPy arrays (.npy)",
+            ClipExportFormat::Npz => "NumPy archive (.npz)",
+            ClipExportFormat::Raw => "Raw float32 PCM",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ClipExportFormat::Npy => "npy",
+            ClipExportFormat::Npz => "npz",
+            ClipExportFormat::Raw => "f32",
+        }
+    }
+}
+
+/// Row-major float32 array with named axes.
+struct ExportArray {
+    name: String,
+    axes: &'static [&'static str],
+    shape: Vec<usize>,
+    data: Vec<f32>,
+    attributes: Vec<(&'static str, i64)>,
+}
+
+/// Export of a clip range together with its peaks, residual and analysis series,
+/// every export gets a JSON sidecar describing the written arrays.
+#[derive(Clone)]
+pub struct ClipExport {
+    clip: WaveClip,
+    start_frame: usize,
+    end_frame: usize,
+    mip_map: bool,
+    analysis: bool,
+    reference: Option<WaveClip>,
+    reference_frames: (usize, usize),
+    reference_offset: isize,
+    gain: Option<SampleGain>,
+    reference_gain: Option<SampleGain>,
+}
+
+impl ClipExport {
+    pub fn new(clip: &WaveClip) -> Self {
+        Self {
+            clip: clip.clone(),
+            start_frame: 0,
+            end_frame: clip.frame_count(),
+            mip_map: true,
+            analysis: true,
+            reference: None,
+            reference_frames: (0, 0),
+            reference_offset: 0,
+            gain: None,
+            reference_gain: None,
+        }
+    }
+
+    pub fn clip(&self) -> &WaveClip {
+        &self.clip
+    }
+
+    pub fn range(mut self, start_frame: usize, end_frame: usize) -> Self {
+        self.end_frame = end_frame.min(self.clip.frame_count());
+        self.start_frame = start_frame.min(self.end_frame);
+        self
+    }
+
+    pub fn mip_map(mut self, mip_map: bool) -> Self {
+        self.mip_map = mip_map;
+        self
+    }
+
+    pub fn analysis(mut self, analysis: bool) -> Self {
+        self.analysis = analysis;
+        self
+    }
+
+    /// Adds `clip - reference` over the exported range, reference frame `frame + offset` plays along with `frame`.
+    /// Only `start_frame..end_frame` of the reference is heard, the lag measured between the two is corrected too.
+    pub fn reference(mut self, reference: &WaveClip, start_frame: usize, end_frame: usize, offset: isize) -> Self {
+        let end_frame = end_frame.min(reference.frame_count());
+        self.reference = Some(reference.clone());
+        self.reference_frames = (start_frame.min(end_frame), end_frame);
+        self.reference_offset = offset;
+        self
+    }
+
+    /// Exports the samples as the region plays them, the residual then compares processed audio.
+    /// Mipmaps and metadata keep describing the source clip.
+    pub fn gain(mut self, gain: SampleGain) -> Self {
+        self.gain = Some(gain);
+        self
+    }
+
+    /// Processing of the reference, for level matched and polarity inverted comparisons.
+    pub fn reference_gain(mut self, gain: SampleGain) -> Self {
+        self.reference_gain = Some(gain);
+        self
+    }
+
+    /// Writes the arrays next to `path`, its extension is replaced. Returns the written files.
+    pub fn write(&self, format: ClipExportFormat, path: &Path) -> Result<Vec<PathBuf>> {
+        let base = path.with_extension("");
+        let stem = base
+            .file_name()
+            .ok_or(anyhow!("no filename"))?
+            .to_string_lossy()
+            .into_owned();
+        let arrays = self.arrays()?;
+
+        let mut written = Vec::new();
+        let mut files = Vec::new();
+        match format {
+            ClipExportFormat::Npz => {
+                let npz_path = base.with_extension("npz");
+                let mut npz = NpzWriter::create(&npz_path)?;
+                for array in &arrays {
+                    npz.add(&array.name, &array.shape, &array.data)?;
+                    files.push(format!("{stem}.npz"));
+                }
+                npz.finish()?;
+                written.push(npz_path);
+            }
+            ClipExportFormat::Npy | ClipExportFormat::Raw => {
+                for array in &arrays {
+                    let filename = format!("{stem}.{}.{}", array.name, format.extension());
+                    let array_path = base.with_file_name(&filename);
+                    match format {
+                        ClipExportFormat::Npy => write_npy(&array_path, &array.shape, &array.data)?,
+                        _ => write_raw_f32(&array_path, &array.data)?,
+                    }
+                    files.push(filename);
+                    written.push(array_path);
+                }
+            }
+        }
+
+        let sidecar_path = base.with_extension("json");
+        std::fs::write(&sidecar_path, self.sidecar(format, &arrays, &files))?;
+        written.push(sidecar_path);
+        Ok(written)
+    }
+
+    fn frames(&self) -> usize {
+        self.end_frame - self.start_frame
+    }
+
+    fn read_interleaved(
+        clip: &WaveClip,
+        start_frame: usize,
+        end_frame: usize,
+        gain: Option<&SampleGain>,
+    ) -> AudioResult<Vec<f32>> {
+        let channels = clip
+            .channels()
+            .iter()
+            .map(|channel| {
+                let mut samples = channel.read_frames(start_frame, end_frame)?;
+                if let Some(gain) = gain {
+                    apply_gain(&mut samples, start_frame, gain.as_ref());
+                }
+                Ok(samples)
+            })
+            .collect::<AudioResult<Vec<Vec<f32>>>>()?;
+        let frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+        Ok((0..frames)
+            .flat_map(|frame| channels.iter().map(move |channel| channel[frame]))
+            .collect())
+    }
+
+    fn arrays(&self) -> Result<Vec<ExportArray>> {
+        let channel_count = self.clip.channel_count();
+        let samples = Self::read_interleaved(&self.clip, self.start_frame, self.end_frame, self.gain.as_ref())?;
+        if samples.len() != self.frames() * channel_count {
+            return Err(anyhow!(
+                "failed to read frames {}..{}",
+                self.start_frame,
+                self.end_frame
+            ));
+        }
+
+        let mut arrays = Vec::new();
+        if self.mip_map {
+            arrays.extend(self.mip_map_arrays());
+        }
+        if let Some(reference) = &self.reference {
+            arrays.push(self.residual_array(&samples, reference)?);
+        }
+        if self.analysis {
+            arrays.extend(self.analysis_arrays(&samples));
+        }
+        arrays.insert(
+            0,
+            ExportArray {
+                name: "samples".to_string(),
+                axes: &["frame", "channel"],
+                shape: vec![self.frames(), channel_count],
+                data: samples,
+                attributes: Vec::new(),
+            },
+        );
+        Ok(arrays)
+    }
+
+    // min/max pairs of the buckets covering the range, for every level
+    fn mip_map_arrays(&self) -> Vec<ExportArray> {
+        let channels = self.clip.channels();
+        let Some(first) = channels.first() else {
+            return Vec::new();
+        };
+
+        (0..first.mip_map_levels().len())
+            .map(|level| {
+                let frames_per_bucket = first.mip_map_levels()[level].frames_per_bucket();
+                let start_bucket = self.start_frame / frames_per_bucket;
+                let end_bucket = self.end_frame.div_ceil(frames_per_bucket);
+                let buckets = channels
+                    .iter()
+                    .map(|channel| channel.mip_map_levels()[level].buckets().len())
+                    .fold(end_bucket, usize::min)
+                    .saturating_sub(start_bucket);
+
+                let mut data = Vec::with_capacity(buckets * channels.len() * 2);
+                for index in start_bucket..start_bucket + buckets {
+                    for channel in channels {
+                        let bucket = &channel.mip_map_levels()[level].buckets()[index];
+                        data.push(bucket.min);
+                        data.push(bucket.max);
+                    }
+                }
+
+                ExportArray {
+                    name: format!("mipmap_{level}"),
+                    axes: &["bucket", "channel", "min_max"],
+                    shape: vec![buckets, channels.len(), 2],
+                    data,
+                    attributes: vec![
+                        ("frames_per_bucket", frames_per_bucket as i64),
+                        ("start_frame", (start_bucket * frames_per_bucket) as i64),
+                    ],
+                }
+            })
+            .collect()
+    }
+
+    fn residual_array(&self, samples: &[f32], reference: &WaveClip) -> Result<ExportArray> {
+        let channel_count = self.clip.channel_count();
+        if reference.channel_count() != channel_count {
+            return Err(anyhow!(
+                "reference has {} channels, expected {channel_count}",
+                reference.channel_count()
+            ));
+        }
+
+        let offset = self.reference_offset - self.reference_lag(reference)?;
+        // the reference is silent outside its frames
+        let (heard_start, heard_end) = (self.reference_frames.0 as isize, self.reference_frames.1 as isize);
+        let start = self.start_frame as isize + offset;
+        let from = start.clamp(heard_start, heard_end);
+        let to = (start + self.frames() as isize).clamp(from, heard_end);
+        let lead = ((from - start).max(0) as usize).min(self.frames());
+        let mut aligned = vec![0.0; lead * channel_count];
+        aligned.extend(Self::read_interleaved(
+            reference,
+            from as usize,
+            to as usize,
+            self.reference_gain.as_ref(),
+        )?);
+        aligned.resize(samples.len(), 0.0);
+        let data: Vec<f32> = samples.iter().zip(&aligned).map(|(s, r)| s - r).collect();
+
+        Ok(ExportArray {
+            name: "residual".to_string(),
+            axes: &["frame", "channel"],
+            shape: vec![self.frames(), channel_count],
+            data,
+            attributes: vec![("reference_offset", offset as i64)],
+        })
+    }
+
+    // frames the clip lags behind the reference where the range starts, zero when either is silent there
+    fn reference_lag(&self, reference: &WaveClip) -> Result<isize> {
+        let (Some(clip_channel), Some(reference_channel)) =
+            (self.clip.channels().first(), reference.channels().first())
+        else {
+            return Ok(0);
+        };
+        let reference_start = (self.start_frame as isize + self.reference_offset).max(self.reference_frames.0 as isize);
+        let clip_start = (reference_start - self.reference_offset) as usize;
+        let reference_start = reference_start as usize;
+        let clip_window =
+            clip_channel.read_frames(clip_start, (clip_start + ALIGN_WINDOW_FRAMES).min(self.end_frame))?;
+        let reference_window = reference_channel.read_frames(
+            reference_start,
+            (reference_start + ALIGN_WINDOW_FRAMES).min(self.reference_frames.1),
+        )?;
+        Ok(measure_lag(&reference_window, &clip_window, MAX_ALIGN_LAG_FRAMES).unwrap_or(0))
+    }
+
+    // linear RMS and peak per hop and channel
+    fn analysis_arrays(&self, samples: &[f32]) -> Vec<ExportArray> {
+        let channel_count = self.clip.channel_count().max(1);
+        let sample_rate = self.clip.metadata().spec().sample_rate as f64;
+        let hop_frames = ((sample_rate * ANALYSIS_HOP_SECONDS).round() as usize).max(1);
+
+        let mut rms = Vec::new();
+        let mut peak = Vec::new();
+        for hop in samples.chunks(hop_frames * channel_count) {
+            let frames = (hop.len() / channel_count).max(1);
+            for channel in 0..channel_count {
+                let values = hop.iter().skip(channel).step_by(channel_count);
+                let power = values.clone().fold(0.0_f64, |sum, s| sum + (*s as f64) * (*s as f64));
+                rms.push((power / frames as f64).sqrt() as f32);
+                peak.push(values.fold(0.0_f32, |max, s| max.max(s.abs())));
+            }
+        }
+
+        let hops = rms.len() / channel_count;
+        [("rms", rms), ("peak", peak)]
+            .into_iter()
+            .map(|(name, data)| ExportArray {
+                name: name.to_string(),
+                axes: &["hop", "channel"],
+                shape: vec![hops, channel_count],
+                data,
+                attributes: vec![("hop_frames", hop_frames as i64)],
+            })
+            .collect()
+    }
+
+    fn sidecar(&self, format: ClipExportFormat, arrays: &[ExportArray], files: &[String]) -> String {
+        let metadata = self.clip.metadata();
+        let spec = metadata.spec();
+
+        let mut json = String::new();
+        json.push_str("{\n");
+        let _ = writeln!(json, "  \"source\": {},", json_string(&metadata.filepath()));
+        let _ = writeln!(json, "  \"sample_rate\": {},", spec.sample_rate);
+        let _ = writeln!(json, "  \"channels\": {},", spec.channels);
+        let _ = writeln!(json, "  \"start_frame\": {},", self.start_frame);
+        let _ = writeln!(json, "  \"end_frame\": {},", self.end_frame);
+        let _ = writeln!(json, "  \"format\": {},", json_string(format.extension()));
+        let _ = writeln!(json, "  \"processed\": {},", self.gain.is_some());
+        if let Some(reference) = &self.reference {
+            let _ = writeln!(
+                json,
+                "  \"reference\": {},",
+                json_string(&reference.metadata().filepath())
+            );
+        }
+        json.push_str("  \"metadata\": {\n");
+        let _ = writeln!(json, "    \"peak_dbfs\": {},", json_number(metadata.peak_dbfs()));
+        let _ = writeln!(json, "    \"rms_dbfs\": {},", json_number(metadata.rms_dbfs()));
+        let _ = writeln!(
+            json,
+            "    \"integrated_lufs\": {},",
+            json_number(metadata.integrated_lufs())
+        );
+        let _ = writeln!(
+            json,
+            "    \"crest_factor_db\": {},",
+            json_number(metadata.crest_factor_db())
+        );
+        let _ = writeln!(
+            json,
+            "    \"dc_offset_percent\": {}",
+            json_number(metadata.dc_offset_percent())
+        );
+        json.push_str("  },\n");
+
+        json.push_str("  \"arrays\": [\n");
+        for (index, (array, file)) in arrays.iter().zip(files).enumerate() {
+            let axes: Vec<String> = array.axes.iter().map(|axis| json_string(axis)).collect();
+            let shape: Vec<String> = array.shape.iter().map(|d| d.to_string()).collect();
+            json.push_str("    {");
+            let _ = write!(json, "\"name\": {}, ", json_string(&array.name));
+            let _ = write!(json, "\"file\": {}, ", json_string(file));
+            if format == ClipExportFormat::Npz {
+                let _ = write!(json, "\"key\": {}, ", json_string(&array.name));
+            }
+            let _ = write!(json, "\"dtype\": \"<f4\", \"order\": \"C\", ");
+            let _ = write!(
+                json,
+                "\"shape\": [{}], \"axes\": [{}]",
+                shape.join(", "),
+                axes.join(", ")
+            );
+            for (key, value) in &array.attributes {
+                let _ = write!(json, ", {}: {value}", json_string(key));
+            }
+            json.push('}');
+            json.push_str(if index + 1 < arrays.len() { ",\n" } else { "\n" });
+        }
+        json.push_str("  ]\n}\n");
+        json
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// JSON has no infinities, silence reports -inf dB
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value}")
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn test_json_number_non_finite() {
+        assert_eq!(json_number(-1.5), "-1.5");
+        assert_eq!(json_number(f64::NEG_INFINITY), "null");
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ClipExportFormat::parse("NPZ"), Some(ClipExportFormat::Npz));
+        assert_eq!(ClipExportFormat::parse("pcm"), Some(ClipExportFormat::Raw));
+        assert_eq!(ClipExportFormat::parse("wav"), None);
+    }
+}
@@ -1,13 +1,65 @@
-use crate::components::waveform::mipmap::WaveFormMipMap;
-use std::sync::Arc;
+use crate::audio::{AudioResult, WavStream};
+use crate::components::waveform::bucket::rms;
+use crate::components::waveform::mipmap::{WaveFormMipMap, WaveFormMipMapBuilder};
+use crate::components::waveform::{
+    SPECTROGRAM_TILE_COLUMNS, SpectrogramSettings, SpectrogramTileKey, SpectrogramTiles, spectrogram_columns,
+    spectrogram_pixels,
+};
+use gpui::RenderImage;
+use image::{Frame, RgbaImage};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 
-const SAMPLES_PER_BUCKET: usize = 2;
+const SAMPLES_PER_BUCKET: usize = 64;
 const REDUCE_BUCKETS: usize = 2;
 const LEVEL_MIN_LEN: usize = 32;
+// frames per cached block of a streamed channel, and blocks kept per channel
+const BLOCK_FRAMES: usize = 16 * 1024;
+const MAX_BLOCKS: usize = 32;
+
+enum WaveFormSamples {
+    Memory(Vec<f32>),
+    Stream {
+        stream: Arc<WavStream>,
+        channel: usize,
+        frames: usize,
+    },
+    // computed from other audio on every read, such as the difference of two regions
+    Derived {
+        read: Box<dyn Fn(usize, usize) -> AudioResult<Vec<f32>> + Send + Sync>,
+        frames: usize,
+    },
+}
+
+// recently read blocks of a streamed channel, most recent last, the lock is never held while reading
+#[derive(Default)]
+struct WaveFormBlocks {
+    blocks: VecDeque<(usize, Arc<Vec<f32>>)>,
+}
+
+impl WaveFormBlocks {
+    fn get(&mut self, index: usize) -> Option<Arc<Vec<f32>>> {
+        let position = self.blocks.iter().position(|(i, _)| *i == index)?;
+        let entry = self.blocks.remove(position)?;
+        let block = entry.1.clone();
+        self.blocks.push_back(entry);
+        Some(block)
+    }
+
+    fn insert(&mut self, index: usize, block: Arc<Vec<f32>>) {
+        self.blocks.retain(|(i, _)| *i != index);
+        self.blocks.push_back((index, block));
+        while self.blocks.len() > MAX_BLOCKS {
+            self.blocks.pop_front();
+        }
+    }
+}
 
 struct WaveFormInner {
-    samples: Vec<f32>,
+    samples: WaveFormSamples,
     mip_map: Vec<WaveFormMipMap>,
+    blocks: Mutex<WaveFormBlocks>,
+    spectrogram: Mutex<SpectrogramTiles>,
 }
 
 #[derive(Clone)]
@@ -16,6 +68,55 @@ pub struct WaveForm {
 }
 
 impl WaveFormInner {
+    fn frames(&self) -> usize {
+        match &self.samples {
+            WaveFormSamples::Memory(samples) => samples.len(),
+            WaveFormSamples::Stream { frames, .. } => *frames,
+            WaveFormSamples::Derived { frames, .. } => *frames,
+        }
+    }
+
+    fn read_frames(&self, start_frame: usize, end_frame: usize) -> AudioResult<Vec<f32>> {
+        let end_frame = end_frame.min(self.frames());
+        if start_frame >= end_frame {
+            return Ok(Vec::new());
+        }
+
+        let (stream, channel) = match &self.samples {
+            WaveFormSamples::Memory(samples) => return Ok(samples[start_frame..end_frame].to_vec()),
+            WaveFormSamples::Derived { read, .. } => return read(start_frame, end_frame),
+            WaveFormSamples::Stream { stream, channel, .. } => (stream, *channel),
+        };
+
+        let first = start_frame / BLOCK_FRAMES;
+        let last = (end_frame - 1) / BLOCK_FRAMES;
+        // long reads such as exports would only flush the blocks of everyone else
+        if last - first >= MAX_BLOCKS {
+            return stream.read_channel(channel, start_frame, end_frame);
+        }
+
+        let mut samples = Vec::with_capacity(end_frame - start_frame);
+        for index in first..=last {
+            let block = self.block(stream, channel, index)?;
+            let block_start = index * BLOCK_FRAMES;
+            let from = (start_frame.max(block_start) - block_start).min(block.len());
+            let to = (end_frame - block_start).min(block.len());
+            samples.extend_from_slice(&block[from..to]);
+        }
+        Ok(samples)
+    }
+
+    fn block(&self, stream: &WavStream, channel: usize, index: usize) -> AudioResult<Arc<Vec<f32>>> {
+        if let Some(block) = self.blocks.lock().unwrap().get(index) {
+            return Ok(block);
+        }
+        let start = index * BLOCK_FRAMES;
+        let end = (start + BLOCK_FRAMES).min(self.frames());
+        let block = Arc::new(stream.read_channel(channel, start, end)?);
+        self.blocks.lock().unwrap().insert(index, block.clone());
+        Ok(block)
+    }
+
     fn mip_level_for_frames_per_px(&self, frames_per_px: f32) -> Option<&WaveFormMipMap> {
         let target_samples = frames_per_px.max(1.0);
         self.mip_map
@@ -25,14 +126,14 @@ impl WaveFormInner {
     }
 
     fn raw_min_max_for_frames(&self, start_frame: usize, end_frame: usize) -> Option<(f32, f32)> {
-        if start_frame >= end_frame || start_frame >= self.samples.len() {
+        let samples = self.read_frames(start_frame, end_frame).ok()?;
+        if samples.is_empty() {
             return None;
         }
 
-        let end_frame = end_frame.min(self.samples.len());
         let mut min = f32::INFINITY;
         let mut max = f32::NEG_INFINITY;
-        for sample in &self.samples[start_frame..end_frame] {
+        for sample in &samples {
             min = min.min(*sample);
             max = max.max(*sample);
         }
@@ -50,31 +151,104 @@ impl WaveFormInner {
             .and_then(|level| level.min_max_for_frames(start_frame, end_frame))
             .or_else(|| self.raw_min_max_for_frames(start_frame, end_frame))
     }
+
+    fn raw_rms_for_frames(&self, start_frame: usize, end_frame: usize) -> Option<f32> {
+        let samples = self.read_frames(start_frame, end_frame).ok()?;
+        if samples.is_empty() {
+            return None;
+        }
+        Some(raw_rms(&samples))
+    }
+
+    fn rms_for_frames(&self, start_frame: usize, end_frame: usize, frames_per_px: f32) -> Option<f32> {
+        let first_level = self.mip_map.first()?;
+        let target_samples = frames_per_px.max(1.0);
+        if target_samples < first_level.frames_per_bucket() as f32 {
+            return self.raw_rms_for_frames(start_frame, end_frame);
+        }
+
+        self.mip_level_for_frames_per_px(target_samples)
+            .and_then(|level| level.rms_for_frames(start_frame, end_frame))
+            .or_else(|| self.raw_rms_for_frames(start_frame, end_frame))
+    }
 }
 
 impl WaveForm {
+    #[allow(dead_code)]
     pub fn from(samples: Vec<f32>) -> Self {
         Self::build(samples, SAMPLES_PER_BUCKET, REDUCE_BUCKETS, LEVEL_MIN_LEN)
     }
 
     pub fn build(samples: Vec<f32>, samples_per_bucket: usize, reduce_buckets: usize, level_min_len: usize) -> Self {
-        let mut level = WaveFormMipMap::from_samples(samples_per_bucket, &samples);
-        let mut mip_map = Vec::new();
-        mip_map.push(level);
+        let level = WaveFormMipMap::from_samples(samples_per_bucket, &samples);
+        Self::with_levels(WaveFormSamples::Memory(samples), level, reduce_buckets, level_min_len)
+    }
+
+    /// Builder for the finest mipmap level of a streamed channel, see `from_stream`.
+    pub fn mip_map_builder() -> WaveFormMipMapBuilder {
+        WaveFormMipMapBuilder::new(SAMPLES_PER_BUCKET)
+    }
+
+    /// Waveform that reads raw samples of `channel` from `stream` on demand.
+    pub fn from_stream(stream: &Arc<WavStream>, channel: usize, level: WaveFormMipMap) -> Self {
+        let samples = WaveFormSamples::Stream {
+            stream: stream.clone(),
+            channel,
+            frames: stream.frame_count(),
+        };
+        Self::with_levels(samples, level, REDUCE_BUCKETS, LEVEL_MIN_LEN)
+    }
+
+    /// Waveform of `frames` frames whose samples `read` computes on demand, `level` is built from the same
+    /// samples with `mip_map_builder`.
+    pub fn from_reader(
+        frames: usize,
+        level: WaveFormMipMap,
+        read: impl Fn(usize, usize) -> AudioResult<Vec<f32>> + Send + Sync + 'static,
+    ) -> Self {
+        let samples = WaveFormSamples::Derived {
+            read: Box::new(read),
+            frames,
+        };
+        Self::with_levels(samples, level, REDUCE_BUCKETS, LEVEL_MIN_LEN)
+    }
+
+    fn with_levels(
+        samples: WaveFormSamples,
+        level: WaveFormMipMap,
+        reduce_buckets: usize,
+        level_min_len: usize,
+    ) -> Self {
+        let mut mip_map = vec![level];
         while mip_map.last().unwrap().buckets_len() > level_min_len {
-            level = WaveFormMipMap::from_level(&mip_map.last().unwrap(), reduce_buckets);
+            let level = WaveFormMipMap::from_level(mip_map.last().unwrap(), reduce_buckets);
             mip_map.push(level);
         }
-        let inner = WaveFormInner { samples, mip_map };
+        let inner = WaveFormInner {
+            samples,
+            mip_map,
+            blocks: Mutex::new(WaveFormBlocks::default()),
+            spectrogram: Mutex::new(SpectrogramTiles::default()),
+        };
         Self { inner: Arc::new(inner) }
     }
 
     pub fn frames(&self) -> usize {
-        self.inner.samples.len()
+        self.inner.frames()
+    }
+
+    /// Raw samples in `start_frame..end_frame`, clamped to the waveform length.
+    pub fn read_frames(&self, start_frame: usize, end_frame: usize) -> AudioResult<Vec<f32>> {
+        self.inner.read_frames(start_frame, end_frame)
+    }
+
+    pub fn mip_map_levels(&self) -> &[WaveFormMipMap] {
+        &self.inner.mip_map
     }
 
-    pub fn samples(&self) -> &[f32] {
-        self.inner.samples.as_slice()
+    /// Coarsest mipmap level, for overviews of the whole clip.
+    pub fn overview_level(&self) -> &WaveFormMipMap {
+        self.inner.mip_map.last().unwrap()
     }
 
     pub fn first_frames_per_bucket(&self) -> usize {
@@ -84,6 +258,59 @@ impl WaveForm {
     pub fn min_max_for_frames(&self, start_frame: usize, end_frame: usize, frames_per_px: f32) -> Option<(f32, f32)> {
         self.inner.min_max_for_frames(start_frame, end_frame, frames_per_px)
     }
+
+    /// RMS of `start_frame..end_frame` from the level picked for `frames_per_px`, coarser levels round the
+    /// range out to whole buckets.
+    pub fn rms_for_frames(&self, start_frame: usize, end_frame: usize, frames_per_px: f32) -> Option<f32> {
+        self.inner.rms_for_frames(start_frame, end_frame, frames_per_px)
+    }
+
+    /// Cached spectrogram tile `index` at `hop` frames per column.
+    pub fn spectrogram_tile(
+        &self,
+        settings: SpectrogramSettings,
+        hop: usize,
+        index: usize,
+    ) -> Option<Arc<RenderImage>> {
+        let key = SpectrogramTileKey { settings, hop, index };
+        self.inner.spectrogram.lock().unwrap().get(&key)
+    }
+
+    /// True when the tile is neither cached nor rendering yet, the caller renders it.
+    pub fn request_spectrogram_tile(&self, settings: SpectrogramSettings, hop: usize, index: usize) -> bool {
+        let key = SpectrogramTileKey { settings, hop, index };
+        self.inner.spectrogram.lock().unwrap().request(key)
+    }
+
+    /// Renders a spectrogram tile into the cache, reads the channel and runs the ffts, so it belongs on a
+    /// background thread.
+    pub fn render_spectrogram_tile(&self, settings: SpectrogramSettings, hop: usize, index: usize) -> AudioResult<()> {
+        let key = SpectrogramTileKey { settings, hop, index };
+        let columns = spectrogram_columns(
+            |start, end| self.read_frames(start, end),
+            settings,
+            hop,
+            index * SPECTROGRAM_TILE_COLUMNS,
+            SPECTROGRAM_TILE_COLUMNS,
+        )?;
+        let rows = SpectrogramTiles::rows(settings);
+        let pixels = spectrogram_pixels(&columns, rows, settings.color_map);
+        let buffer = RgbaImage::from_raw(SPECTROGRAM_TILE_COLUMNS as u32, rows as u32, pixels).unwrap();
+        let image = Arc::new(RenderImage::new(vec![Frame::new(buffer)]));
+        self.inner.spectrogram.lock().unwrap().insert(key, image);
+        Ok(())
+    }
+
+    /// Tiles dropped from the spectrogram cache, to be released from the sprite atlas.
+    pub fn take_evicted_spectrogram_tiles(&self) -> Vec<Arc<RenderImage>> {
+        self.inner.spectrogram.lock().unwrap().take_evicted()
+    }
+}
+
+/// Root mean square of `samples`, 0 when empty.
+pub fn raw_rms(samples: &[f32]) -> f32 {
+    let sum_squares = samples.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    rms(sum_squares, samples.len())
 }
 
 #[cfg(test)]
@@ -95,7 +322,7 @@ mod test {
         let samples = vec![1f32; 8 * 8 * 8];
         let channel = WaveForm::build(samples.clone(), 8, 8, 8);
 
-        assert_eq!(channel.inner.samples, samples);
+        assert_eq!(channel.read_frames(0, samples.len()).unwrap(), samples);
         assert_eq!(channel.inner.mip_map.len(), 2);
         assert_eq!(channel.inner.mip_map[0].frames_per_bucket(), 8);
         assert_eq!(channel.inner.mip_map[0].buckets_len(), 8 * 8);
@@ -138,4 +365,14 @@ mod test {
         assert_eq!(min, 10.0);
         assert_eq!(max, 10.0);
     }
+
+    #[test]
+    fn test_rms_for_frames() {
+        let samples: Vec<f32> = (0..1024).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        let channel = WaveForm::build(samples, 8, 2, 4);
+
+        // raw below the finest level, buckets above it
+        assert_eq!(channel.rms_for_frames(3, 5, 1.0), Some(0.5));
+        assert_eq!(channel.rms_for_frames(0, 1024, 256.0), Some(0.5));
+    }
 }
@@ -1,17 +1,26 @@
-use crate::components::waveform::WaveForm;
+use crate::components::waveform::{
+    SampleGain, WaveForm, WaveFormAmplitude, apply_gain, gain_min_max, is_clipped, raw_rms,
+};
 use gpui::{
-    App, Bounds, ContentMask, Element, ElementId, GlobalElementId, InspectorElementId, IntoElement, LayoutId, Path,
-    Pixels, Point, Refineable, Style, StyleRefinement, Styled, Window, point, px,
+    App, Bounds, ContentMask, Element, ElementId, GlobalElementId, Hsla, InspectorElementId, IntoElement, LayoutId,
+    Path, Pixels, Point, Refineable, Style, StyleRefinement, Styled, Window, black, fill, point, px, rgba,
 };
 use gpui_component::{ActiveTheme, PixelsExt};
+use log::error;
 use std::panic::Location;
 
+// darkening of the trace colour for the RMS band drawn inside the peaks
+const RMS_SHADE: f32 = 0.35;
+
 pub struct WaveFormView {
     waveform: WaveForm,
     start_frame: usize,
     end_frame: usize,
     frames_per_px: f32,
     stroke_width_half: Pixels,
+    gain: Option<SampleGain>,
+    amplitude: WaveFormAmplitude,
+    color: Option<Hsla>,
     style: StyleRefinement,
 }
 
@@ -23,9 +32,30 @@ impl WaveFormView {
             end_frame,
             frames_per_px: frames_per_px as f32,
             stroke_width_half: px(0.5),
+            gain: None,
+            amplitude: WaveFormAmplitude::default(),
+            color: None,
             style: StyleRefinement::default(),
         }
     }
+
+    /// Draws the samples scaled by `gain`, mipmap buckets take the gain at their middle frame.
+    pub fn gain(mut self, gain: Option<SampleGain>) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Vertical zoom and scale, samples at or above full scale are marked either way.
+    pub fn amplitude(mut self, amplitude: WaveFormAmplitude) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Colour of the trace, the theme foreground when `None`.
+    pub fn color(mut self, color: Option<Hsla>) -> Self {
+        self.color = color;
+        self
+    }
 }
 
 impl Styled for WaveFormView {
@@ -44,6 +74,10 @@ impl IntoElement for WaveFormView {
 
 pub struct WaveFormPrepaintState {
     path: Option<Path<Pixels>>,
+    // only when zoomed out to min/max columns
+    rms_path: Option<Path<Pixels>>,
+    // x ranges of clipped samples
+    clips: Vec<(Pixels, Pixels)>,
 }
 
 impl Element for WaveFormView {
@@ -83,15 +117,43 @@ impl Element for WaveFormView {
         _cx: &mut App,
     ) -> Self::PrepaintState {
         let min_frames_per_px = self.waveform.first_frames_per_bucket() as f32;
-        let path = if self.frames_per_px >= min_frames_per_px {
-            self.prepaint_mipmap(bounds)
-        } else if self.frames_per_px >= 0.5 {
-            self.prepaint_samples_lines(bounds)
+        let mut clips = Vec::new();
+        let (path, rms_path) = if self.frames_per_px >= min_frames_per_px {
+            self.prepaint_min_max(bounds, &mut clips, |start, end| {
+                let min_max = self.waveform.min_max_for_frames(start, end, self.frames_per_px)?;
+                let rms = self.waveform.rms_for_frames(start, end, self.frames_per_px)?;
+                Some(match &self.gain {
+                    Some(gain) => {
+                        let gain = gain((start + end) / 2);
+                        (gain_min_max(min_max, gain), rms * gain.abs())
+                    }
+                    None => (min_max, rms),
+                })
+            })
         } else {
-            self.prepaint_samples_square(bounds)
+            // below the finest mip level the visible range is read once
+            let mut samples = match self.waveform.read_frames(self.start_frame, self.end_frame) {
+                Ok(samples) => samples,
+                Err(err) => {
+                    error!("waveform samples unavailable: {err}");
+                    Vec::new()
+                }
+            };
+            if let Some(gain) = &self.gain {
+                apply_gain(&mut samples, self.start_frame, gain.as_ref());
+            }
+            if self.frames_per_px >= 2.0 {
+                self.prepaint_min_max(bounds, &mut clips, |start, end| {
+                    raw_min_max_rms(&samples, start - self.start_frame, end - self.start_frame)
+                })
+            } else if self.frames_per_px >= 0.5 {
+                (self.prepaint_samples_lines(bounds, &samples, &mut clips), None)
+            } else {
+                (self.prepaint_samples_square(bounds, &samples, &mut clips), None)
+            }
         };
 
-        WaveFormPrepaintState { path }
+        WaveFormPrepaintState { path, rms_path, clips }
     }
 
     fn paint(
@@ -110,10 +172,18 @@ impl Element for WaveFormView {
             let theme = cx.theme();
             window.with_text_style(style.text_style().cloned(), |window| {
                 window.with_content_mask(Some(ContentMask { bounds }), |window| {
-                    let color = theme.primary_foreground;
+                    for (left, right) in prepaint.clips.drain(..) {
+                        let clip_bounds =
+                            Bounds::from_corners(point(left, bounds.top()), point(right, bounds.bottom()));
+                        window.paint_quad(fill(clip_bounds, rgba(0xE5484D66)));
+                    }
+                    let color = self.color.unwrap_or(theme.primary_foreground);
                     if let Some(path) = prepaint.path.take() {
                         window.paint_path(path, color);
                     }
+                    if let Some(path) = prepaint.rms_path.take() {
+                        window.paint_path(path, color.blend(black().alpha(RMS_SHADE)));
+                    }
                 })
             })
         });
@@ -124,16 +194,17 @@ impl WaveFormView {
     const STP: Point<f32> = point(0., 1.);
     const ST: (Point<f32>, Point<f32>, Point<f32>) = (Self::STP, Self::STP, Self::STP);
 
-    fn prepaint_samples_square(&self, bounds: Bounds<Pixels>) -> Option<Path<Pixels>> {
+    fn prepaint_samples_square(
+        &self,
+        bounds: Bounds<Pixels>,
+        samples: &[f32],
+        clips: &mut Vec<(Pixels, Pixels)>,
+    ) -> Option<Path<Pixels>> {
         let mid_y = bounds.center().y;
         let amp = bounds.size.height * 0.5;
         let frame_width = px(1.0 / self.frames_per_px);
 
-        let start_frame = self.start_frame.min(self.waveform.samples().len().saturating_sub(1));
-        let end_frame = self.end_frame.max(start_frame).min(self.waveform.samples().len());
-
-        let range = start_frame..end_frame;
-        let samples = self.waveform.samples()[range].iter().enumerate();
+        let samples = samples.iter().enumerate();
 
         let st = (point(0., 1.), point(0., 1.), point(0., 1.));
 
@@ -142,8 +213,11 @@ impl WaveFormView {
         let mut prev: Option<Point<Pixels>> = None;
 
         for (frame, sample) in samples {
-            let y = mid_y - amp * sample.clamp(-1.0, 1.0);
+            let y = mid_y - amp * self.amplitude.height(*sample);
             let x = bounds.left() + (frame_width * frame);
+            if is_clipped(*sample) {
+                push_clip(clips, x, x + frame_width);
+            }
 
             if let Some(prev) = prev {
                 // draw vertical line
@@ -168,24 +242,29 @@ impl WaveFormView {
         Some(path)
     }
 
-    fn prepaint_samples_lines(&self, bounds: Bounds<Pixels>) -> Option<Path<Pixels>> {
+    fn prepaint_samples_lines(
+        &self,
+        bounds: Bounds<Pixels>,
+        samples: &[f32],
+        clips: &mut Vec<(Pixels, Pixels)>,
+    ) -> Option<Path<Pixels>> {
         let mid_y = bounds.center().y;
         let amp = bounds.size.height * 0.5;
         let frame_width = px(1.0 / self.frames_per_px);
 
-        let start_frame = self.start_frame.min(self.waveform.samples().len().saturating_sub(1));
-        let end_frame = self.end_frame.max(start_frame).min(self.waveform.samples().len());
-        let range = start_frame..end_frame;
-        let samples = self.waveform.samples()[range].iter().enumerate();
+        let samples = samples.iter().enumerate();
 
         // tesselation requires too much time, so manually
         let mut path = Path::new(bounds.origin);
         let mut prev: Option<Point<Pixels>> = None;
 
         for (frame, sample) in samples {
-            let y = mid_y - amp * sample.clamp(-1.0, 1.0);
+            let y = mid_y - amp * self.amplitude.height(*sample);
             let x = bounds.left() + (frame_width * frame);
             let curr = point(x, y);
+            if is_clipped(*sample) {
+                push_clip(clips, x - px(1.0), x + px(1.0));
+            }
 
             if let Some(prev) = prev {
                 self.push_line_as_triangles(&mut path, prev, curr);
@@ -196,7 +275,13 @@ impl WaveFormView {
         Some(path)
     }
 
-    fn prepaint_mipmap(&self, bounds: Bounds<Pixels>) -> Option<Path<Pixels>> {
+    // peak band and the RMS band inside it, from ((min, max), rms) of each column
+    fn prepaint_min_max(
+        &self,
+        bounds: Bounds<Pixels>,
+        clips: &mut Vec<(Pixels, Pixels)>,
+        min_max_rms_for_frames: impl Fn(usize, usize) -> Option<((f32, f32), f32)>,
+    ) -> (Option<Path<Pixels>>, Option<Path<Pixels>>) {
         let mid_y = bounds.center().y;
         let amp = bounds.size.height * 0.5;
         let width = bounds.size.width.as_f64().round().max(1.0) as usize;
@@ -205,41 +290,67 @@ impl WaveFormView {
 
         // tesselation requires too much time, so manually
         let mut path = Path::new(bounds.origin);
-        let mut prev: Option<(f32, f32)> = None;
+        let mut rms_path = Path::new(bounds.origin);
+        let mut prev: Option<(f32, f32, f32, f32)> = None;
         for x in 0..width {
             let start_frame = frame_offset + (x as f32 * self.frames_per_px).round() as usize;
             let end_frame = frame_offset + ((x + 1) as f32 * self.frames_per_px).round() as usize;
 
-            if let Some((min, max)) = self
-                .waveform
-                .min_max_for_frames(start_frame, end_frame, self.frames_per_px)
-            {
-                if let Some((prev_min, prev_max)) = prev {
-                    let left = bounds.left() + px(x as f32 - 1.0) - self.stroke_width_half;
-                    let right = bounds.left() + px(x as f32) + self.stroke_width_half;
-
-                    let left_max = mid_y - amp * prev_max.clamp(-1.0, 1.0) - self.stroke_width_half;
-                    let left_min = mid_y - amp * prev_min.clamp(-1.0, 1.0) - self.stroke_width_half;
-
-                    let right_max = mid_y - amp * max.clamp(-1.0, 1.0) + self.stroke_width_half;
-                    let right_min = mid_y - amp * min.clamp(-1.0, 1.0) + self.stroke_width_half;
-
-                    // top triangle
-                    path.push_triangle(
-                        (point(left, left_min), point(left, left_max), point(right, right_max)),
-                        Self::ST,
-                    );
-                    // bottom triangle
-                    path.push_triangle(
-                        (point(left, left_min), point(right, right_max), point(right, right_min)),
-                        Self::ST,
+            if let Some(((min, max), rms)) = min_max_rms_for_frames(start_frame, end_frame) {
+                if is_clipped(min) || is_clipped(max) {
+                    push_clip(clips, bounds.left() + px(x as f32), bounds.left() + px(x as f32 + 1.0));
+                }
+                // the RMS band stays within the peaks, signals with an offset may not straddle zero
+                let (rms_min, rms_max) = ((-rms).clamp(min, max), rms.clamp(min, max));
+                let (min, max) = (self.amplitude.height(min), self.amplitude.height(max));
+                let (rms_min, rms_max) = (self.amplitude.height(rms_min), self.amplitude.height(rms_max));
+                if let Some((prev_min, prev_max, prev_rms_min, prev_rms_max)) = prev {
+                    let left = bounds.left() + px(x as f32 - 1.0);
+                    let right = bounds.left() + px(x as f32);
+                    self.push_band(&mut path, mid_y, amp, (left, prev_min, prev_max), (right, min, max));
+                    self.push_band(
+                        &mut rms_path,
+                        mid_y,
+                        amp,
+                        (left, prev_rms_min, prev_rms_max),
+                        (right, rms_min, rms_max),
                     );
                 }
 
-                prev = Some((min, max));
+                prev = Some((min, max, rms_min, rms_max));
             }
         }
-        Some(path)
+        (Some(path), Some(rms_path))
+    }
+
+    // quad between the columns at `left` and `right`, heights widened by the stroke
+    fn push_band(
+        &self,
+        path: &mut Path<Pixels>,
+        mid_y: Pixels,
+        amp: Pixels,
+        (left, left_min, left_max): (Pixels, f32, f32),
+        (right, right_min, right_max): (Pixels, f32, f32),
+    ) {
+        let left = left - self.stroke_width_half;
+        let right = right + self.stroke_width_half;
+
+        let left_max = mid_y - amp * left_max - self.stroke_width_half;
+        let left_min = mid_y - amp * left_min - self.stroke_width_half;
+
+        let right_max = mid_y - amp * right_max + self.stroke_width_half;
+        let right_min = mid_y - amp * right_min + self.stroke_width_half;
+
+        // top triangle
+        path.push_triangle(
+            (point(left, left_min), point(left, left_max), point(right, right_max)),
+            Self::ST,
+        );
+        // bottom triangle
+        path.push_triangle(
+            (point(left, left_min), point(right, right_max), point(right, right_min)),
+            Self::ST,
+        );
     }
 
     fn push_line_as_triangles(&self, path: &mut Path<Pixels>, a: Point<Pixels>, b: Point<Pixels>) {
@@ -266,3 +377,21 @@ impl WaveFormView {
         path.push_triangle((p2, p1, p3), Self::ST);
     }
 }
+
+// adjacent clipped samples merge into one range
+fn push_clip(clips: &mut Vec<(Pixels, Pixels)>, left: Pixels, right: Pixels) {
+    match clips.last_mut() {
+        Some((_, last_right)) if *last_right >= left => *last_right = right.max(*last_right),
+        _ => clips.push((left, right)),
+    }
+}
+
+fn raw_min_max_rms(samples: &[f32], start: usize, end: usize) -> Option<((f32, f32), f32)> {
+    let end = end.min(samples.len());
+    if start >= end {
+        return None;
+    }
+    let min = samples[start..end].iter().fold(f32::INFINITY, |min, s| min.min(*s));
+    let max = samples[start..end].iter().fold(f32::NEG_INFINITY, |max, s| max.max(*s));
+    Some(((min, max), raw_rms(&samples[start..end])))
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+/// Linear gain for a clip frame, applied to samples before they are drawn, played or compared.
+pub type SampleGain = Arc<dyn Fn(usize) -> f32 + Send + Sync>;
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Multiplies samples starting at frame `start_frame` by their gain.
+pub fn apply_gain(samples: &mut [f32], start_frame: usize, gain: impl Fn(usize) -> f32) {
+    for (offset, sample) in samples.iter_mut().enumerate() {
+        *sample *= gain(start_frame + offset);
+    }
+}
+
+/// Min/max of a frame range after gain, a negative gain swaps them.
+pub fn gain_min_max((min, max): (f32, f32), gain: f32) -> (f32, f32) {
+    match gain < 0.0 {
+        true => (max * gain, min * gain),
+        false => (min * gain, max * gain),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_db_to_gain() {
+        assert_eq!(db_to_gain(0.0), 1.0);
+        assert!((db_to_gain(-6.0206) - 0.5).abs() < 1e-4);
+        assert!((db_to_gain(20.0) - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_gain_min_max_inverted() {
+        assert_eq!(gain_min_max((-0.5, 1.0), 2.0), (-1.0, 2.0));
+        assert_eq!(gain_min_max((-0.5, 1.0), -1.0), (-1.0, 0.5));
+    }
+}
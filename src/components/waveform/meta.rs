@@ -46,9 +46,25 @@ pub struct WaveClipMetadata {
     dc_offset_percent: f64,
 }
 
+/// Figures that take a pass over every sample, the rest of the metadata follows from the spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaveClipStatistics {
+    pub sample_count: usize,
+    pub peak_dbfs: f64,
+    pub rms_dbfs: f64,
+    pub integrated_lufs: f64,
+    pub dc_offset_percent: f64,
+}
+
 #[allow(dead_code)]
 impl WaveClipMetadata {
     pub fn new(filepath: &Path, spec: WavSpec, samples: &Vec<f32>) -> Result<Self> {
+        let mut builder = WaveClipMetadataBuilder::new(spec);
+        builder.push(samples);
+        builder.build(filepath)
+    }
+
+    pub fn from_statistics(filepath: &Path, spec: WavSpec, statistics: WaveClipStatistics) -> Result<Self> {
         let filename = filepath
             .file_name()
             .ok_or(anyhow!("no filename"))?
@@ -58,24 +74,36 @@ impl WaveClipMetadata {
 
         let filepath = filepath.as_os_str().to_string_lossy().into_owned().into();
 
-        let provider = WaveClipMetadataProvider { spec: &spec, samples };
-
-        Ok(Self {
+        let frame_count = statistics.sample_count / (spec.channels as usize).max(1);
+        let duration_millis = (frame_count as f64 * 1000.0 / spec.sample_rate as f64) as u64;
+        let bitrate_kbps =
+            ((spec.sample_rate as f64 * spec.channels as f64 * spec.bits_per_sample as f64) / 1000.0).round() as u64;
+        Ok(WaveClipMetadata {
             filename,
             filepath,
             spec,
-            sample_count: samples.len(),
-            frame_count: provider.frame_count(),
-            bitrate_kbps: provider.bitrate_kbps(),
-            duration_millis: provider.duration_millis(),
-            peak_dbfs: provider.peak_dbfs(),
-            rms_dbfs: provider.rms_dbfs(),
-            integrated_lufs: provider.integrated_lufs(),
-            crest_factor_db: provider.crest_factor_db(),
-            dc_offset_percent: provider.dc_offset_percent(),
+            sample_count: statistics.sample_count,
+            frame_count,
+            bitrate_kbps,
+            duration_millis,
+            peak_dbfs: statistics.peak_dbfs,
+            rms_dbfs: statistics.rms_dbfs,
+            integrated_lufs: statistics.integrated_lufs,
+            crest_factor_db: statistics.peak_dbfs - statistics.rms_dbfs,
+            dc_offset_percent: statistics.dc_offset_percent,
         })
     }
 
+    pub fn statistics(&self) -> WaveClipStatistics {
+        WaveClipStatistics {
+            sample_count: self.sample_count,
+            peak_dbfs: self.peak_dbfs,
+            rms_dbfs: self.rms_dbfs,
+            integrated_lufs: self.integrated_lufs,
+            dc_offset_percent: self.dc_offset_percent,
+        }
+    }
+
     #[allow(dead_code)]
     pub fn info(&self) -> Vec<WaveClipParameter> {
         vec![
@@ -143,73 +171,101 @@ impl WaveClipMetadata {
     }
 }
 
-struct WaveClipMetadataProvider<'a> {
-    spec: &'a WavSpec,
-    samples: &'a Vec<f32>,
+/// Accumulates clip statistics over interleaved chunks so the samples never need to be held at once.
+pub struct WaveClipMetadataBuilder {
+    spec: WavSpec,
+    sample_count: usize,
+    peak_amplitude: f32,
+    power_sum: f64,
+    sum: f64,
+    hop_frames: usize,
+    hop_power_sum: f64,
+    hop_frames_filled: usize,
+    // power sums of complete 100 ms hops, a 400 ms loudness block spans 4 of them
+    hop_powers: Vec<f64>,
 }
 
-impl<'a> WaveClipMetadataProvider<'a> {
-    fn frame_count(&self) -> usize {
-        self.samples.len() / self.spec.channels as usize
+impl WaveClipMetadataBuilder {
+    pub fn new(spec: WavSpec) -> Self {
+        Self {
+            spec,
+            sample_count: 0,
+            peak_amplitude: 0.0,
+            power_sum: 0.0,
+            sum: 0.0,
+            hop_frames: ((spec.sample_rate as f64 * 0.1).round() as usize).max(1),
+            hop_power_sum: 0.0,
+            hop_frames_filled: 0,
+            hop_powers: Vec::new(),
+        }
     }
 
-    fn duration_millis(&self) -> u64 {
-        let millis = self.frame_count() as f64 * 1000.0 / self.spec.sample_rate as f64;
-        millis as u64
+    pub fn push(&mut self, samples: &[f32]) {
+        let channels = (self.spec.channels as usize).max(1);
+        for frame in samples.chunks_exact(channels) {
+            let mut frame_power = 0.0_f64;
+            for sample in frame {
+                let sample = *sample as f64;
+                self.peak_amplitude = self.peak_amplitude.max(sample.abs() as f32);
+                self.sum += sample;
+                frame_power += sample * sample;
+            }
+            self.sample_count += frame.len();
+            self.power_sum += frame_power;
+            self.hop_power_sum += frame_power;
+            self.hop_frames_filled += 1;
+            if self.hop_frames_filled == self.hop_frames {
+                self.hop_powers.push(self.hop_power_sum);
+                self.hop_power_sum = 0.0;
+                self.hop_frames_filled = 0;
+            }
+        }
     }
 
-    fn peak_amplitude(&self) -> f32 {
-        self.samples.iter().fold(0.0_f32, |max, sample| max.max(sample.abs()))
+    pub fn build(self, filepath: &Path) -> Result<WaveClipMetadata> {
+        let statistics = WaveClipStatistics {
+            sample_count: self.sample_count,
+            peak_dbfs: self.peak_dbfs(),
+            rms_dbfs: self.rms_dbfs(),
+            integrated_lufs: self.integrated_lufs(),
+            dc_offset_percent: self.dc_offset_percent(),
+        };
+        WaveClipMetadata::from_statistics(filepath, self.spec, statistics)
+    }
+
+    fn frame_count(&self) -> usize {
+        self.sample_count / (self.spec.channels as usize).max(1)
     }
 
     fn peak_dbfs(&self) -> f64 {
-        Self::amplitude_to_dbfs(self.peak_amplitude() as f64)
+        Self::amplitude_to_dbfs(self.peak_amplitude as f64)
     }
 
     fn rms_amplitude(&self) -> f64 {
-        if self.samples.is_empty() {
+        if self.sample_count == 0 {
             return 0.0;
         }
-        let power_sum = self
-            .samples
-            .iter()
-            .fold(0.0_f64, |sum, sample| sum + (*sample as f64) * (*sample as f64));
-        (power_sum / self.samples.len() as f64).sqrt()
+        (self.power_sum / self.sample_count as f64).sqrt()
     }
 
     fn rms_dbfs(&self) -> f64 {
         Self::amplitude_to_dbfs(self.rms_amplitude())
     }
 
-    fn crest_factor_db(&self) -> f64 {
-        self.peak_dbfs() - self.rms_dbfs()
-    }
-
     /// Integrated loudness estimate in LUFS.
     /// Uses BS.1770-style 400 ms blocks with absolute/relative gating,
     /// but without K-weighting filter.
     fn integrated_lufs(&self) -> f64 {
-        let frames = self.frame_count();
-        if frames == 0 || self.spec.sample_rate == 0 {
+        if self.frame_count() == 0 || self.spec.sample_rate == 0 {
             return f64::NEG_INFINITY;
         }
 
-        let block_frames = ((self.spec.sample_rate as f64 * 0.4).round() as usize).max(1);
-        let hop_frames = ((self.spec.sample_rate as f64 * 0.1).round() as usize).max(1);
-
-        let mut block_powers = Vec::new();
-        let mut start = 0usize;
-        while start + block_frames <= frames {
-            let mut power_sum = 0.0_f64;
-            for frame in start..(start + block_frames) {
-                let i = frame * self.spec.channels as usize;
-                let l = self.samples[i] as f64;
-                let r = self.samples[i + 1] as f64;
-                power_sum += l * l + r * r;
-            }
-            block_powers.push(power_sum / block_frames as f64);
-            start += hop_frames;
-        }
+        let block_frames = (self.hop_frames * 4) as f64;
+        let block_powers: Vec<f64> = self
+            .hop_powers
+            .windows(4)
+            .map(|hops| hops.iter().sum::<f64>() / block_frames)
+            .collect();
 
         if block_powers.is_empty() {
             return f64::NEG_INFINITY;
@@ -239,16 +295,10 @@ impl<'a> WaveClipMetadataProvider<'a> {
     }
 
     fn dc_offset_percent(&self) -> f64 {
-        if self.samples.is_empty() {
+        if self.sample_count == 0 {
             return 0.0;
         }
-        let sum = self.samples.iter().fold(0.0_f64, |acc, sample| acc + *sample as f64);
-        (sum / self.samples.len() as f64).abs() * 100.0
-    }
-
-    fn bitrate_kbps(&self) -> u64 {
-        ((self.spec.sample_rate as f64 * self.spec.channels as f64 * self.spec.bits_per_sample as f64) / 1000.0).round()
-            as u64
+        (self.sum / self.sample_count as f64).abs() * 100.0
     }
 
     fn amplitude_to_dbfs(amplitude: f64) -> f64 {
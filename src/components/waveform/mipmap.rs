@@ -1,8 +1,10 @@
-use crate::components::waveform::bucket::WaveFormBucket;
+use crate::components::waveform::bucket::{WaveFormBucket, rms};
 
 // read-only structure
 pub struct WaveFormMipMap {
     frames_per_bucket: usize,
+    // frames covered, every bucket but the last holds `frames_per_bucket` of them
+    frames: usize,
     buckets: Vec<WaveFormBucket>,
 }
 
@@ -15,6 +17,7 @@ impl WaveFormMipMap {
 
         Self {
             frames_per_bucket,
+            frames: samples.len(),
             buckets,
         }
     }
@@ -30,6 +33,16 @@ impl WaveFormMipMap {
 
         Self {
             frames_per_bucket,
+            frames: level.frames,
+            buckets,
+        }
+    }
+
+    /// Level restored from buckets built earlier, see `PeakCache`.
+    pub fn from_buckets(frames_per_bucket: usize, frames: usize, buckets: Vec<WaveFormBucket>) -> Self {
+        Self {
+            frames_per_bucket,
+            frames,
             buckets,
         }
     }
@@ -38,6 +51,14 @@ impl WaveFormMipMap {
         self.frames_per_bucket
     }
 
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn buckets(&self) -> &[WaveFormBucket] {
+        &self.buckets
+    }
+
     pub fn buckets_len(&self) -> usize {
         self.buckets.len()
     }
@@ -64,6 +85,63 @@ impl WaveFormMipMap {
 
         Some((min, max))
     }
+
+    /// RMS of the buckets covering `start_frame..end_frame`.
+    pub fn rms_for_frames(&self, start_frame: usize, end_frame: usize) -> Option<f32> {
+        let bucket_start = start_frame / self.frames_per_bucket;
+        let bucket_end = end_frame.div_ceil(self.frames_per_bucket).min(self.buckets.len());
+        if start_frame >= end_frame || bucket_start >= bucket_end {
+            return None;
+        }
+
+        let buckets = &self.buckets[bucket_start..bucket_end];
+        let sum_squares = buckets.iter().map(|bucket| bucket.sum_squares as f64).sum();
+        let frames = (bucket_end * self.frames_per_bucket).min(self.frames) - bucket_start * self.frames_per_bucket;
+        Some(rms(sum_squares, frames))
+    }
+}
+
+/// Builds the finest level while samples are streamed in, without keeping them.
+pub struct WaveFormMipMapBuilder {
+    frames_per_bucket: usize,
+    frames: usize,
+    buckets: Vec<WaveFormBucket>,
+    current: WaveFormBucket,
+    current_frames: usize,
+}
+
+impl WaveFormMipMapBuilder {
+    pub fn new(frames_per_bucket: usize) -> Self {
+        Self {
+            frames_per_bucket,
+            frames: 0,
+            buckets: Vec::new(),
+            current: WaveFormBucket::empty(),
+            current_frames: 0,
+        }
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        self.current.push_sample(sample);
+        self.current_frames += 1;
+        self.frames += 1;
+        if self.current_frames == self.frames_per_bucket {
+            let bucket = std::mem::replace(&mut self.current, WaveFormBucket::empty());
+            self.buckets.push(bucket);
+            self.current_frames = 0;
+        }
+    }
+
+    pub fn finish(mut self) -> WaveFormMipMap {
+        if self.current_frames > 0 {
+            self.buckets.push(self.current);
+        }
+        WaveFormMipMap {
+            frames_per_bucket: self.frames_per_bucket,
+            frames: self.frames,
+            buckets: self.buckets,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +208,24 @@ mod test {
         assert_eq!(level.buckets[4].max, 9f32);
     }
 
+    #[test]
+    fn test_builder_matches_from_samples() {
+        let samples = vec![0., 1., 2., 3., 4., 5., 6., 7., 8., 9.];
+        let mut builder = WaveFormMipMapBuilder::new(3);
+        samples.iter().for_each(|s| builder.push(*s));
+        let actual = builder.finish();
+        let expected = WaveFormMipMap::from_samples(3, &samples);
+
+        assert_eq!(actual.frames_per_bucket, expected.frames_per_bucket);
+        assert_eq!(actual.frames, expected.frames);
+        assert_eq!(actual.buckets.len(), expected.buckets.len());
+        for (a, e) in actual.buckets.iter().zip(expected.buckets.iter()) {
+            assert_eq!(a.min, e.min);
+            assert_eq!(a.max, e.max);
+            assert_eq!(a.sum_squares, e.sum_squares);
+        }
+    }
+
     #[test]
     fn test_min_max_for_frames() {
         let samples = vec![0., 1., 2., 3., 4., 5., 6., 7., 8., 9.];
@@ -139,4 +235,23 @@ mod test {
         assert_eq!(min, 2.0);
         assert_eq!(max, 9.0);
     }
+
+    #[test]
+    fn test_rms_for_frames() {
+        let samples = vec![1., -1., 1., -1., 0.5, -0.5, 0.5, -0.5];
+        let level = WaveFormMipMap::from_samples(2, &samples);
+        let reduced = WaveFormMipMap::from_level(&level, 2);
+
+        assert_eq!(level.rms_for_frames(0, 4), Some(1.0));
+        assert_eq!(reduced.rms_for_frames(4, 8), Some(0.5));
+        // squares add up across levels, the mean is not of the bucket RMS values
+        let expected = (1.25f32 / 2.0).sqrt();
+        assert!((reduced.rms_for_frames(0, 8).unwrap() - expected).abs() < 1e-6);
+        assert_eq!(level.rms_for_frames(8, 10), None);
+
+        // the last bucket holds only the frames left over
+        let partial = WaveFormMipMap::from_samples(2, &vec![1., 1., 0.5]);
+        assert_eq!(partial.rms_for_frames(2, 3), Some(0.5));
+        assert_eq!(WaveFormMipMap::from_level(&partial, 2).frames(), 3);
+    }
 }
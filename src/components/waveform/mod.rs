@@ -1,22 +1,30 @@
+mod align;
+mod amplitude;
 mod bucket;
 mod clip;
 mod clip_view;
+mod export;
 mod form;
 mod form_view;
+mod gain;
 mod meta;
 mod mipmap;
+mod peak_cache;
+mod spectrogram;
+mod spectrogram_view;
+mod spectrum;
 
-#[allow(unused_imports)]
+pub use align::*;
+pub use amplitude::*;
 pub use bucket::*;
-#[allow(unused_imports)]
 pub use clip::*;
-#[allow(unused_imports)]
 pub use clip_view::*;
-#[allow(unused_imports)]
+pub use export::*;
 pub use form::*;
-#[allow(unused_imports)]
 pub use form_view::*;
-#[allow(unused_imports)]
+pub use gain::*;
 pub use meta::*;
-#[allow(unused_imports)]
 pub use mipmap::*;
+pub use spectrogram::*;
+pub use spectrogram_view::*;
+pub use spectrum::*;
@@ -0,0 +1,320 @@
+use crate::components::waveform::{WaveClipStatistics, WaveFormBucket, WaveFormMipMap};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Directory of the peak cache, empty or `off` disables it.
+pub const PEAK_CACHE_ENV: &str = "UNRECORD_PEAK_CACHE";
+
+const PEAK_CACHE_MAGIC: &[u8; 8] = b"URPEAK02";
+const PEAK_CACHE_EXTENSION: &str = "peaks";
+// bytes hashed from each end of the file, the size and mtime catch changes in between
+const HASH_SPAN_BYTES: u64 = 1 << 20;
+
+/// Identity of an audio file, a cached entry is used only while all of it still matches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeakCacheKey {
+    path: String,
+    size: u64,
+    modified_nanos: u64,
+    content_hash: u64,
+}
+
+impl PeakCacheKey {
+    pub fn of(path: &Path) -> Result<Self> {
+        let path = path.canonicalize().with_context(|| format!("resolve {path:?}"))?;
+        let mut file = File::open(&path).with_context(|| format!("open {path:?}"))?;
+        let metadata = file.metadata()?;
+        let modified_nanos = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+        let size = metadata.len();
+
+        let mut hash = Fnv::new();
+        let mut head = Vec::new();
+        (&mut file).take(HASH_SPAN_BYTES).read_to_end(&mut head)?;
+        hash.write(&head);
+        // files up to twice the span are hashed whole
+        if size > HASH_SPAN_BYTES * 2 {
+            file.seek(SeekFrom::End(-(HASH_SPAN_BYTES as i64)))?;
+        }
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+        hash.write(&tail);
+
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            size,
+            modified_nanos,
+            content_hash: hash.finish(),
+        })
+    }
+}
+
+/// Finest mipmap level of every channel and the clip statistics, the coarser levels are rebuilt from them.
+pub struct PeakCacheEntry {
+    pub statistics: WaveClipStatistics,
+    pub levels: Vec<WaveFormMipMap>,
+}
+
+/// Sidecar files with the peaks of opened clips, one per audio file path.
+#[derive(Clone, Debug)]
+pub struct PeakCache {
+    dir: PathBuf,
+}
+
+impl PeakCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache in the directory named by `UNRECORD_PEAK_CACHE`, the user cache directory when it is unset.
+    pub fn from_env() -> Option<Self> {
+        match std::env::var_os(PEAK_CACHE_ENV) {
+            Some(dir) if dir.is_empty() || dir == "off" => None,
+            Some(dir) => Some(Self::new(dir)),
+            None => Some(Self::new(Self::default_dir())),
+        }
+    }
+
+    pub fn default_dir() -> PathBuf {
+        match std::env::var_os("HOME") {
+            Some(home) => Path::new(&home).join("Library/Caches/unrecord/peaks"),
+            None => std::env::temp_dir().join("unrecord-peaks"),
+        }
+    }
+
+    /// Entry stored for `key`, `None` when missing, unreadable or stored for another version of the file.
+    pub fn load(&self, key: &PeakCacheKey) -> Option<PeakCacheEntry> {
+        let bytes = std::fs::read(self.entry_path(key)).ok()?;
+        let mut reader = EntryReader { bytes: &bytes };
+        if reader.take(PEAK_CACHE_MAGIC.len())? != PEAK_CACHE_MAGIC {
+            return None;
+        }
+        if reader.key()? != *key {
+            return None;
+        }
+        reader.entry()
+    }
+
+    /// Writes the entry for `key`, replacing the one of any earlier version of the file.
+    pub fn store(&self, key: &PeakCacheKey, entry: &PeakCacheEntry) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("create {:?}", self.dir))?;
+        let mut bytes = PEAK_CACHE_MAGIC.to_vec();
+        write_key(&mut bytes, key);
+        write_entry(&mut bytes, entry);
+
+        // written aside and renamed so a concurrent open never reads half an entry
+        let path = self.entry_path(key);
+        let partial = path.with_extension(format!("{PEAK_CACHE_EXTENSION}.partial"));
+        std::fs::write(&partial, &bytes).with_context(|| format!("write {partial:?}"))?;
+        std::fs::rename(&partial, &path).with_context(|| format!("rename {partial:?}"))
+    }
+
+    // named after the path only, so a changed file overwrites its stale entry
+    fn entry_path(&self, key: &PeakCacheKey) -> PathBuf {
+        let mut hash = Fnv::new();
+        hash.write(key.path.as_bytes());
+        self.dir.join(format!("{:016x}.{PEAK_CACHE_EXTENSION}", hash.finish()))
+    }
+}
+
+fn write_key(bytes: &mut Vec<u8>, key: &PeakCacheKey) {
+    bytes.extend((key.path.len() as u64).to_le_bytes());
+    bytes.extend(key.path.as_bytes());
+    bytes.extend(key.size.to_le_bytes());
+    bytes.extend(key.modified_nanos.to_le_bytes());
+    bytes.extend(key.content_hash.to_le_bytes());
+}
+
+fn write_entry(bytes: &mut Vec<u8>, entry: &PeakCacheEntry) {
+    let statistics = &entry.statistics;
+    bytes.extend((statistics.sample_count as u64).to_le_bytes());
+    for value in [
+        statistics.peak_dbfs,
+        statistics.rms_dbfs,
+        statistics.integrated_lufs,
+        statistics.dc_offset_percent,
+    ] {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend((entry.levels.len() as u64).to_le_bytes());
+    // 12 bytes a bucket, the level keeps the frame total the bucket frame counts follow from
+    for level in &entry.levels {
+        bytes.extend((level.frames_per_bucket() as u64).to_le_bytes());
+        bytes.extend((level.frames() as u64).to_le_bytes());
+        bytes.extend((level.buckets_len() as u64).to_le_bytes());
+        for bucket in level.buckets() {
+            bytes.extend(bucket.min.to_le_bytes());
+            bytes.extend(bucket.max.to_le_bytes());
+            bytes.extend(bucket.sum_squares.to_le_bytes());
+        }
+    }
+}
+
+struct EntryReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> EntryReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.bytes.len() {
+            return None;
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(head)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    // a length that cannot fit in the remaining bytes means a damaged file, checked before allocating
+    fn len(&mut self, item_bytes: usize) -> Option<usize> {
+        let len = self.u64()? as usize;
+        (len.checked_mul(item_bytes)? <= self.bytes.len()).then_some(len)
+    }
+
+    fn key(&mut self) -> Option<PeakCacheKey> {
+        let path_len = self.len(1)?;
+        let path = String::from_utf8(self.take(path_len)?.to_vec()).ok()?;
+        Some(PeakCacheKey {
+            path,
+            size: self.u64()?,
+            modified_nanos: self.u64()?,
+            content_hash: self.u64()?,
+        })
+    }
+
+    fn entry(&mut self) -> Option<PeakCacheEntry> {
+        let statistics = WaveClipStatistics {
+            sample_count: self.u64()? as usize,
+            peak_dbfs: self.f64()?,
+            rms_dbfs: self.f64()?,
+            integrated_lufs: self.f64()?,
+            dc_offset_percent: self.f64()?,
+        };
+        let level_count = self.len(24)?;
+        let mut levels = Vec::with_capacity(level_count);
+        for _ in 0..level_count {
+            let frames_per_bucket = self.u64()? as usize;
+            let frames = self.u64()? as usize;
+            let bucket_count = self.len(12)?;
+            if frames_per_bucket == 0 || frames.div_ceil(frames_per_bucket) != bucket_count {
+                return None;
+            }
+            let mut buckets = Vec::with_capacity(bucket_count);
+            for _ in 0..bucket_count {
+                buckets.push(WaveFormBucket {
+                    min: self.f32()?,
+                    max: self.f32()?,
+                    sum_squares: self.f32()?,
+                });
+            }
+            levels.push(WaveFormMipMap::from_buckets(frames_per_bucket, frames, buckets));
+        }
+        self.bytes.is_empty().then_some(PeakCacheEntry { statistics, levels })
+    }
+}
+
+// FNV-1a, stable across builds unlike the std hasher
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // unique per test run and call, parallel and concurrent runs never share files
+    fn test_dir(name: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("unrecord_test_{name}_{}_{count}", std::process::id()))
+    }
+
+    fn entry() -> PeakCacheEntry {
+        let samples = vec![0.5, -0.25, 1.0, 0.0, -1.0];
+        PeakCacheEntry {
+            statistics: WaveClipStatistics {
+                sample_count: samples.len(),
+                peak_dbfs: 0.0,
+                rms_dbfs: -3.5,
+                integrated_lufs: f64::NEG_INFINITY,
+                dc_offset_percent: 2.5,
+            },
+            levels: vec![WaveFormMipMap::from_samples(2, &samples)],
+        }
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = test_dir("peak_cache");
+        let audio = dir.join("audio.wav");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&audio, [1u8; 64]).unwrap();
+        let cache = PeakCache::new(dir.join("peaks"));
+        let key = PeakCacheKey::of(&audio).unwrap();
+        assert!(cache.load(&key).is_none());
+
+        cache.store(&key, &entry()).unwrap();
+        let loaded = cache.load(&key).unwrap();
+        assert_eq!(loaded.statistics, entry().statistics);
+        assert_eq!(loaded.levels.len(), 1);
+        assert_eq!(loaded.levels[0].frames_per_bucket(), 2);
+        assert_eq!(loaded.levels[0].buckets_len(), 3);
+        assert_eq!(loaded.levels[0].min_max_for_frames(0, 5), Some((-1.0, 1.0)));
+        assert_eq!(loaded.levels[0].rms_for_frames(4, 5), Some(1.0));
+        assert_eq!(loaded.levels[0].frames(), 5);
+
+        // same size and possibly the same mtime, the content hash tells them apart
+        std::fs::write(&audio, [2u8; 64]).unwrap();
+        let changed = PeakCacheKey::of(&audio).unwrap();
+        assert_ne!(changed, key);
+        assert!(cache.load(&changed).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rejects_damaged_entry() {
+        let dir = test_dir("peak_cache_damaged");
+        let audio = dir.join("audio.wav");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&audio, [1u8; 64]).unwrap();
+        let cache = PeakCache::new(dir.join("peaks"));
+        let key = PeakCacheKey::of(&audio).unwrap();
+        cache.store(&key, &entry()).unwrap();
+
+        let path = cache.entry_path(&key);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        assert!(cache.load(&key).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
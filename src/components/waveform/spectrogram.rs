@@ -0,0 +1,123 @@
+use crate::components::waveform::SpectrogramSettings;
+use gpui::RenderImage;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Spectrogram columns per cached tile.
+pub const SPECTROGRAM_TILE_COLUMNS: usize = 256;
+// frequency bands of a tile, finer ffts fold their bins into these
+const SPECTROGRAM_MAX_ROWS: usize = 256;
+// tiles kept per channel, a few screens worth at any zoom
+const SPECTROGRAM_MAX_TILES: usize = 48;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpectrogramTileKey {
+    pub settings: SpectrogramSettings,
+    pub hop: usize,
+    pub index: usize,
+}
+
+/// Rendered spectrogram tiles of one channel, keyed by settings, zoom level and position, like the
+/// waveform mipmap the hop is a power of two so every zoom between two levels reuses the same tiles.
+#[derive(Default)]
+pub struct SpectrogramTiles {
+    tiles: HashMap<SpectrogramTileKey, Arc<RenderImage>>,
+    // least recently used first
+    order: VecDeque<SpectrogramTileKey>,
+    // dropped tiles still to be released from the sprite atlas
+    evicted: Vec<Arc<RenderImage>>,
+    // rendering in the background, or failed and not tried again
+    requested: HashSet<SpectrogramTileKey>,
+}
+
+impl SpectrogramTiles {
+    pub fn rows(settings: SpectrogramSettings) -> usize {
+        (settings.fft_size.size() / 2).min(SPECTROGRAM_MAX_ROWS)
+    }
+
+    pub fn get(&mut self, key: &SpectrogramTileKey) -> Option<Arc<RenderImage>> {
+        let image = self.tiles.get(key)?.clone();
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            self.order.remove(position);
+        }
+        self.order.push_back(*key);
+        Some(image)
+    }
+
+    /// Marks a missing tile for rendering, false when it is cached or was already requested.
+    pub fn request(&mut self, key: SpectrogramTileKey) -> bool {
+        !self.tiles.contains_key(&key) && self.requested.insert(key)
+    }
+
+    pub fn insert(&mut self, key: SpectrogramTileKey, image: Arc<RenderImage>) {
+        self.requested.remove(&key);
+        if let Some(previous) = self.tiles.insert(key, image) {
+            self.evicted.push(previous);
+        } else {
+            self.order.push_back(key);
+        }
+        while self.order.len() > SPECTROGRAM_MAX_TILES {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(image) = self.tiles.remove(&oldest) {
+                self.evicted.push(image);
+            }
+        }
+    }
+
+    pub fn take_evicted(&mut self) -> Vec<Arc<RenderImage>> {
+        std::mem::take(&mut self.evicted)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{Frame, RgbaImage};
+
+    fn tile() -> Arc<RenderImage> {
+        Arc::new(RenderImage::new(vec![Frame::new(RgbaImage::new(1, 1))]))
+    }
+
+    fn key(index: usize) -> SpectrogramTileKey {
+        SpectrogramTileKey {
+            settings: SpectrogramSettings::default(),
+            hop: 1,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut tiles = SpectrogramTiles::default();
+        for index in 0..SPECTROGRAM_MAX_TILES {
+            tiles.insert(key(index), tile());
+        }
+        assert!(tiles.take_evicted().is_empty());
+
+        // the first tile was used again, the second one goes
+        assert!(tiles.get(&key(0)).is_some());
+        tiles.insert(key(SPECTROGRAM_MAX_TILES), tile());
+        assert_eq!(tiles.take_evicted().len(), 1);
+        assert!(tiles.get(&key(0)).is_some());
+        assert!(tiles.get(&key(1)).is_none());
+        assert!(tiles.take_evicted().is_empty());
+    }
+
+    #[test]
+    fn test_requests_missing_tiles_once() {
+        let mut tiles = SpectrogramTiles::default();
+        assert!(tiles.request(key(0)));
+        assert!(!tiles.request(key(0)));
+
+        tiles.insert(key(0), tile());
+        assert!(!tiles.request(key(0)));
+
+        // evicted tiles are rendered again when they come back into view
+        for index in 1..=SPECTROGRAM_MAX_TILES {
+            tiles.insert(key(index), tile());
+        }
+        assert!(tiles.request(key(0)));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::components::waveform::{SPECTROGRAM_TILE_COLUMNS, SpectrogramSettings, WaveForm, spectrogram_hop};
+use gpui::{
+    App, Bounds, ContentMask, Corners, Element, ElementId, GlobalElementId, InspectorElementId, IntoElement, LayoutId,
+    Pixels, Refineable, RenderImage, Style, StyleRefinement, Styled, Window, fill, point, px, rgb, size,
+};
+use log::error;
+use std::panic::Location;
+use std::sync::Arc;
+
+pub struct SpectrogramView {
+    waveform: WaveForm,
+    start_frame: usize,
+    end_frame: usize,
+    frames_per_px: f64,
+    settings: SpectrogramSettings,
+    style: StyleRefinement,
+}
+
+impl SpectrogramView {
+    pub fn new(
+        waveform: &WaveForm,
+        start_frame: usize,
+        end_frame: usize,
+        frames_per_px: f64,
+        settings: SpectrogramSettings,
+    ) -> Self {
+        Self {
+            waveform: waveform.clone(),
+            start_frame,
+            end_frame,
+            frames_per_px,
+            settings,
+            style: StyleRefinement::default(),
+        }
+    }
+
+    // renders off the main thread and repaints the window once the tile is cached
+    fn render_tile(&self, hop: usize, index: usize, window: &mut Window, cx: &mut App) {
+        let (waveform, settings) = (self.waveform.clone(), self.settings);
+        window
+            .spawn(cx, async move |cx| {
+                let rendered = cx
+                    .background_spawn(async move { waveform.render_spectrogram_tile(settings, hop, index) })
+                    .await;
+                if let Err(err) = rendered {
+                    error!("spectrogram tile {index} failed: {err}");
+                    return;
+                }
+                cx.update(|window, _| window.refresh()).ok();
+            })
+            .detach();
+    }
+}
+
+impl Styled for SpectrogramView {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl IntoElement for SpectrogramView {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+pub struct SpectrogramPrepaintState {
+    // tiles still rendering paint as the floor of the colour map
+    tiles: Vec<(Bounds<Pixels>, Option<Arc<RenderImage>>)>,
+}
+
+impl Element for SpectrogramView {
+    type RequestLayoutState = ();
+    type PrepaintState = SpectrogramPrepaintState;
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let layout_id = window.with_text_style(style.text_style().cloned(), |window| {
+            window.request_layout(style, None, cx)
+        });
+        (layout_id, ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        let hop = spectrogram_hop(self.frames_per_px);
+        let tile_frames = SPECTROGRAM_TILE_COLUMNS * hop;
+        let end_frame = self.end_frame.min(self.waveform.frames());
+
+        // columns are centered on their frame, tiles start half a hop early
+        let mut tiles = Vec::new();
+        if end_frame > self.start_frame {
+            let first = (self.start_frame + hop / 2) / tile_frames;
+            let last = (end_frame + hop / 2).div_ceil(tile_frames);
+            for index in first..last {
+                let start = (index * tile_frames) as f64 - (hop / 2) as f64 - self.start_frame as f64;
+                let tile_bounds = Bounds::new(
+                    point(bounds.left() + px((start / self.frames_per_px) as f32), bounds.top()),
+                    size(px((tile_frames as f64 / self.frames_per_px) as f32), bounds.size.height),
+                );
+                let image = self.waveform.spectrogram_tile(self.settings, hop, index);
+                if image.is_none() && self.waveform.request_spectrogram_tile(self.settings, hop, index) {
+                    self.render_tile(hop, index, window, cx);
+                }
+                tiles.push((tile_bounds, image));
+            }
+        }
+
+        for image in self.waveform.take_evicted_spectrogram_tiles() {
+            window.drop_image(image).ok();
+        }
+
+        SpectrogramPrepaintState { tiles }
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        style.paint(bounds, window, cx, |window, _| {
+            window.with_content_mask(Some(ContentMask { bounds }), |window| {
+                let placeholder = rgb(self.settings.color_map.color(0.0));
+                for (tile_bounds, image) in prepaint.tiles.drain(..) {
+                    let Some(image) = image else {
+                        window.paint_quad(fill(tile_bounds, placeholder));
+                        continue;
+                    };
+                    if let Err(err) = window.paint_image(tile_bounds, Corners::default(), image, 0, false) {
+                        error!("paint spectrogram tile failed: {err}");
+                    }
+                }
+            })
+        });
+    }
+}
@@ -0,0 +1,393 @@
+use std::f64::consts::PI;
+
+// level mapped to the bottom of the colour map
+pub const SPECTROGRAM_FLOOR_DB: f32 = -120.0;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FftSize {
+    N256,
+    N512,
+    N1024,
+    #[default]
+    N2048,
+    N4096,
+    N8192,
+}
+
+impl FftSize {
+    pub const ALL: [FftSize; 6] = [
+        FftSize::N256,
+        FftSize::N512,
+        FftSize::N1024,
+        FftSize::N2048,
+        FftSize::N4096,
+        FftSize::N8192,
+    ];
+
+    pub fn size(&self) -> usize {
+        match self {
+            FftSize::N256 => 256,
+            FftSize::N512 => 512,
+            FftSize::N1024 => 1024,
+            FftSize::N2048 => 2048,
+            FftSize::N4096 => 4096,
+            FftSize::N8192 => 8192,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        self.size().to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SpectrumWindow {
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    BlackmanHarris,
+}
+
+impl SpectrumWindow {
+    pub const ALL: [SpectrumWindow; 4] = [
+        SpectrumWindow::Rectangular,
+        SpectrumWindow::Hann,
+        SpectrumWindow::Hamming,
+        SpectrumWindow::BlackmanHarris,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            SpectrumWindow::Rectangular => "Rectangular",
+            SpectrumWindow::Hann => "Hann",
+            SpectrumWindow::Hamming => "Hamming",
+            SpectrumWindow::BlackmanHarris => "Blackman-Harris",
+        }
+    }
+
+    /// Periodic window of `len` coefficients.
+    pub fn coefficients(&self, len: usize) -> Vec<f32> {
+        let cosines = |a: &[f64]| -> Vec<f32> {
+            (0..len)
+                .map(|i| {
+                    let phase = 2.0 * PI * i as f64 / len as f64;
+                    let value = a.iter().enumerate().fold(0.0, |sum, (k, a)| {
+                        sum + a * (-1f64).powi(k as i32) * (k as f64 * phase).cos()
+                    });
+                    value as f32
+                })
+                .collect()
+        };
+        match self {
+            SpectrumWindow::Rectangular => vec![1.0; len],
+            SpectrumWindow::Hann => cosines(&[0.5, 0.5]),
+            SpectrumWindow::Hamming => cosines(&[0.54, 0.46]),
+            SpectrumWindow::BlackmanHarris => cosines(&[0.35875, 0.48829, 0.14128, 0.01168]),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorMap {
+    #[default]
+    Magma,
+    Viridis,
+    Gray,
+}
+
+impl ColorMap {
+    pub const ALL: [ColorMap; 3] = [ColorMap::Magma, ColorMap::Viridis, ColorMap::Gray];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ColorMap::Magma => "Magma",
+            ColorMap::Viridis => "Viridis",
+            ColorMap::Gray => "Gray",
+        }
+    }
+
+    fn stops(&self) -> &'static [u32] {
+        match self {
+            ColorMap::Magma => &[0x000004, 0x3B0F70, 0x8C2981, 0xDE4968, 0xFE9F6D, 0xFCFDBF],
+            ColorMap::Viridis => &[0x440154, 0x414487, 0x2A788E, 0x22A884, 0x7AD151, 0xFDE725],
+            ColorMap::Gray => &[0x000000, 0xFFFFFF],
+        }
+    }
+
+    /// Rgb colour at `t` in 0.0 ..= 1.0, linear between the stops.
+    pub fn color(&self, t: f32) -> u32 {
+        let stops = self.stops();
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let index = (position.floor() as usize).min(stops.len() - 2);
+        let fraction = position - index as f32;
+        let channel = |shift: u32| {
+            let (a, b) = ((stops[index] >> shift) & 0xFF, (stops[index + 1] >> shift) & 0xFF);
+            ((a as f32 + (b as f32 - a as f32) * fraction).round() as u32) << shift
+        };
+        channel(16) | channel(8) | channel(0)
+    }
+}
+
+/// Analysis and look of a spectrogram.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpectrogramSettings {
+    pub fft_size: FftSize,
+    pub window: SpectrumWindow,
+    pub color_map: ColorMap,
+}
+
+/// Radix-2 FFT of one power of two size, the twiddle factors are computed once.
+pub struct Fft {
+    size: usize,
+    // e^(-2πik/size) for k in 0..size / 2
+    twiddles: Vec<(f64, f64)>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+        let twiddles = (0..size / 2)
+            .map(|k| {
+                let angle = -2.0 * PI * k as f64 / size as f64;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+        Self { size, twiddles }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// In place, both slices must be `size` long.
+    pub fn process(&self, re: &mut [f32], im: &mut [f32]) {
+        let n = self.size;
+        debug_assert!(re.len() == n && im.len() == n);
+
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= n {
+            // the stage's factors are every (n / len)th of the full table
+            let stride = n / len;
+            for start in (0..n).step_by(len) {
+                for k in 0..len / 2 {
+                    let (w_re, w_im) = self.twiddles[k * stride];
+                    let (a, b) = (start + k, start + k + len / 2);
+                    let t_re = re[b] as f64 * w_re - im[b] as f64 * w_im;
+                    let t_im = re[b] as f64 * w_im + im[b] as f64 * w_re;
+                    re[b] = (re[a] as f64 - t_re) as f32;
+                    im[b] = (im[a] as f64 - t_im) as f32;
+                    re[a] = (re[a] as f64 + t_re) as f32;
+                    im[a] = (im[a] as f64 + t_im) as f32;
+                }
+            }
+            len <<= 1;
+        }
+    }
+}
+
+/// Level of the lower half of the bins of the windowed `samples` in dBFS, a full scale sine reads 0 dB.
+/// The window is `fft.size()` long.
+pub fn spectrum_db(fft: &Fft, samples: &[f32], window: &[f32]) -> Vec<f32> {
+    let n = fft.size();
+    let mut re: Vec<f32> = window
+        .iter()
+        .enumerate()
+        .map(|(i, w)| samples.get(i).copied().unwrap_or(0.0) * w)
+        .collect();
+    let mut im = vec![0.0; n];
+    fft.process(&mut re, &mut im);
+
+    let scale = 2.0 / window.iter().sum::<f32>().max(f32::EPSILON);
+    (0..n / 2)
+        .map(|bin| {
+            let amplitude = (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * scale;
+            (20.0 * amplitude.max(1e-12).log10()).max(SPECTROGRAM_FLOOR_DB)
+        })
+        .collect()
+}
+
+/// Frames between spectrogram columns at a zoom, a power of two so nearby zooms share columns.
+pub fn spectrogram_hop(frames_per_px: f64) -> usize {
+    (frames_per_px.max(1.0).ceil() as usize).next_power_of_two()
+}
+
+/// Spectra of columns `first..first + count`, column `c` centered on frame `c * hop`.
+pub fn spectrogram_columns<E>(
+    read_frames: impl Fn(usize, usize) -> Result<Vec<f32>, E>,
+    settings: SpectrogramSettings,
+    hop: usize,
+    first: usize,
+    count: usize,
+) -> Result<Vec<Vec<f32>>, E> {
+    let n = settings.fft_size.size();
+    let fft = Fft::new(n);
+    let window = settings.window.coefficients(n);
+    let half = n / 2;
+    // frames before the start of the audio are silence
+    let column_samples = |samples: &[f32], samples_start: usize, center: usize| -> Vec<f32> {
+        let start = center as isize - half as isize - samples_start as isize;
+        (start..start + n as isize)
+            .map(|index| match usize::try_from(index) {
+                Ok(index) => samples.get(index).copied().unwrap_or(0.0),
+                Err(_) => 0.0,
+            })
+            .collect()
+    };
+
+    if hop <= n {
+        // overlapping columns are read once
+        let start = (first * hop).saturating_sub(half);
+        let samples = read_frames(start, (first + count) * hop + half)?;
+        Ok((first..first + count)
+            .map(|column| spectrum_db(&fft, &column_samples(&samples, start, column * hop), &window))
+            .collect())
+    } else {
+        (first..first + count)
+            .map(|column| {
+                let start = (column * hop).saturating_sub(half);
+                let samples = read_frames(start, column * hop + half)?;
+                Ok(spectrum_db(
+                    &fft,
+                    &column_samples(&samples, start, column * hop),
+                    &window,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Bgra pixels of `columns` left to right, highest frequencies on top, the bins folded into `rows` bands.
+pub fn spectrogram_pixels(columns: &[Vec<f32>], rows: usize, color_map: ColorMap) -> Vec<u8> {
+    let mut pixels = vec![0u8; columns.len() * rows * 4];
+    for (x, column) in columns.iter().enumerate() {
+        let bins = column.len();
+        if bins == 0 {
+            continue;
+        }
+        for band in 0..rows {
+            let from = (band * bins / rows).min(bins - 1);
+            let to = ((band + 1) * bins / rows).clamp(from + 1, bins);
+            let db = column[from..to]
+                .iter()
+                .fold(SPECTROGRAM_FLOOR_DB, |max, db| max.max(*db));
+            let color = color_map.color(1.0 - db / SPECTROGRAM_FLOOR_DB);
+            let offset = ((rows - 1 - band) * columns.len() + x) * 4;
+            pixels[offset..offset + 4].copy_from_slice(&[color as u8, (color >> 8) as u8, (color >> 16) as u8, 0xFF]);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sine(periods: f64, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| (2.0 * PI * periods * i as f64 / n as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_fft_of_a_sine() {
+        let window = SpectrumWindow::Rectangular.coefficients(256);
+        let spectrum = spectrum_db(&Fft::new(256), &sine(16.0, 256), &window);
+
+        assert_eq!(spectrum.len(), 128);
+        assert!(spectrum[16].abs() < 0.01, "{}", spectrum[16]);
+        assert!(spectrum.iter().enumerate().all(|(bin, db)| bin == 16 || *db < -100.0));
+    }
+
+    #[test]
+    fn test_fft_matches_dft() {
+        let samples = sine(3.0, 16)
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s + i as f32 * 0.01)
+            .collect::<Vec<_>>();
+        let (mut re, mut im) = (samples.clone(), vec![0.0; 16]);
+        Fft::new(16).process(&mut re, &mut im);
+
+        for bin in 0..16 {
+            let (dft_re, dft_im) = samples.iter().enumerate().fold((0.0, 0.0), |(sum_re, sum_im), (i, s)| {
+                let angle = -2.0 * PI * (bin * i) as f64 / 16.0;
+                (sum_re + *s as f64 * angle.cos(), sum_im + *s as f64 * angle.sin())
+            });
+            assert!((re[bin] as f64 - dft_re).abs() < 1e-4, "{bin}");
+            assert!((im[bin] as f64 - dft_im).abs() < 1e-4, "{bin}");
+        }
+    }
+
+    #[test]
+    fn test_windows() {
+        let hann = SpectrumWindow::Hann.coefficients(8);
+        assert!(hann[0].abs() < 1e-6);
+        assert!((hann[4] - 1.0).abs() < 1e-6);
+        for window in SpectrumWindow::ALL {
+            let coefficients = window.coefficients(64);
+            assert!(
+                coefficients.iter().all(|c| (-1e-6..=1.0 + 1e-6).contains(c)),
+                "{window:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_map_ends() {
+        assert_eq!(ColorMap::Gray.color(0.0), 0x000000);
+        assert_eq!(ColorMap::Gray.color(0.5), 0x808080);
+        assert_eq!(ColorMap::Viridis.color(1.0), 0xFDE725);
+        assert_eq!(ColorMap::Magma.color(-1.0), 0x000004);
+    }
+
+    #[test]
+    fn test_spectrogram_columns() {
+        let settings = SpectrogramSettings {
+            fft_size: FftSize::N256,
+            window: SpectrumWindow::Hann,
+            color_map: ColorMap::Gray,
+        };
+        // a tone at an eighth of the sample rate in the second half only
+        let samples: Vec<f32> = (0..4096)
+            .map(|i| {
+                if i < 2048 {
+                    0.0
+                } else {
+                    (2.0 * PI * i as f64 / 8.0).sin() as f32
+                }
+            })
+            .collect();
+        let read = |start: usize, end: usize| Ok::<_, ()>(samples[start.min(4096)..end.min(4096)].to_vec());
+
+        let columns = spectrogram_columns(read, settings, 256, 0, 16).unwrap();
+        assert_eq!(columns.len(), 16);
+        assert!(columns[2][32] <= SPECTROGRAM_FLOOR_DB);
+        assert!(columns[12][32] > -1.0, "{}", columns[12][32]);
+
+        // sparse columns are read around their centers
+        let sparse = spectrogram_columns(read, settings, 1024, 0, 4).unwrap();
+        assert_eq!(sparse[3], columns[12]);
+
+        let pixels = spectrogram_pixels(&columns, 4, settings.color_map);
+        assert_eq!(pixels.len(), 16 * 4 * 4);
+        // the tone lights the second band from the bottom of the loud columns
+        let pixel = |x: usize, y: usize| pixels[(y * 16 + x) * 4];
+        assert_eq!(pixel(12, 2), 0xFF);
+        assert_eq!(pixel(2, 2), 0x00);
+    }
+}
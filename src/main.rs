@@ -1,11 +1,14 @@
 mod audio;
+mod cli;
 mod components;
 mod time;
 mod ui;
 
 use crate::components::grid::GridState;
 use crate::time::SampleRate;
-use crate::ui::{ClipInfoState, GridProjectView, SessionPanel, SessionState, TrackInfoPanel};
+use crate::ui::{
+    ClipInfoState, GridProjectView, SessionPanel, SessionState, TrackInfoPanel, bind_grid_actions, notify_error,
+};
 use anyhow::Result;
 use gpui::{
     App, AppContext, Application, Context, Entity, IntoElement, ParentElement, Render, Styled, Window, WindowOptions,
@@ -13,10 +16,16 @@ use gpui::{
 };
 use gpui_component::{Root, gray_400};
 use gpui_component_assets::Assets;
+use log::error;
 
 fn main() -> Result<()> {
     env_logger::init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        return result;
+    }
+
     Application::new().with_assets(Assets).run(|cx: &mut App| {
         gpui_component::init(cx);
         {
@@ -51,7 +60,7 @@ fn main() -> Result<()> {
             let options = WindowOptions::default();
             cx.open_window(options, |window, cx| {
                 window.set_rem_size(px(16.0));
-                let view = cx.new(|cx| UnrecordApp::new(window, cx).unwrap());
+                let view = cx.new(|cx| UnrecordApp::new(window, cx));
                 // This first level on the window, should be a Root.
                 cx.new(|cx| Root::new(view, window, cx))
             })?;
@@ -72,16 +81,21 @@ struct UnrecordApp {
 }
 
 impl UnrecordApp {
-    fn new(window: &mut Window, cx: &mut Context<Self>) -> Result<Self> {
+    fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         let info_state = cx.new(ClipInfoState::new);
         let grid_state = cx.new(|cx| GridState::new(SampleRate::Hz44100, cx));
-        let session_state = cx.new(|cx| SessionState::new(window, cx, &grid_state, &info_state).unwrap());
+        if let Err(error) = grid_state.read(cx).load_test_tracks() {
+            error!("failed to load test tracks: {error}");
+            notify_error(window, cx, "Failed to load test signal", error);
+        }
+        bind_grid_actions(&grid_state, window, cx);
+        let session_state = cx.new(|cx| SessionState::new(window, cx, &grid_state, &info_state));
 
-        Ok(Self {
+        Self {
             session: session_state,
             info: info_state,
             grid: grid_state,
-        })
+        }
     }
 }
 
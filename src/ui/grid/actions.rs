@@ -0,0 +1,144 @@
+use crate::components::grid::GridState;
+use crate::ui::notify_error;
+use gpui::{Action, App, Entity, KeyBinding, Window, actions};
+use log::info;
+
+// scale factor of one keyboard zoom step
+const ZOOM_STEP: f64 = 0.5;
+
+actions!(
+    grid,
+    [
+        Undo,
+        Redo,
+        SplitRegions,
+        CutRegions,
+        CopyRegions,
+        PasteRegions,
+        DuplicateRegions,
+        DeleteRegions,
+        RippleDeleteRegions,
+        TogglePlayback,
+        ReturnToStart,
+        AddMarker,
+        NextMarker,
+        PreviousMarker,
+        ToggleLoop,
+        ZoomIn,
+        ZoomOut,
+        ZoomToFit,
+        ZoomToSelection,
+        ZoomBack,
+        ZoomForward,
+        CompareRegions,
+        ToggleComparisonFront
+    ]
+);
+
+/// Binds the timeline edit shortcuts, text inputs keep their own bindings while focused.
+/// Playback failures are notified in `window`.
+pub fn bind_grid_actions(grid: &Entity<GridState>, window: &Window, cx: &mut App) {
+    cx.bind_keys([
+        KeyBinding::new("cmd-z", Undo, None),
+        KeyBinding::new("shift-cmd-z", Redo, None),
+        KeyBinding::new("cmd-e", SplitRegions, None),
+        KeyBinding::new("cmd-x", CutRegions, None),
+        KeyBinding::new("cmd-c", CopyRegions, None),
+        KeyBinding::new("cmd-v", PasteRegions, None),
+        KeyBinding::new("cmd-d", DuplicateRegions, None),
+        KeyBinding::new("backspace", DeleteRegions, None),
+        KeyBinding::new("delete", DeleteRegions, None),
+        KeyBinding::new("shift-backspace", RippleDeleteRegions, None),
+        // printable keys stay with text inputs for typing
+        KeyBinding::new("space", TogglePlayback, Some("!Input")),
+        KeyBinding::new("home", ReturnToStart, None),
+        KeyBinding::new("m", AddMarker, Some("!Input")),
+        KeyBinding::new("alt-right", NextMarker, None),
+        KeyBinding::new("alt-left", PreviousMarker, None),
+        KeyBinding::new("l", ToggleLoop, Some("!Input")),
+        KeyBinding::new("cmd-=", ZoomIn, None),
+        KeyBinding::new("cmd--", ZoomOut, None),
+        KeyBinding::new("cmd-0", ZoomToFit, None),
+        KeyBinding::new("z", ZoomToSelection, Some("!Input")),
+        KeyBinding::new("cmd-[", ZoomBack, None),
+        KeyBinding::new("cmd-]", ZoomForward, None),
+        KeyBinding::new("shift-cmd-c", CompareRegions, None),
+        KeyBinding::new("b", ToggleComparisonFront, Some("!Input")),
+    ]);
+
+    on_grid_action::<Undo>(grid, cx, |grid| {
+        if let Some(label) = grid.undo() {
+            info!("undo {label}");
+        }
+    });
+    on_grid_action::<Redo>(grid, cx, |grid| {
+        if let Some(label) = grid.redo() {
+            info!("redo {label}");
+        }
+    });
+    on_grid_action::<SplitRegions>(grid, cx, GridState::split_at_cursor);
+    on_grid_action::<CutRegions>(grid, cx, GridState::cut_selection);
+    on_grid_action::<CopyRegions>(grid, cx, |grid| {
+        grid.copy_selection();
+    });
+    on_grid_action::<PasteRegions>(grid, cx, GridState::paste_at_cursor);
+    on_grid_action::<DuplicateRegions>(grid, cx, GridState::duplicate_selection);
+    on_grid_action::<DeleteRegions>(grid, cx, |grid| grid.delete_selection("Delete regions", false));
+    on_grid_action::<RippleDeleteRegions>(grid, cx, |grid| grid.delete_selection("Ripple delete regions", true));
+    on_grid_action::<ReturnToStart>(grid, cx, |grid| grid.locate(0));
+    on_grid_action::<AddMarker>(grid, cx, GridState::add_marker_at_playhead);
+    on_grid_action::<NextMarker>(grid, cx, GridState::locate_next_marker);
+    on_grid_action::<PreviousMarker>(grid, cx, GridState::locate_previous_marker);
+    on_grid_action::<ToggleLoop>(grid, cx, GridState::toggle_loop);
+    on_grid_action::<ZoomIn>(grid, cx, |grid| grid.zoom_at_cursor(ZOOM_STEP));
+    on_grid_action::<ZoomOut>(grid, cx, |grid| grid.zoom_at_cursor(1.0 / ZOOM_STEP));
+    on_grid_action::<ZoomToFit>(grid, cx, |grid| grid.viewport.zoom_to_fit());
+    on_grid_action::<ZoomToSelection>(grid, cx, GridState::zoom_to_selection);
+    on_grid_action::<ZoomBack>(grid, cx, |grid| grid.viewport.zoom_back());
+    on_grid_action::<ZoomForward>(grid, cx, |grid| grid.viewport.zoom_forward());
+    on_grid_action::<CompareRegions>(grid, cx, GridState::compare_selection);
+
+    let state = grid.clone();
+    cx.on_action(move |_: &ToggleComparisonFront, cx| {
+        state.update(cx, |grid, cx| {
+            grid.toggle_comparison_front(cx);
+            cx.notify();
+        });
+    });
+
+    let (state, handle) = (grid.clone(), window.window_handle());
+    cx.on_action(move |_: &TogglePlayback, cx| {
+        // deferred until the window dispatching the action is released
+        let state = state.clone();
+        cx.defer(move |cx| {
+            handle
+                .update(cx, |_, window, cx| toggle_playback(&state, window, cx))
+                .ok();
+        });
+    });
+}
+
+/// Starts or stops playback, a failure to start and a read error ending playback are notified in `window`.
+pub(super) fn toggle_playback(grid: &Entity<GridState>, window: &mut Window, cx: &mut App) {
+    match grid.update(cx, |grid, cx| grid.toggle_playback(cx)) {
+        Ok(playing) => window
+            .spawn(cx, async move |cx| {
+                if let Some(failure) = playing.await {
+                    cx.update(|window, cx| notify_error(window, cx, "Playback stopped", failure))
+                        .ok();
+                }
+            })
+            .detach(),
+        Err(error) => notify_error(window, cx, "Failed to start playback", error),
+    }
+}
+
+fn on_grid_action<A: Action>(grid: &Entity<GridState>, cx: &mut App, edit: impl Fn(&GridState) + 'static) {
+    let state = grid.clone();
+    cx.on_action(move |_: &A, cx| {
+        state.update(cx, |grid, cx| {
+            edit(grid);
+            cx.notify();
+        });
+    });
+}
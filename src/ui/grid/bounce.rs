@@ -0,0 +1,127 @@
+use crate::components::grid::GridState;
+use crate::components::track::Track;
+use crate::components::transport::Bounce;
+use crate::components::waveform::WaveClip;
+use crate::ui::{notify_error, notify_success};
+use gpui::{App, AppContext, Entity, SharedString, Window};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+use log::{error, info};
+use std::path::{Path, PathBuf};
+
+/// Bounce entries of the timeline menu, the selection bounces the span of the selected regions.
+/// The loop and the named ranges bounce their own span.
+pub(super) fn bounce_menu(menu: PopupMenu, grid: &Entity<GridState>, cx: &App) -> PopupMenu {
+    let state = grid.read(cx);
+    let tracks = state.tracks();
+    let span = state.selection_span();
+    let loop_range = state.markers.loop_range();
+    let ranges = state.markers.ranges();
+
+    let timeline_grid = grid.clone();
+    let timeline = Bounce::new(tracks.clone());
+    let menu = menu.item(
+        PopupMenuItem::new("Bounce timeline")
+            .disabled(timeline.frames() == 0)
+            .on_click(move |_, window, cx| bounce(&timeline_grid, timeline.clone(), "bounce", window, cx)),
+    );
+
+    let menu = menu
+        .item(range_item("Bounce selection", "bounce_selection", grid, &tracks, span))
+        .item(range_item("Bounce loop", "bounce_loop", grid, &tracks, loop_range));
+    if ranges.is_empty() {
+        return menu;
+    }
+    ranges.into_iter().fold(menu.separator(), |menu, range| {
+        let label = format!("Bounce range \"{}\"", range.name);
+        menu.item(range_item(label, &range.name, grid, &tracks, range.range()))
+    })
+}
+
+// disabled without a range
+fn range_item(
+    label: impl Into<SharedString>,
+    name: &str,
+    grid: &Entity<GridState>,
+    tracks: &[Track],
+    range: Option<(usize, usize)>,
+) -> PopupMenuItem {
+    let grid = grid.clone();
+    let name = name.to_string();
+    let range_bounce = range.map(|(start, end)| Bounce::new(tracks.to_vec()).range(start, end));
+    PopupMenuItem::new(label)
+        .disabled(range_bounce.is_none())
+        .on_click(move |_, window, cx| {
+            if let Some(range_bounce) = range_bounce.clone() {
+                bounce(&grid, range_bounce, &name, window, cx);
+            }
+        })
+}
+
+/// Bounces a single track through its mixer, regardless of the solo state of the others.
+pub(super) fn bounce_track_item(grid: &Entity<GridState>, track: &Track) -> PopupMenuItem {
+    let grid = grid.clone();
+    let track = track.clone();
+    PopupMenuItem::new("Bounce track").on_click(move |_, window, cx| {
+        let name = format!("{}_bounce", track.title());
+        bounce(&grid, Bounce::new(vec![track.clone()]), &name, window, cx)
+    })
+}
+
+// next to the first clip of the timeline
+fn bounce_directory(tracks: &[Track]) -> PathBuf {
+    let first_clip = tracks
+        .iter()
+        .flat_map(|track| track.regions().into_iter().next())
+        .next()
+        .map(|region| PathBuf::from(region.clip().metadata().filepath().to_string()));
+    first_clip
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+/// Renders in the background to a prompted file and adds it back as a clip on a new track.
+fn bounce(grid: &Entity<GridState>, bounce: Bounce, name: &str, window: &mut Window, cx: &mut App) {
+    if bounce.frames() == 0 {
+        notify_error(window, cx, "Nothing to bounce", "the range has no frames");
+        return;
+    }
+    let state = grid.read(cx);
+    let sample_rate = state.transport.sample_rate();
+    let directory = bounce_directory(&state.tracks());
+    let prompt = cx.prompt_for_new_path(&directory, Some(&format!("{name}.wav")));
+
+    let grid = grid.clone();
+    window
+        .spawn(cx, async move |cx| {
+            let Ok(Ok(Some(path))) = prompt.await else {
+                return;
+            };
+            let written = path.clone();
+            let result = cx
+                .background_spawn(async move {
+                    bounce.write(&written, sample_rate)?;
+                    WaveClip::open(&written)
+                })
+                .await;
+            cx.update(|window, cx| match result {
+                Ok(clip) => {
+                    info!("bounced {:?}", path);
+                    grid.update(cx, |grid, cx| {
+                        grid.update_tracks("Bounce", |tracks| {
+                            let track = Track::new(clip.metadata().filename());
+                            track.add_clip(&clip, 0);
+                            tracks.push(track);
+                        });
+                        cx.notify();
+                    });
+                    notify_success(window, cx, "Bounce finished", path.display());
+                }
+                Err(error) => {
+                    error!("failed to bounce: {error}");
+                    notify_error(window, cx, "Bounce failed", error);
+                }
+            })
+            .ok();
+        })
+        .detach();
+}
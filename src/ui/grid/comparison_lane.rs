@@ -0,0 +1,314 @@
+use crate::components::grid::{GridComparison, GridState, GridViewport, GridViewportHandle};
+use crate::components::waveform::{SampleGain, WaveForm, WaveFormView};
+use gpui::{
+    AnyElement, App, AvailableSpace, Bounds, ContentMask, Div, Element, ElementId, Entity, GlobalElementId, Hsla,
+    InspectorElementId, IntoElement, LayoutId, ParentElement, Pixels, Refineable, RenderOnce, Style, StyleRefinement,
+    Styled, Window, div, point, px, rems, rgb, size, white,
+};
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::{ActiveTheme, Sizable, gray_400};
+use std::panic::Location;
+
+const LANE_HEIGHT: Pixels = px(160.0);
+const LANE_PADDING: Pixels = px(8.0);
+// layers behind the front one stay readable through each other
+const BACK_ALPHA: f32 = 0.45;
+const FRONT_ALPHA: f32 = 0.9;
+const DIFFERENCE_COLOR: u32 = 0xFFFFFF;
+
+/// Lane above the tracks overlaying the compared regions, time-aligned by their measured lag.
+#[derive(IntoElement)]
+pub struct GridComparisonLane {
+    grid: Entity<GridState>,
+    comparison: GridComparison,
+    viewport: GridViewport,
+}
+
+impl GridComparisonLane {
+    pub fn new(grid: &Entity<GridState>, comparison: &GridComparison, viewport: &GridViewport) -> Self {
+        Self {
+            grid: grid.clone(),
+            comparison: comparison.clone(),
+            viewport: viewport.clone(),
+        }
+    }
+
+    // layer titles with their lag, the A/B switch and the difference toggle
+    fn header(&self, cx: &App) -> Div {
+        let front = self.comparison.front();
+        let layers = self.comparison.layers().into_iter().enumerate().map(|(index, layer)| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_1()
+                .text_color(if index == front { white() } else { gray_400() })
+                .child(div().w(px(8.0)).h(px(8.0)).rounded_full().bg(rgb(layer.color)))
+                .child(div().flex_1().truncate().child(layer.title()))
+                .child(format!("{:+}", layer.lag))
+        });
+        let (toggle_grid, difference_grid, close_grid) = (self.grid.clone(), self.grid.clone(), self.grid.clone());
+        let show_difference = self.comparison.show_difference();
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .px_3()
+            .py_2()
+            .bg(rgb(0x474747))
+            .text_color(gray_400())
+            .text_size(rems(0.75))
+            .border_r(px(1.0))
+            .border_color(cx.theme().border)
+            .child("Comparison")
+            .children(layers)
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .gap_1()
+                    .child(
+                        Button::new("comparison-front")
+                            .label("A/B")
+                            .xsmall()
+                            .ghost()
+                            .on_click(move |_, _, cx| {
+                                toggle_grid.update(cx, |grid, cx| {
+                                    grid.toggle_comparison_front(cx);
+                                    cx.notify();
+                                })
+                            }),
+                    )
+                    .child(
+                        Button::new("comparison-difference")
+                            .label("Difference")
+                            .xsmall()
+                            .with_variant(if show_difference {
+                                ButtonVariant::Primary
+                            } else {
+                                ButtonVariant::Ghost
+                            })
+                            .on_click(move |_, _, cx| {
+                                difference_grid.update(cx, |grid, cx| {
+                                    grid.toggle_comparison_difference(cx);
+                                    cx.notify();
+                                })
+                            }),
+                    )
+                    .child(
+                        Button::new("comparison-close")
+                            .label("Close")
+                            .xsmall()
+                            .ghost()
+                            .on_click(move |_, _, cx| {
+                                close_grid.update(cx, |grid, cx| {
+                                    grid.comparison.close();
+                                    cx.notify();
+                                })
+                            }),
+                    ),
+            )
+    }
+}
+
+impl RenderOnce for GridComparisonLane {
+    fn render(self, _: &mut Window, cx: &mut App) -> impl IntoElement {
+        let header_width = self.viewport.header_size().width;
+        div()
+            .flex()
+            .flex_row()
+            .h(LANE_HEIGHT)
+            .border_b(px(1.0))
+            .border_color(cx.theme().border)
+            .child(self.header(cx).w(header_width).h_full())
+            .child(
+                ComparisonOverlay::new(&self.comparison, &self.viewport)
+                    .flex_1()
+                    .h_full()
+                    .bg(rgb(0x2E2E2E)),
+            )
+    }
+}
+
+// the layers back to front and the difference on top, each channel in its own row
+struct ComparisonOverlay {
+    comparison: GridComparison,
+    viewport: GridViewport,
+    style: StyleRefinement,
+}
+
+struct ComparisonTrace {
+    element: AnyElement,
+    bounds: Bounds<Pixels>,
+}
+
+impl ComparisonOverlay {
+    fn new(comparison: &GridComparison, viewport: &GridViewport) -> Self {
+        Self {
+            comparison: comparison.clone(),
+            viewport: viewport.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+
+    fn layout_traces(&self, bounds: Bounds<Pixels>, window: &mut Window, cx: &mut App) -> Vec<ComparisonTrace> {
+        let layers = self.comparison.layers();
+        let Some(reference) = layers.first() else {
+            return Vec::new();
+        };
+        let channels = reference.region.clip().channel_count().max(1);
+        let row_height = (bounds.size.height - LANE_PADDING * 2.0) / channels as f32;
+        let row = |channel: usize| {
+            Bounds::new(
+                point(bounds.left(), bounds.top() + LANE_PADDING + row_height * channel as f32),
+                size(bounds.size.width, row_height),
+            )
+        };
+
+        let front = self.comparison.front();
+        let mut traces = Vec::new();
+        for index in self.comparison.paint_order() {
+            let layer = &layers[index];
+            let alpha = if index == front { FRONT_ALPHA } else { BACK_ALPHA };
+            let color = Hsla::from(rgb(layer.color)).alpha(alpha);
+            let gain = (!layer.region.is_unprocessed()).then(|| layer.region.sample_gain());
+            // lane frame of clip frame 0
+            let origin = -(layer.region.clip_start_frame() as isize + layer.lag);
+            let clip = layer.region.clip();
+            for (channel, waveform) in clip.channels().iter().enumerate().take(channels) {
+                let trace = self.trace(row(channel), waveform, origin, layer.lane_range(), gain.clone(), color);
+                traces.extend(trace);
+            }
+        }
+        if let Some(difference) = self.comparison.difference() {
+            let color = Hsla::from(rgb(DIFFERENCE_COLOR));
+            for (channel, waveform) in difference.channels.iter().enumerate().take(channels) {
+                let range = (difference.start, difference.start + waveform.frames() as isize);
+                traces.extend(self.trace(row(channel), waveform, difference.start, range, None, color));
+            }
+        }
+
+        for trace in &mut traces {
+            trace.element.layout_as_root(
+                size(
+                    AvailableSpace::Definite(trace.bounds.size.width),
+                    AvailableSpace::Definite(trace.bounds.size.height),
+                ),
+                window,
+                cx,
+            );
+        }
+        traces
+    }
+
+    // the visible part of `waveform`, whose frame 0 is at lane frame `origin`, within the lane frames `range`
+    fn trace(
+        &self,
+        row: Bounds<Pixels>,
+        waveform: &WaveForm,
+        origin: isize,
+        (start, end): (isize, isize),
+        gain: Option<SampleGain>,
+        color: Hsla,
+    ) -> Option<ComparisonTrace> {
+        let anchor = self.comparison.anchor() as isize;
+        let visible_start = self.viewport.scroll_offset_to_frame(px(0.0)) as isize - anchor;
+        let visible_end = self.viewport.scroll_offset_to_frame(row.size.width) as isize + 1 - anchor;
+        // the timeline starts at frame 0
+        let start = start.max(visible_start).max(-anchor);
+        let end = end.min(visible_end);
+        if start >= end {
+            return None;
+        }
+
+        let left = self.viewport.frame_to_scroll_offset((start + anchor) as usize);
+        let right = self.viewport.frame_to_scroll_offset((end + anchor) as usize);
+        let bounds = Bounds::new(point(row.left() + left, row.top()), size(right - left, row.size.height));
+        let (from, to) = ((start - origin) as usize, (end - origin) as usize);
+        let element = WaveFormView::new(waveform, from, to, self.viewport.frames_per_px())
+            .gain(gain)
+            .color(Some(color))
+            .w(bounds.size.width)
+            .h(bounds.size.height)
+            .into_any_element();
+        Some(ComparisonTrace { element, bounds })
+    }
+}
+
+impl IntoElement for ComparisonOverlay {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for ComparisonOverlay {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl Element for ComparisonOverlay {
+    type RequestLayoutState = ();
+    type PrepaintState = Vec<ComparisonTrace>;
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        (window.request_layout(style, None, cx), ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        let mut traces = self.layout_traces(bounds, window, cx);
+        window.with_content_mask(Some(ContentMask { bounds }), |window| {
+            for trace in &mut traces {
+                trace.element.prepaint_at(trace.bounds.origin, window, cx);
+            }
+        });
+        traces
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        traces: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        style.paint(bounds, window, cx, |window, cx| {
+            window.with_content_mask(Some(ContentMask { bounds }), |window| {
+                for trace in traces.iter_mut() {
+                    trace.element.paint(window, cx);
+                }
+            })
+        });
+    }
+}
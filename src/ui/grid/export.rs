@@ -0,0 +1,132 @@
+use crate::components::grid::TimelineMarker;
+use crate::components::region::TrackRegion;
+use crate::components::track::Track;
+use crate::components::waveform::{ClipExport, ClipExportFormat};
+use crate::ui::{notify_error, notify_success};
+use gpui::{App, AppContext, Context, Window};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+use log::{error, info};
+use std::path::{Path, PathBuf};
+
+/// Export entries of the track context menu, `reference` adds the residual against it.
+/// Every named range gets a submenu exporting only the part of the track under it.
+pub(super) fn track_export_menu(
+    menu: PopupMenu,
+    track: &Track,
+    reference: Option<&Track>,
+    ranges: Vec<TimelineMarker>,
+    window: &mut Window,
+    cx: &mut Context<PopupMenu>,
+) -> PopupMenu {
+    let menu = export_items(menu, track, reference, None);
+    ranges.into_iter().fold(menu, |menu, range| {
+        let (track, reference) = (track.clone(), reference.cloned());
+        menu.submenu(
+            format!("Export range \"{}\"", range.name),
+            window,
+            cx,
+            move |menu, _, _| export_items(menu, &track, reference.as_ref(), range.range()),
+        )
+    })
+}
+
+fn export_items(menu: PopupMenu, track: &Track, reference: Option<&Track>, range: Option<(usize, usize)>) -> PopupMenu {
+    ClipExportFormat::ALL.into_iter().fold(menu, |menu, format| {
+        let track = track.clone();
+        let reference = reference.cloned();
+        let item = PopupMenuItem::new(format!("Export {}", format.title()))
+            .on_click(move |_, window, cx| export_track(&track, reference.as_ref(), range, format, window, cx));
+        menu.item(item)
+    })
+}
+
+// The region of the track under the timeline `range`, or its first region without one, exported as it
+// plays. The export covers only the clip frames under the range. The residual is taken against the
+// reference region playing over the same frames, aligned by where both sit on the timeline.
+fn track_export(track: &Track, reference: Option<&Track>, range: Option<(usize, usize)>) -> Option<ClipExport> {
+    let region = match range {
+        Some(range) => region_over(track, range)?,
+        None => track.regions().into_iter().next()?,
+    };
+    let (start, end) = range.unwrap_or((region.track_start_frame(), region.track_end_frame()));
+    let start = start.clamp(region.track_start_frame(), region.track_end_frame());
+    let end = end.clamp(start, region.track_end_frame());
+    if start == end {
+        return None;
+    }
+    let to_clip = |frame: usize| region.clip_start_frame() + frame - region.track_start_frame();
+    let mut export = ClipExport::new(&region.clip()).range(to_clip(start), to_clip(end));
+    if !region.is_unprocessed() {
+        export = export.gain(region.sample_gain());
+    }
+    if let Some(reference) = reference.and_then(|track| region_over(track, (start, end))) {
+        // clip frames playing at the same timeline frame, the export aligns the remaining latency
+        let offset = (reference.clip_start_frame() as isize - reference.track_start_frame() as isize)
+            - (region.clip_start_frame() as isize - region.track_start_frame() as isize);
+        let (start, end) = (reference.clip_start_frame(), reference.clip_end_frame());
+        export = export.reference(&reference.clip(), start, end, offset);
+        if !reference.is_unprocessed() {
+            export = export.reference_gain(reference.sample_gain());
+        }
+    }
+    Some(export)
+}
+
+// the region of `track` overlapping most of the timeline frames `start..end`
+fn region_over(track: &Track, (start, end): (usize, usize)) -> Option<TrackRegion> {
+    let overlap = |region: &TrackRegion| {
+        let overlap_end = end.min(region.track_end_frame());
+        overlap_end.saturating_sub(start.max(region.track_start_frame()))
+    };
+    track
+        .regions()
+        .into_iter()
+        .filter(|region| overlap(region) > 0)
+        .max_by_key(overlap)
+}
+
+fn export_track(
+    track: &Track,
+    reference: Option<&Track>,
+    range: Option<(usize, usize)>,
+    format: ClipExportFormat,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let Some(export) = track_export(track, reference, range) else {
+        let reason = match range {
+            Some(_) => "has no clip in the range",
+            None => "has no clips",
+        };
+        notify_error(window, cx, "Nothing to export", format!("{} {reason}", track.title()));
+        return;
+    };
+
+    let clip_path = PathBuf::from(export.clip().metadata().filepath().to_string());
+    let directory = clip_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = clip_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prompt = cx.prompt_for_new_path(&directory, Some(&format!("{stem}.{}", format.extension())));
+
+    window
+        .spawn(cx, async move |cx| {
+            let Ok(Ok(Some(path))) = prompt.await else {
+                return;
+            };
+            let result = cx.background_spawn(async move { export.write(format, &path) }).await;
+            cx.update(|window, cx| match result {
+                Ok(paths) => {
+                    info!("exported {:?}", paths);
+                    notify_success(window, cx, "Export finished", format!("{} files written", paths.len()));
+                }
+                Err(error) => {
+                    error!("failed to export: {error}");
+                    notify_error(window, cx, "Export failed", error);
+                }
+            })
+            .ok();
+        })
+        .detach();
+}
@@ -0,0 +1,54 @@
+use crate::components::grid::{GridPoint, GridState};
+use crate::components::waveform::WaveClip;
+use crate::ui::notify_error;
+use anyhow::Result;
+use gpui::{App, AppContext, Entity, ExternalPaths, Task, Window};
+use log::{error, info};
+use std::path::PathBuf;
+
+/// Opens the dropped files in the background and places the clips at `point` as a single edit.
+pub(super) fn drop_files(
+    grid: &Entity<GridState>,
+    paths: &ExternalPaths,
+    point: GridPoint,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let loads: Vec<(PathBuf, Task<Result<WaveClip>>)> = paths
+        .paths()
+        .iter()
+        .map(|path| {
+            let open = path.clone();
+            (path.clone(), cx.background_spawn(async move { WaveClip::open(&open) }))
+        })
+        .collect();
+
+    let grid = grid.clone();
+    window
+        .spawn(cx, async move |cx| {
+            let mut clips = Vec::new();
+            let mut failures = Vec::new();
+            for (path, load) in loads {
+                match load.await {
+                    Ok(clip) => clips.push(clip),
+                    Err(error) => failures.push((path, error)),
+                }
+            }
+            cx.update(|window, cx| {
+                for (path, error) in failures {
+                    error!("failed to open dropped file {:?}: {error}", path);
+                    notify_error(window, cx, format!("Failed to open {}", path.display()), error);
+                }
+                if clips.is_empty() {
+                    return;
+                }
+                info!("dropped {} clips", clips.len());
+                grid.update(cx, |grid, cx| {
+                    grid.place_clips(&clips, point);
+                    cx.notify();
+                });
+            })
+            .ok();
+        })
+        .detach();
+}
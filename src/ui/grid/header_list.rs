@@ -1,24 +1,70 @@
-use crate::components::grid::{GridViewport, GridViewportHandle};
+use crate::components::grid::{GridState, GridViewport, GridViewportHandle};
 use crate::components::track::{Track, TrackHeaderView};
+use crate::components::transport::Transport;
+use crate::ui::grid::bounce::bounce_track_item;
+use crate::ui::grid::export::track_export_menu;
+use crate::ui::grid::track_menu::track_menu;
 use gpui::{
-    AnyElement, App, AvailableSpace, Bounds, ContentMask, DispatchPhase, Element, ElementId, GlobalElementId, Hitbox,
-    HitboxBehavior, InspectorElementId, IntoElement, LayoutId, Pixels, Point, Refineable, ScrollDelta,
-    ScrollWheelEvent, Style, StyleRefinement, Styled, Window, px, rgb, size,
+    AnyElement, App, AppContext, AvailableSpace, Bounds, ContentMask, Context, DispatchPhase, Div, Element, ElementId,
+    Entity, GlobalElementId, Hitbox, HitboxBehavior, InspectorElementId, InteractiveElement, IntoElement, LayoutId,
+    MouseButton, MouseUpEvent, ParentElement, Pixels, Point, Refineable, Render, ScrollDelta, ScrollWheelEvent,
+    SharedString, Stateful, StatefulInteractiveElement, Style, StyleRefinement, Styled, Window, div, px, rems, rgb,
+    size,
 };
+use gpui_component::Sizable;
+use gpui_component::input::Input;
+use gpui_component::menu::ContextMenuExt;
 use std::collections::VecDeque;
 use std::panic::Location;
 
+// grab height of the bottom edge of a header for resizing
+const RESIZE_HANDLE_PX: f32 = 4.0;
+
+// payload and drag preview of a header dragged to a new position
+#[derive(Clone)]
+struct DraggedTrack {
+    index: usize,
+    title: SharedString,
+}
+
+impl Render for DraggedTrack {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_3()
+            .py_1()
+            .rounded_md()
+            .bg(rgb(0x474747))
+            .text_size(rems(0.875))
+            .child(self.title.clone())
+    }
+}
+
+#[derive(Clone)]
+struct ResizedTrack {
+    index: usize,
+}
+
+impl Render for ResizedTrack {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        div()
+    }
+}
+
 pub struct GridHeaderList {
     tracks: Vec<Track>,
     viewport: GridViewport,
+    transport: Transport,
+    grid: Entity<GridState>,
     style: StyleRefinement,
 }
 
 impl GridHeaderList {
-    pub fn new(tracks: Vec<Track>, viewport: &GridViewport) -> Self {
+    pub fn new(tracks: Vec<Track>, viewport: &GridViewport, transport: &Transport, grid: &Entity<GridState>) -> Self {
         Self {
             tracks,
             viewport: viewport.clone(),
+            transport: transport.clone(),
+            grid: grid.clone(),
             style: StyleRefinement::default(),
         }
     }
@@ -32,33 +78,128 @@ impl GridHeaderList {
         );
 
         let mut header_layouts = VecDeque::new();
-        let element_size = self.viewport.header_size();
+        let header_width = self.viewport.header_size().width;
 
         let mut origin = bounds.origin;
         origin.y += self.viewport.scroll_offset().y;
 
         // todo optimize skip to start
         // todo optimize break on end
-        for track in &self.tracks {
-            let intersects = (origin.y + element_size.height >= bounds.origin.y)
-                && (origin.y <= bounds.origin.y + bounds.size.height);
+        for (index, track) in self.tracks.iter().enumerate() {
+            let height = self.viewport.track_height(index);
+            let intersects =
+                (origin.y + height >= bounds.origin.y) && (origin.y <= bounds.origin.y + bounds.size.height);
 
             if intersects {
-                let mut element = TrackHeaderView::new(&track)
-                    .w(element_size.width)
-                    .h(element_size.height)
+                let mut element = self
+                    .header(index, track, cx)
+                    .w(header_width)
+                    .h(height)
                     .into_any_element();
 
                 element.layout_as_root(available_item_space, window, cx);
                 header_layouts.push_back(GridHeaderLayout { element, origin });
             }
 
-            origin.y += element_size.height;
+            origin.y += height;
         }
 
         GridHeaderListLayoutResponse { header_layouts }
     }
 
+    // title drags to reorder and double clicks to rename, the bottom edge drags to resize
+    fn header(&self, index: usize, track: &Track, cx: &App) -> Stateful<Div> {
+        // passes are compared against the first track
+        let reference = self.tracks.first().filter(|_| index > 0).cloned();
+        let (menu_track, menu_grid) = (track.clone(), self.grid.clone());
+        let (drop_grid, resize_grid, resize_track) = (self.grid.clone(), self.grid.clone(), track.clone());
+        let (reset_grid, reset_track) = (self.grid.clone(), track.clone());
+
+        let title = match self.grid.read(cx).rename_input(track) {
+            Some(input) => div().id(("track-title", index)).child(Input::new(&input).xsmall()),
+            None => {
+                let (rename_grid, rename_track) = (self.grid.clone(), track.clone());
+                div()
+                    .id(("track-title", index))
+                    .cursor_grab()
+                    .whitespace_nowrap()
+                    .child(track.title())
+                    .on_drag(
+                        DraggedTrack {
+                            index,
+                            title: track.title(),
+                        },
+                        |drag, _, _, cx| cx.new(|_| drag.clone()),
+                    )
+                    .on_mouse_down(MouseButton::Left, move |event, window, cx| {
+                        if event.click_count == 2 {
+                            rename_grid.update(cx, |grid, cx| {
+                                grid.start_rename(&rename_track, window, cx);
+                                cx.notify();
+                            });
+                        }
+                    })
+            }
+        };
+
+        div()
+            .id(("track-header", index))
+            .relative()
+            .child(
+                div()
+                    .size_full()
+                    .child(
+                        TrackHeaderView::new(track)
+                            .title(title)
+                            .meter_frame(self.transport.meter_frame())
+                            .size_full(),
+                    )
+                    .context_menu(move |menu, window, cx| {
+                        let ranges = menu_grid.read(cx).markers.ranges();
+                        let menu = track_export_menu(menu, &menu_track, reference.as_ref(), ranges, window, cx)
+                            .separator()
+                            .item(bounce_track_item(&menu_grid, &menu_track))
+                            .separator();
+                        track_menu(menu, &menu_grid, &menu_track, index, window, cx)
+                    }),
+            )
+            .child(
+                div()
+                    .id(("track-resize", index))
+                    .absolute()
+                    .bottom_0()
+                    .left_0()
+                    .w_full()
+                    .h(px(RESIZE_HANDLE_PX))
+                    .cursor_row_resize()
+                    .on_drag(ResizedTrack { index }, |drag, _, _, cx| cx.new(|_| drag.clone()))
+                    .on_mouse_down(MouseButton::Left, move |event, _, cx| {
+                        if event.click_count == 2 {
+                            reset_grid.update(cx, |grid, cx| {
+                                grid.set_track_height(&reset_track, None);
+                                cx.notify();
+                            });
+                        }
+                    }),
+            )
+            .on_drag_move::<ResizedTrack>(move |event, _, cx| {
+                if event.drag(cx).index == index {
+                    let height = event.event.position.y - event.bounds.top();
+                    resize_grid.update(cx, |grid, cx| {
+                        grid.resize_track(&resize_track, height);
+                        cx.notify();
+                    });
+                }
+            })
+            .drag_over::<DraggedTrack>(|style, _, _, _| style.border_t_2().border_color(rgb(0x4FB3FF)))
+            .on_drop(move |drag: &DraggedTrack, _, cx| {
+                drop_grid.update(cx, |grid, cx| {
+                    grid.move_track(drag.index, index);
+                    cx.notify();
+                });
+            })
+    }
+
     fn prepaint_items(
         &self,
         bounds: Bounds<Pixels>,
@@ -190,5 +331,17 @@ impl Element for GridHeaderList {
                 cx.notify(current_view)
             }
         });
+
+        // a resize drag ends wherever the button is released
+        let grid = self.grid.clone();
+        window.on_mouse_event(move |event: &MouseUpEvent, phase, _window, cx| {
+            if phase == DispatchPhase::Bubble && event.button == MouseButton::Left {
+                grid.update(cx, |grid, cx| {
+                    if grid.finish_track_resize() {
+                        cx.notify();
+                    }
+                });
+            }
+        });
     }
 }
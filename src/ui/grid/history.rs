@@ -0,0 +1,26 @@
+use crate::components::grid::GridState;
+use gpui::{App, Entity, SharedString};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+
+/// Edit history entries, selecting one undoes or redoes up to and including it.
+pub(super) fn history_menu(menu: PopupMenu, grid: &Entity<GridState>, cx: &App) -> PopupMenu {
+    let entries = grid.read(cx).history.entries();
+    let menu = menu.item(jump_item("Initial state", grid, 0));
+    entries.into_iter().enumerate().fold(menu, |menu, (index, entry)| {
+        let label = match entry.applied {
+            true => entry.label,
+            false => format!("{} (undone)", entry.label),
+        };
+        menu.item(jump_item(label, grid, index + 1))
+    })
+}
+
+fn jump_item(label: impl Into<SharedString>, grid: &Entity<GridState>, position: usize) -> PopupMenuItem {
+    let grid = grid.clone();
+    PopupMenuItem::new(label).on_click(move |_, _, cx| {
+        grid.update(cx, |grid, cx| {
+            grid.jump_history(position);
+            cx.notify();
+        })
+    })
+}
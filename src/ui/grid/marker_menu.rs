@@ -0,0 +1,93 @@
+use crate::components::grid::{GridState, MarkerId, TimelineMarker};
+use gpui::{App, AppContext, Context, Entity, ParentElement, Window};
+use gpui_component::WindowExt;
+use gpui_component::input::{Input, InputState};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+
+/// Ruler context menu, adds markers and ranges and edits the existing ones.
+pub(super) fn marker_menu(
+    menu: PopupMenu,
+    grid: &Entity<GridState>,
+    window: &mut Window,
+    cx: &mut Context<PopupMenu>,
+) -> PopupMenu {
+    let state = grid.read(cx);
+    let has_selection = state.selection_span().is_some();
+    let has_loop = state.markers.loop_range().is_some();
+    let markers = state.markers.markers();
+
+    let menu = menu
+        .item(grid_item(
+            "Add marker at playhead",
+            grid,
+            GridState::add_marker_at_playhead,
+        ))
+        .item(grid_item("Add range from selection", grid, GridState::add_range_from_selection).disabled(!has_selection))
+        .item(
+            grid_item("Loop selection", grid, |grid| {
+                grid.set_loop_range(grid.selection_span())
+            })
+            .disabled(!has_selection),
+        )
+        .item(grid_item("Clear loop", grid, |grid| grid.set_loop_range(None)).disabled(!has_loop));
+    if markers.is_empty() {
+        return menu;
+    }
+    markers.into_iter().fold(menu.separator(), |menu, marker| {
+        let grid = grid.clone();
+        menu.submenu(marker.name.clone(), window, cx, move |menu, _, _| {
+            marker_submenu(menu, &grid, &marker)
+        })
+    })
+}
+
+fn marker_submenu(menu: PopupMenu, grid: &Entity<GridState>, marker: &TimelineMarker) -> PopupMenu {
+    let (id, frame, range) = (marker.id, marker.frame, marker.range());
+    let menu = menu.item(grid_item("Go to", grid, move |grid| grid.locate(frame)));
+    let menu = match range {
+        Some(range) => menu.item(grid_item("Loop range", grid, move |grid| {
+            grid.set_loop_range(Some(range))
+        })),
+        None => menu,
+    };
+    let rename_grid = grid.clone();
+    menu.item(PopupMenuItem::new("Rename…").on_click(move |_, window, cx| rename_marker(&rename_grid, id, window, cx)))
+        .item(grid_item("Remove", grid, move |grid| {
+            grid.update_markers("Remove marker", |markers| markers.remove(id))
+        }))
+}
+
+fn grid_item(label: &'static str, grid: &Entity<GridState>, action: impl Fn(&GridState) + 'static) -> PopupMenuItem {
+    let grid = grid.clone();
+    PopupMenuItem::new(label).on_click(move |_, _, cx| {
+        grid.update(cx, |grid, cx| {
+            action(grid);
+            cx.notify();
+        })
+    })
+}
+
+fn rename_marker(grid: &Entity<GridState>, id: MarkerId, window: &mut Window, cx: &mut App) {
+    let Some(marker) = grid.read(cx).markers.marker(id) else {
+        return;
+    };
+    let input = cx.new(|cx| InputState::new(window, cx).default_value(marker.name));
+    let grid = grid.clone();
+    window.open_dialog(cx, move |dialog, _, _| {
+        let (grid, input) = (grid.clone(), input.clone());
+        dialog
+            .title("Rename marker")
+            .child(Input::new(&input))
+            .confirm()
+            .on_ok(move |_, _, cx| {
+                let name = input.read(cx).value();
+                if !name.trim().is_empty() {
+                    grid.update(cx, |grid, cx| {
+                        grid.update_markers("Rename marker", |markers| markers.rename(id, name));
+                        cx.notify();
+                    });
+                }
+                true
+            })
+    });
+}
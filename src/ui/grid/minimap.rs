@@ -0,0 +1,245 @@
+use crate::components::grid::{GridState, GridViewport, GridViewportHandle, track_overview};
+use crate::components::track::Track;
+use gpui::{
+    App, BorderStyle, Bounds, Context, CursorStyle, DispatchPhase, Element, ElementId, Entity, GlobalElementId, Hitbox,
+    HitboxBehavior, InspectorElementId, InteractiveElement, IntoElement, LayoutId, MouseButton, MouseDownEvent,
+    ParentElement, Pixels, Refineable, Render, RenderOnce, StatefulInteractiveElement, Style, StyleRefinement, Styled,
+    Window, div, fill, outline, point, px, rgb, rgba, size,
+};
+use gpui_component::PixelsExt;
+use std::panic::Location;
+
+const MINIMAP_HEIGHT: Pixels = px(36.0);
+// grab width of the edges of the view window for zooming
+const EDGE_GRAB_PX: f32 = 4.0;
+// bars of tracks without a colour
+const BAR_COLOR: u32 = 0x8A8A8A;
+
+// the body of the view window scrolls, its edges zoom, each keeping the other edge in place
+#[derive(Copy, Clone)]
+enum MinimapDrag {
+    Move { grab: f64 },
+    Start { end: usize },
+    End { start: usize },
+}
+
+impl Render for MinimapDrag {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        div()
+    }
+}
+
+/// Overview of all tracks above the ruler with the visible part of the timeline as a window.
+#[derive(IntoElement)]
+pub struct GridMinimap {
+    tracks: Vec<Track>,
+    grid: Entity<GridState>,
+    viewport: GridViewport,
+}
+
+impl GridMinimap {
+    pub fn new(tracks: Vec<Track>, grid: &Entity<GridState>, viewport: &GridViewport) -> Self {
+        Self {
+            tracks,
+            grid: grid.clone(),
+            viewport: viewport.clone(),
+        }
+    }
+}
+
+impl RenderOnce for GridMinimap {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let (drag_viewport, move_viewport, grid) = (self.viewport.clone(), self.viewport.clone(), self.grid.clone());
+        div()
+            .id("grid-minimap")
+            .w_full()
+            .h(MINIMAP_HEIGHT)
+            .bg(rgb(0x2A2A2A))
+            .border_b(px(1.0))
+            .border_color(rgb(0x1F1F1F))
+            .child(GridOverview::new(self.tracks, &self.viewport).size_full())
+            .on_drag(MinimapDrag::Move { grab: 0.0 }, move |_, offset, _, cx| {
+                let drag = minimap_drag(&drag_viewport, offset.x);
+                cx.new(|_| drag)
+            })
+            .on_drag_move::<MinimapDrag>(move |event, _, cx| {
+                let frame = minimap_frame(&move_viewport, event.event.position.x - event.bounds.left());
+                let (start, _) = move_viewport.visible_frames();
+                match *event.drag(cx) {
+                    MinimapDrag::Move { grab } => {
+                        let delta = frame - grab - start as f64;
+                        move_viewport.on_scroll_x(px((-delta / move_viewport.frames_per_px()) as f32));
+                    }
+                    MinimapDrag::Start { end } => {
+                        move_viewport.zoom_to_frames((frame.max(0.0) as usize).min(end.saturating_sub(1)), end)
+                    }
+                    MinimapDrag::End { start } => move_viewport.zoom_to_frames(start, (frame as usize).max(start + 1)),
+                }
+                grid.update(cx, |_, cx| cx.notify());
+            })
+    }
+}
+
+fn minimap_frames_per_px(viewport: &GridViewport) -> f64 {
+    viewport.total_frames() as f64 / viewport.viewport_size().width.as_f64().max(1.0)
+}
+
+// the minimap spans the width of the track list
+fn minimap_frame(viewport: &GridViewport, x: Pixels) -> f64 {
+    x.as_f64() * minimap_frames_per_px(viewport)
+}
+
+fn minimap_x(viewport: &GridViewport, frame: usize) -> Pixels {
+    px((frame as f64 / minimap_frames_per_px(viewport)) as f32)
+}
+
+fn minimap_drag(viewport: &GridViewport, x: Pixels) -> MinimapDrag {
+    let (start, end) = viewport.visible_frames();
+    let (left, right) = (minimap_x(viewport, start), minimap_x(viewport, end));
+    if (x - left).abs() <= px(EDGE_GRAB_PX) {
+        MinimapDrag::Start { end }
+    } else if (x - right).abs() <= px(EDGE_GRAB_PX) {
+        MinimapDrag::End { start }
+    } else {
+        MinimapDrag::Move {
+            grab: minimap_frame(viewport, x) - start as f64,
+        }
+    }
+}
+
+// track lanes of mipmap peaks, a click outside the window centers the view there
+struct GridOverview {
+    tracks: Vec<Track>,
+    viewport: GridViewport,
+    style: StyleRefinement,
+}
+
+impl GridOverview {
+    fn new(tracks: Vec<Track>, viewport: &GridViewport) -> Self {
+        Self {
+            tracks,
+            viewport: viewport.clone(),
+            style: StyleRefinement::default(),
+        }
+    }
+
+    fn paint_lanes(&self, bounds: Bounds<Pixels>, window: &mut Window) {
+        if self.tracks.is_empty() || self.viewport.total_frames() == 0 {
+            return;
+        }
+        let lane_height = bounds.size.height / self.tracks.len() as f32;
+        for (index, track) in self.tracks.iter().enumerate() {
+            let color = rgb(track.color().unwrap_or(BAR_COLOR));
+            let mid_y = bounds.top() + lane_height * (index as f32 + 0.5);
+            for bar in track_overview(track) {
+                let left = bounds.left() + minimap_x(&self.viewport, bar.start_frame);
+                let width = (minimap_x(&self.viewport, bar.end_frame) - minimap_x(&self.viewport, bar.start_frame))
+                    .max(px(1.0));
+                let height = (lane_height * bar.peak.min(1.0)).max(px(1.0));
+                let bar_bounds = Bounds::new(point(left, mid_y - height / 2.0), size(width, height));
+                window.paint_quad(fill(bar_bounds, color));
+            }
+        }
+    }
+
+    fn window_bounds(&self, bounds: Bounds<Pixels>) -> Bounds<Pixels> {
+        let (start, end) = self.viewport.visible_frames();
+        let left = bounds.left() + minimap_x(&self.viewport, start);
+        let right = bounds.left() + minimap_x(&self.viewport, end);
+        Bounds::new(
+            point(left, bounds.top()),
+            size((right - left).max(px(2.0)), bounds.size.height),
+        )
+    }
+}
+
+impl IntoElement for GridOverview {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for GridOverview {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+impl Element for GridOverview {
+    type RequestLayoutState = ();
+    type PrepaintState = Hitbox;
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        (window.request_layout(style, None, cx), ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        _cx: &mut App,
+    ) -> Self::PrepaintState {
+        window.insert_hitbox(bounds, HitboxBehavior::Normal)
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        hitbox: &mut Self::PrepaintState,
+        window: &mut Window,
+        _cx: &mut App,
+    ) {
+        let current_view = window.current_view();
+        let window_bounds = self.window_bounds(bounds);
+
+        self.paint_lanes(bounds, window);
+        window.paint_quad(fill(window_bounds, rgba(0xffffff14)));
+        window.paint_quad(outline(window_bounds, rgb(0x4FB3FF), BorderStyle::Solid));
+
+        let mouse_x = window.mouse_position().x;
+        let on_edge = |x: Pixels| (mouse_x - x).abs() <= px(EDGE_GRAB_PX);
+        if on_edge(window_bounds.left()) || on_edge(window_bounds.right()) {
+            window.set_cursor_style(CursorStyle::ResizeLeftRight, hitbox);
+        }
+
+        let (viewport, hitbox_id) = (self.viewport.clone(), hitbox.id);
+        window.on_mouse_event(move |event: &MouseDownEvent, phase, window, cx| {
+            let outside = event.position.x < window_bounds.left() || event.position.x > window_bounds.right();
+            if phase == DispatchPhase::Bubble
+                && event.button == MouseButton::Left
+                && hitbox_id.is_hovered(window)
+                && outside
+            {
+                let (start, end) = viewport.visible_frames();
+                let center = minimap_x(&viewport, (start + end) / 2);
+                let delta = event.position.x - bounds.left() - center;
+                viewport.on_scroll_x(px((-minimap_frame(&viewport, delta) / viewport.frames_per_px()) as f32));
+                cx.notify(current_view);
+            }
+        });
+    }
+}
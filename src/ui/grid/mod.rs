@@ -1,5 +1,17 @@
+mod actions;
+mod bounce;
+mod comparison_lane;
+mod export;
+mod file_drop;
 mod header_list;
+mod history;
+mod marker_menu;
+mod minimap;
+mod region_menu;
+mod ruler_menu;
 mod track_list;
+mod track_menu;
 mod view;
 
+pub use actions::*;
 pub use view::GridProjectView;
@@ -0,0 +1,91 @@
+use crate::components::grid::GridState;
+use crate::components::region::{Fade, FadeCurve, TrackRegion};
+use gpui::{App, Entity};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+
+// coarse and fine region gain steps
+const GAIN_STEPS_DB: [f32; 4] = [1.0, -1.0, 0.1, -0.1];
+
+/// Fade, gain and polarity entries of the region context menu, they apply to the selected regions.
+/// Two or more selected regions can be overlaid on the comparison lane.
+pub(super) fn region_menu(menu: PopupMenu, grid: &Entity<GridState>, cx: &App) -> PopupMenu {
+    let selected = grid.read(cx).selection.selected().len();
+    if selected == 0 {
+        return menu;
+    }
+    let compare_grid = grid.clone();
+    let menu = menu
+        .item(
+            PopupMenuItem::new("Compare regions")
+                .disabled(selected < 2)
+                .on_click(move |_, _, cx| {
+                    compare_grid.update(cx, |grid, cx| {
+                        grid.compare_selection();
+                        cx.notify();
+                    })
+                }),
+        )
+        .separator();
+    let menu = FadeCurve::ALL.into_iter().fold(menu, |menu, curve| {
+        menu.item(region_item(
+            format!("Fade in: {}", curve.title()),
+            grid,
+            move |region| {
+                region.set_fade_in(Fade {
+                    curve,
+                    ..region.fade_in()
+                })
+            },
+        ))
+    });
+    let menu = FadeCurve::ALL.into_iter().fold(menu.separator(), |menu, curve| {
+        menu.item(region_item(
+            format!("Fade out: {}", curve.title()),
+            grid,
+            move |region| {
+                region.set_fade_out(Fade {
+                    curve,
+                    ..region.fade_out()
+                })
+            },
+        ))
+    });
+    let menu = menu
+        .separator()
+        .item(region_item("Remove fades".to_string(), grid, |region| {
+            region.set_fade_in(Fade::NONE);
+            region.set_fade_out(Fade::NONE);
+        }))
+        .separator();
+    let menu = GAIN_STEPS_DB.into_iter().fold(menu, |menu, step| {
+        menu.item(region_item(format!("Gain {step:+} dB"), grid, move |region| {
+            // round so repeated fine steps stay on the 0.1 dB grid
+            region.set_gain_db(((region.gain_db() + step) * 100.0).round() / 100.0)
+        }))
+    });
+    menu.item(region_item("Reset gain".to_string(), grid, |region| {
+        region.set_gain_db(0.0)
+    }))
+    .item(region_item("Invert polarity".to_string(), grid, |region| {
+        region.set_inverted(!region.inverted())
+    }))
+    .item(region_item("Clear volume envelope".to_string(), grid, |region| {
+        region.envelope_mut().clear()
+    }))
+}
+
+fn region_item(
+    label: String,
+    grid: &Entity<GridState>,
+    updater: impl Fn(&mut TrackRegion) + Clone + 'static,
+) -> PopupMenuItem {
+    let grid = grid.clone();
+    let edit = label.clone();
+    PopupMenuItem::new(label).on_click(move |_, _, cx| {
+        let updater = updater.clone();
+        grid.update(cx, |grid, cx| {
+            grid.update_selected_regions(&edit, updater);
+            cx.notify();
+        })
+    })
+}
@@ -0,0 +1,93 @@
+use crate::components::grid::GridState;
+use crate::components::tick::{RulerFormat, SmpteRate};
+use gpui::{App, AppContext, Context, Entity, ParentElement, Styled, Window, div};
+use gpui_component::WindowExt;
+use gpui_component::input::{Input, InputState};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+
+// tempo of the bars/beats ruler until one is set
+const DEFAULT_TEMPO: (f64, u32) = (120.0, 4);
+
+/// Ruler format entries, bars/beats keeps the last tempo.
+pub(super) fn ruler_menu(
+    menu: PopupMenu,
+    grid: &Entity<GridState>,
+    window: &mut Window,
+    cx: &mut Context<PopupMenu>,
+) -> PopupMenu {
+    let current = grid.read(cx).viewport.ruler_format();
+    let (bpm, beats_per_bar) = match current {
+        RulerFormat::BarsBeats { bpm, beats_per_bar } => (bpm, beats_per_bar),
+        _ => DEFAULT_TEMPO,
+    };
+    let bars_beats = RulerFormat::BarsBeats { bpm, beats_per_bar };
+
+    let smpte_grid = grid.clone();
+    let tempo_grid = grid.clone();
+    menu.item(format_item(grid, RulerFormat::Time, current))
+        .item(format_item(grid, RulerFormat::Samples, current))
+        .submenu("SMPTE", window, cx, move |menu, _, _| {
+            SmpteRate::ALL.into_iter().fold(menu, |menu, rate| {
+                menu.item(format_item(&smpte_grid, RulerFormat::Smpte(rate), current))
+            })
+        })
+        .item(format_item(grid, bars_beats, current))
+        .item(
+            PopupMenuItem::new("Tempo…")
+                .on_click(move |_, window, cx| set_tempo(&tempo_grid, bpm, beats_per_bar, window, cx)),
+        )
+}
+
+fn format_item(grid: &Entity<GridState>, format: RulerFormat, current: RulerFormat) -> PopupMenuItem {
+    let grid = grid.clone();
+    let label = match format {
+        RulerFormat::Smpte(rate) => rate.title().to_string(),
+        _ => format.title(),
+    };
+    PopupMenuItem::new(label)
+        .checked(format == current)
+        .on_click(move |_, _, cx| {
+            grid.update(cx, |grid, cx| {
+                grid.viewport.set_ruler_format(format);
+                cx.notify();
+            })
+        })
+}
+
+fn set_tempo(grid: &Entity<GridState>, bpm: f64, beats_per_bar: u32, window: &mut Window, cx: &mut App) {
+    let bpm_input = cx.new(|cx| InputState::new(window, cx).default_value(bpm.to_string()));
+    let bar_input = cx.new(|cx| InputState::new(window, cx).default_value(beats_per_bar.to_string()));
+    let grid = grid.clone();
+    window.open_dialog(cx, move |dialog, _, _| {
+        let (grid, bpm_input, bar_input) = (grid.clone(), bpm_input.clone(), bar_input.clone());
+        dialog
+            .title("Tempo")
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap_2()
+                    .child("Beats per minute")
+                    .child(Input::new(&bpm_input))
+                    .child("Beats per bar")
+                    .child(Input::new(&bar_input)),
+            )
+            .confirm()
+            .on_ok(move |_, _, cx| {
+                let bpm = bpm_input.read(cx).value().trim().parse::<f64>().ok();
+                let beats_per_bar = bar_input.read(cx).value().trim().parse::<u32>().ok();
+                let (Some(bpm), Some(beats_per_bar)) = (bpm, beats_per_bar) else {
+                    return false;
+                };
+                if !(1.0..=999.0).contains(&bpm) || beats_per_bar == 0 {
+                    return false;
+                }
+                grid.update(cx, |grid, cx| {
+                    grid.viewport
+                        .set_ruler_format(RulerFormat::BarsBeats { bpm, beats_per_bar });
+                    cx.notify();
+                });
+                true
+            })
+    });
+}
@@ -1,25 +1,58 @@
-use crate::components::grid::{GridViewport, GridViewportHandle};
+use crate::components::grid::{
+    GridDrag, GridHistory, GridMarkers, GridPoint, GridSelection, GridSnap, GridViewport, GridViewportHandle, HitSlop,
+    RegionHitKind, hit_test,
+};
 use crate::components::tick::GridTickType;
 use crate::components::track::{Track, TrackView};
+use crate::components::transport::Transport;
 use gpui::{
-    AnyElement, App, AvailableSpace, Bounds, ContentMask, DispatchPhase, Element, ElementId, GlobalElementId, Hitbox,
-    HitboxBehavior, InspectorElementId, IntoElement, LayoutId, Pixels, Point, Refineable, ScrollDelta,
-    ScrollWheelEvent, Style, StyleRefinement, Styled, Window, fill, point, px, rgb, size,
+    AnyElement, App, AvailableSpace, BorderStyle, Bounds, ContentMask, CursorStyle, DispatchPhase, Element, ElementId,
+    GlobalElementId, Hitbox, HitboxBehavior, InspectorElementId, IntoElement, LayoutId, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Pixels, Point, Refineable, ScrollDelta, ScrollWheelEvent, Style, StyleRefinement,
+    Styled, Window, fill, outline, point, px, rgb, rgba, size,
 };
 use std::collections::VecDeque;
 use std::panic::Location;
 
+// grab width of region edges for trimming
+const REGION_EDGE_PX: f64 = 6.0;
+// height of the fade handle band from the top of a track row
+const FADE_HANDLE_BAND_PX: f32 = 20.0;
+// space above and below the regions of a track row
+const TRACK_PADDING_PX: f32 = 8.0;
+// reach of snap targets
+const SNAP_PX: f64 = 8.0;
+// zoom factor exponent per pixel of cmd-scroll
+const ZOOM_PER_PX: f64 = 0.01;
+// vertical zoom factor exponent per pixel of alt-scroll
+const AMPLITUDE_ZOOM_PER_PX: f64 = 0.02;
+
 pub struct GridTrackList {
     tracks: Vec<Track>,
     viewport: GridViewport,
+    selection: GridSelection,
+    history: GridHistory,
+    transport: Transport,
+    markers: GridMarkers,
     style: StyleRefinement,
 }
 
 impl GridTrackList {
-    pub fn new(tracks: Vec<Track>, viewport: &GridViewport) -> Self {
+    pub fn new(
+        tracks: Vec<Track>,
+        viewport: &GridViewport,
+        selection: &GridSelection,
+        history: &GridHistory,
+        transport: &Transport,
+        markers: &GridMarkers,
+    ) -> Self {
         Self {
             tracks,
             viewport: viewport.clone(),
+            selection: selection.clone(),
+            history: history.clone(),
+            transport: transport.clone(),
+            markers: markers.clone(),
             style: StyleRefinement::default(),
         }
     }
@@ -33,7 +66,6 @@ impl GridTrackList {
         self.viewport.set_viewport_height(bounds.size.height);
 
         let element_width = self.viewport.viewport_size().width;
-        let header_size = self.viewport.header_size();
         let scroll_offset_y = self.viewport.scroll_offset().y;
 
         let mut origin = bounds.origin;
@@ -42,15 +74,16 @@ impl GridTrackList {
         // todo optimize skip to start
         // todo optimize break on end
 
-        for track in &self.tracks {
-            let intersects = (origin.y + header_size.height >= bounds.origin.y)
-                && (origin.y <= bounds.origin.y + bounds.size.height);
+        for (index, track) in self.tracks.iter().enumerate() {
+            let height = self.viewport.track_height(index);
+            let intersects =
+                (origin.y + height >= bounds.origin.y) && (origin.y <= bounds.origin.y + bounds.size.height);
 
             if intersects {
-                let mut element = TrackView::new(&self.viewport, &track)
+                let mut element = TrackView::new(&self.viewport, &self.selection, &track)
                     .w(element_width)
-                    .h(header_size.height)
-                    .py(px(8.0))
+                    .h(height)
+                    .py(px(TRACK_PADDING_PX))
                     .gap(px(8.0))
                     .into_any_element();
 
@@ -58,7 +91,7 @@ impl GridTrackList {
                 layouts.push_back(GridTrackLayout { element, origin });
             }
 
-            origin.y += header_size.height;
+            origin.y += height;
         }
 
         GridTrackListLayoutResponse { layouts }
@@ -82,6 +115,99 @@ impl GridTrackList {
             Ok(layout)
         })
     }
+
+    fn paint_cursor(&self, bounds: Bounds<Pixels>, window: &mut Window) {
+        let Some(cursor) = self.selection.cursor() else {
+            return;
+        };
+        let x = bounds.origin.x + self.viewport.frame_to_scroll_offset(cursor.frame);
+        let cursor_bounds = Bounds::new(point(x, bounds.origin.y), size(px(1.0), bounds.size.height));
+        window.paint_quad(fill(cursor_bounds, rgb(0xF5F5F7)));
+    }
+
+    fn paint_markers(&self, bounds: Bounds<Pixels>, window: &mut Window) {
+        let line = |frame: usize| {
+            let x = bounds.origin.x + self.viewport.frame_to_scroll_offset(frame);
+            Bounds::new(point(x, bounds.origin.y), size(px(1.0), bounds.size.height))
+        };
+        if let Some((start, end)) = self.markers.loop_range() {
+            let band = Bounds::from_corners(line(start).origin, line(end).bottom_left());
+            window.paint_quad(fill(band, rgba(0x46A75814)));
+        }
+        for marker in self.markers.markers() {
+            match marker.end_frame {
+                Some(end) => {
+                    window.paint_quad(fill(line(marker.frame), rgba(0x6E8FB580)));
+                    window.paint_quad(fill(line(end), rgba(0x6E8FB580)));
+                }
+                None => window.paint_quad(fill(line(marker.frame), rgba(0xE2B93B80))),
+            }
+        }
+    }
+
+    fn paint_playhead(&self, bounds: Bounds<Pixels>, window: &mut Window) {
+        let x = bounds.origin.x + self.viewport.frame_to_scroll_offset(self.transport.position());
+        let playhead_bounds = Bounds::new(point(x, bounds.origin.y), size(px(1.0), bounds.size.height));
+        window.paint_quad(fill(playhead_bounds, rgb(0x4FB3FF)));
+    }
+
+    fn paint_rubber_band(&self, bounds: Bounds<Pixels>, window: &mut Window) {
+        let Some(GridDrag::Select { origin, position, .. }) = self.selection.drag() else {
+            return;
+        };
+        let top = bounds.origin.y + self.viewport.scroll_offset().y;
+        let corner = |p: GridPoint| {
+            point(
+                bounds.origin.x + self.viewport.frame_to_scroll_offset(p.frame),
+                top + self.viewport.row_to_y(p.row),
+            )
+        };
+        let (a, b) = (corner(origin), corner(position));
+        let band = Bounds::from_corners(point(a.x.min(b.x), a.y.min(b.y)), point(a.x.max(b.x), a.y.max(b.y)));
+        window.paint_quad(fill(band, rgba(0xffffff14)));
+        window.paint_quad(outline(band, rgb(0x8a8a8a), BorderStyle::Solid));
+    }
+}
+
+pub(super) fn grid_point(viewport: &GridViewport, bounds: Bounds<Pixels>, position: Point<Pixels>) -> GridPoint {
+    let local = position - bounds.origin;
+    GridPoint {
+        frame: viewport.scroll_offset_to_frame(local.x),
+        row: viewport.y_to_row(local.y - viewport.scroll_offset().y),
+    }
+}
+
+// row fractions are relative to the height of the track under `point`
+fn hit_slop(viewport: &GridViewport, point: GridPoint) -> HitSlop {
+    let height = viewport.track_height(point.track_index().unwrap_or(0));
+    HitSlop {
+        edge_frames: (REGION_EDGE_PX * viewport.frames_per_px()) as usize,
+        handle_rows: px(FADE_HANDLE_BAND_PX) / height,
+        padding_rows: px(TRACK_PADDING_PX) / height,
+    }
+}
+
+// ticks in view, markers, ranges, the loop and the playhead, regions are added by the drag
+fn grid_snap(viewport: &GridViewport, markers: &GridMarkers, transport: &Transport) -> GridSnap {
+    let ticks = viewport
+        .ticks()
+        .into_iter()
+        .map(|tick| viewport.scroll_offset_to_frame(tick.offset_x));
+    let marker_frames = markers
+        .markers()
+        .into_iter()
+        .flat_map(|marker| [Some(marker.frame), marker.end_frame])
+        .flatten();
+    let loop_frames = markers.loop_range().into_iter().flat_map(|(start, end)| [start, end]);
+    GridSnap::new((SNAP_PX * viewport.frames_per_px()) as usize)
+        .with_targets(ticks)
+        .with_targets(marker_frames)
+        .with_targets(loop_frames)
+        .with_targets([0, transport.position()])
+}
+
+fn tracks_frames(tracks: &[Track]) -> usize {
+    tracks.iter().fold(0, |acc, track| acc.max(track.frames()))
 }
 
 impl IntoElement for GridTrackList {
@@ -188,21 +314,99 @@ impl Element for GridTrackList {
                     for item in &mut prepaint.layouts {
                         item.element.paint(window, cx);
                     }
+
+                    self.paint_markers(bounds, window);
+                    self.paint_cursor(bounds, window);
+                    self.paint_playhead(bounds, window);
+                    self.paint_rubber_band(bounds, window);
                 })
             })
         });
 
-        let viewport = self.viewport.clone();
+        let trimming = matches!(self.selection.drag(), Some(GridDrag::Trim { .. }));
+        let hover = grid_point(&self.viewport, bounds, window.mouse_position());
+        let hover_edge = hit_test(&self.tracks, hover, hit_slop(&self.viewport, hover))
+            .is_some_and(|hit| hit.kind != RegionHitKind::Body);
+        if trimming || (hover_edge && !self.selection.is_dragging()) {
+            window.set_cursor_style(CursorStyle::ResizeLeftRight, &prepaint.hitbox);
+        }
+
+        let (viewport, scroll_tracks) = (self.viewport.clone(), self.tracks.clone());
         let hitbox_id = prepaint.hitbox.id;
         let mut accumulated_scroll_delta = ScrollDelta::default();
 
         window.on_mouse_event(move |event: &ScrollWheelEvent, phase, window, cx| {
-            if phase == DispatchPhase::Bubble && hitbox_id.should_handle_scroll(window) {
+            if phase == DispatchPhase::Bubble && hitbox_id.should_handle_scroll(window) && event.modifiers.platform {
+                // cmd-scroll zooms around the pointer, scrolling up zooms in
+                let delta_y = event.delta.pixel_delta(px(20.)).y;
+                viewport.zoom_at(
+                    (-delta_y.to_f64() * ZOOM_PER_PX).exp(),
+                    event.position.x - bounds.left(),
+                );
+                cx.notify(current_view);
+            } else if phase == DispatchPhase::Bubble && hitbox_id.should_handle_scroll(window) && event.modifiers.alt {
+                // alt-scroll zooms the waveforms of the track under the pointer vertically
+                let point = grid_point(&viewport, bounds, event.position);
+                let track = point.track_index().and_then(|index| scroll_tracks.get(index));
+                if let Some(track) = track {
+                    let delta_y = event.delta.pixel_delta(px(20.)).y;
+                    let factor = (-delta_y.to_f64() * AMPLITUDE_ZOOM_PER_PX).exp();
+                    track.set_amplitude(track.amplitude().zoomed(factor as f32));
+                    cx.notify(current_view);
+                }
+            } else if phase == DispatchPhase::Bubble && hitbox_id.should_handle_scroll(window) {
                 accumulated_scroll_delta = accumulated_scroll_delta.coalesce(event.delta);
                 let pixel_delta = accumulated_scroll_delta.pixel_delta(px(20.));
                 viewport.on_scroll(pixel_delta);
                 cx.notify(current_view);
             }
         });
+
+        let (viewport, selection, tracks) = (self.viewport.clone(), self.selection.clone(), self.tracks.clone());
+        window.on_mouse_event(move |event: &MouseDownEvent, phase, window, cx| {
+            if phase == DispatchPhase::Bubble && hitbox_id.is_hovered(window) {
+                let point = grid_point(&viewport, bounds, event.position);
+                match event.button {
+                    // alt edits the volume envelope, alt-shift removes a breakpoint
+                    MouseButton::Left if event.modifiers.alt => {
+                        selection.press_envelope(&tracks, point, hit_slop(&viewport, point), event.modifiers.shift)
+                    }
+                    MouseButton::Left => {
+                        let extend = event.modifiers.shift || event.modifiers.platform;
+                        selection.press(&tracks, point, hit_slop(&viewport, point), extend);
+                    }
+                    // the context menu acts on the region under the pointer
+                    MouseButton::Right => selection.select_at(&tracks, point, hit_slop(&viewport, point)),
+                    _ => return,
+                }
+                cx.notify(current_view);
+            }
+        });
+
+        // cmd holds off snapping for the drag
+        let snap = grid_snap(&self.viewport, &self.markers, &self.transport);
+        let (viewport, selection, tracks) = (self.viewport.clone(), self.selection.clone(), self.tracks.clone());
+        let move_snap = snap.clone();
+        window.on_mouse_event(move |event: &MouseMoveEvent, phase, _window, cx| {
+            if phase == DispatchPhase::Bubble && selection.is_dragging() {
+                let snap = (selection.snapping() && !event.modifiers.platform).then_some(&move_snap);
+                selection.update_drag(&tracks, grid_point(&viewport, bounds, event.position), snap);
+                cx.notify(current_view);
+            }
+        });
+
+        let (viewport, selection, tracks) = (self.viewport.clone(), self.selection.clone(), self.tracks.clone());
+        let history = self.history.clone();
+        window.on_mouse_event(move |event: &MouseUpEvent, phase, _window, cx| {
+            if phase == DispatchPhase::Bubble && event.button == MouseButton::Left && selection.is_dragging() {
+                let snap = (selection.snapping() && !event.modifiers.platform).then_some(&snap);
+                selection.update_drag(&tracks, grid_point(&viewport, bounds, event.position), snap);
+                if let Some(edit) = selection.end_drag(&tracks) {
+                    history.push(edit);
+                    viewport.set_total_frames(tracks_frames(&tracks));
+                }
+                cx.notify(current_view);
+            }
+        });
     }
 }
@@ -0,0 +1,184 @@
+use crate::components::grid::GridState;
+use crate::components::track::{TRACK_COLORS, Track, TrackDisplay};
+use crate::components::waveform::{
+    AMPLITUDE_ZOOM_MIN, AmplitudeScale, ColorMap, FftSize, SpectrogramSettings, SpectrumWindow, WaveFormAmplitude,
+};
+use gpui::{Context, Entity, SharedString, Window};
+use gpui_component::menu::{PopupMenu, PopupMenuItem};
+
+/// Track header context menu entries to add, colour, display, resize and delete tracks,
+/// titles are renamed by double click and alt-scroll over a track zooms it vertically.
+pub(super) fn track_menu(
+    menu: PopupMenu,
+    grid: &Entity<GridState>,
+    track: &Track,
+    index: usize,
+    window: &mut Window,
+    cx: &mut Context<PopupMenu>,
+) -> PopupMenu {
+    let (color_grid, color_track) = (grid.clone(), track.clone());
+    let (display_grid, display_track) = (grid.clone(), track.clone());
+    let (amplitude_grid, amplitude_track) = (grid.clone(), track.clone());
+    let (height_track, delete_track) = (track.clone(), track.clone());
+    menu.item(grid_item("Add track below", grid, move |grid| {
+        grid.add_track(index + 1)
+    }))
+    .submenu("Colour", window, cx, move |menu, _, _| {
+        color_menu(menu, &color_grid, &color_track)
+    })
+    .submenu("Display", window, cx, move |menu, window, cx| {
+        display_menu(menu, &display_grid, &display_track, window, cx)
+    })
+    .submenu("Amplitude", window, cx, move |menu, _, _| {
+        amplitude_menu(menu, &amplitude_grid, &amplitude_track)
+    })
+    .item(
+        grid_item("Reset height", grid, move |grid| {
+            grid.set_track_height(&height_track, None)
+        })
+        .disabled(track.height().is_none()),
+    )
+    .separator()
+    .item(grid_item("Delete track", grid, move |grid| {
+        grid.delete_track(&delete_track)
+    }))
+}
+
+fn color_menu(menu: PopupMenu, grid: &Entity<GridState>, track: &Track) -> PopupMenu {
+    let current = track.color();
+    let default_track = track.clone();
+    let menu = menu
+        .item(
+            grid_item("Default", grid, move |grid| grid.set_track_color(&default_track, None))
+                .checked(current.is_none()),
+        )
+        .separator();
+    TRACK_COLORS.iter().fold(menu, |menu, (name, color)| {
+        let (track, color) = (track.clone(), *color);
+        menu.item(
+            grid_item(*name, grid, move |grid| grid.set_track_color(&track, Some(color)))
+                .checked(current == Some(color)),
+        )
+    })
+}
+
+// waveform or spectrogram, with the spectrogram analysis and colours
+fn display_menu(
+    menu: PopupMenu,
+    grid: &Entity<GridState>,
+    track: &Track,
+    window: &mut Window,
+    cx: &mut Context<PopupMenu>,
+) -> PopupMenu {
+    let display = track.display();
+    let (waveform_track, spectrogram_track) = (track.clone(), track.clone());
+    let (fft_grid, fft_track) = (grid.clone(), track.clone());
+    let (window_grid, window_track) = (grid.clone(), track.clone());
+    let (color_grid, color_track) = (grid.clone(), track.clone());
+    menu.item(
+        grid_item("Waveform", grid, move |_| {
+            waveform_track.set_display(TrackDisplay::Waveform)
+        })
+        .checked(display == TrackDisplay::Waveform),
+    )
+    .item(
+        grid_item("Spectrogram", grid, move |_| {
+            spectrogram_track.set_display(TrackDisplay::Spectrogram)
+        })
+        .checked(display == TrackDisplay::Spectrogram),
+    )
+    .separator()
+    .submenu("FFT size", window, cx, move |menu, _, _| {
+        spectrogram_menu(
+            menu,
+            &fft_grid,
+            &fft_track,
+            &FftSize::ALL,
+            |size| size.title(),
+            |settings, size| settings.fft_size = size,
+        )
+    })
+    .submenu("Window", window, cx, move |menu, _, _| {
+        spectrogram_menu(
+            menu,
+            &window_grid,
+            &window_track,
+            &SpectrumWindow::ALL,
+            |window| window.title(),
+            |settings, window| settings.window = window,
+        )
+    })
+    .submenu("Colour map", window, cx, move |menu, _, _| {
+        spectrogram_menu(
+            menu,
+            &color_grid,
+            &color_track,
+            &ColorMap::ALL,
+            |map| map.title(),
+            |settings, map| settings.color_map = map,
+        )
+    })
+}
+
+// scale and vertical zoom of the waveforms, a zoom step is 6 dB
+fn amplitude_menu(menu: PopupMenu, grid: &Entity<GridState>, track: &Track) -> PopupMenu {
+    let current = track.amplitude();
+    let menu = AmplitudeScale::ALL.iter().fold(menu, |menu, scale| {
+        let track = track.clone();
+        let amplitude = WaveFormAmplitude {
+            scale: *scale,
+            ..current
+        };
+        menu.item(
+            grid_item(scale.title(), grid, move |_| track.set_amplitude(amplitude)).checked(*scale == current.scale),
+        )
+    });
+    let (in_track, out_track, reset_track) = (track.clone(), track.clone(), track.clone());
+    menu.separator()
+        .item(grid_item("Zoom in", grid, move |_| {
+            in_track.set_amplitude(current.zoomed(2.0))
+        }))
+        .item(grid_item("Zoom out", grid, move |_| {
+            out_track.set_amplitude(current.zoomed(0.5))
+        }))
+        .item(
+            grid_item("Reset zoom", grid, move |_| {
+                reset_track.set_amplitude(WaveFormAmplitude {
+                    zoom: AMPLITUDE_ZOOM_MIN,
+                    ..current
+                })
+            })
+            .disabled(current.zoom == AMPLITUDE_ZOOM_MIN),
+        )
+}
+
+fn spectrogram_menu<T: Copy + 'static, L: Into<SharedString>>(
+    menu: PopupMenu,
+    grid: &Entity<GridState>,
+    track: &Track,
+    options: &[T],
+    title: impl Fn(T) -> L,
+    apply: fn(&mut SpectrogramSettings, T),
+) -> PopupMenu {
+    let current = track.spectrogram();
+    options.iter().fold(menu, |menu, option| {
+        let (track, option) = (track.clone(), *option);
+        let mut settings = current;
+        apply(&mut settings, option);
+        menu.item(grid_item(title(option), grid, move |_| track.set_spectrogram(settings)).checked(settings == current))
+    })
+}
+
+fn grid_item(
+    label: impl Into<SharedString>,
+    grid: &Entity<GridState>,
+    action: impl Fn(&GridState) + 'static,
+) -> PopupMenuItem {
+    let grid = grid.clone();
+    PopupMenuItem::new(label).on_click(move |_, _, cx| {
+        grid.update(cx, |grid, cx| {
+            action(grid);
+            cx.notify();
+        })
+    })
+}
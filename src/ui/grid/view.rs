@@ -1,12 +1,30 @@
 use crate::components::grid::{GridState, GridViewportHandle};
-use crate::components::tick::{GridTickLabelView, GridTickView};
+use crate::components::tick::{GridMarkerView, GridTickLabelView, GridTickView};
+use crate::components::transport::PlayheadView;
 use crate::ui::app_panel_title;
+use crate::ui::grid::actions::toggle_playback;
+use crate::ui::grid::bounce::bounce_menu;
+use crate::ui::grid::comparison_lane::GridComparisonLane;
+use crate::ui::grid::file_drop::drop_files;
 use crate::ui::grid::header_list::GridHeaderList;
-use crate::ui::grid::track_list::GridTrackList;
-use gpui::{App, Div, Entity, Hsla, IntoElement, ParentElement, RenderOnce, Styled, Window, div, px, rems, rgb};
+use crate::ui::grid::history::history_menu;
+use crate::ui::grid::marker_menu::marker_menu;
+use crate::ui::grid::minimap::GridMinimap;
+use crate::ui::grid::region_menu::region_menu;
+use crate::ui::grid::ruler_menu::ruler_menu;
+use crate::ui::grid::track_list::{GridTrackList, grid_point};
+use gpui::{
+    App, Div, Entity, ExternalPaths, Hsla, InteractiveElement, IntoElement, ParentElement, RenderOnce, Styled, Window,
+    div, px, rems, rgb, rgba,
+};
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::menu::{ContextMenuExt, DropdownMenu};
 use gpui_component::scroll::{Scrollbar, ScrollbarShow};
 use gpui_component::slider::Slider;
-use gpui_component::{ActiveTheme, gray_400};
+use gpui_component::{ActiveTheme, Sizable, gray_400};
+
+// the slider step, closer positions already show the viewport's zoom
+const ZOOM_SLIDER_TOLERANCE: f32 = 0.01;
 
 #[derive(IntoElement)]
 pub struct GridProjectView {
@@ -35,6 +53,11 @@ impl GridProjectView {
             .justify_between()
             .bg(self.head_bg)
             .child(app_panel_title().child("Timeline"))
+            .child(self.transport_controls(cx))
+            .child(self.history_button(cx))
+            .child(self.snap_button(cx))
+            .child(self.bounce_button())
+            .child(self.zoom_buttons(cx))
             .child(
                 div()
                     .rounded_xl()
@@ -51,20 +74,142 @@ impl GridProjectView {
             )
     }
 
+    fn transport_controls(&self, cx: &mut App) -> Div {
+        let project = self.project.clone();
+        let state = self.project.read(cx);
+        let transport = &state.transport;
+        let position = state
+            .viewport
+            .ruler_format()
+            .format_frame(transport.position(), transport.sample_rate());
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_2()
+            .child(
+                Button::new("grid-play")
+                    .label(if transport.is_playing() { "Stop" } else { "Play" })
+                    .small()
+                    .ghost()
+                    .on_click(move |_, window, cx| toggle_playback(&project, window, cx)),
+            )
+            .child(div().text_size(rems(0.75)).text_color(gray_400()).child(position))
+    }
+
+    fn history_button(&self, cx: &mut App) -> impl IntoElement {
+        let project = self.project.clone();
+        let history = &self.project.read(cx).history;
+        Button::new("grid-history")
+            .label(format!("History ({})", history.position()))
+            .small()
+            .ghost()
+            .disabled(!history.can_undo() && !history.can_redo())
+            .dropdown_menu(move |menu, _, cx| history_menu(menu, &project, cx))
+    }
+
+    // cmd held during a drag bypasses snapping
+    fn snap_button(&self, cx: &mut App) -> impl IntoElement {
+        let project = self.project.clone();
+        let snapping = self.project.read(cx).selection.snapping();
+        Button::new("grid-snap")
+            .label("Snap")
+            .small()
+            .with_variant(if snapping {
+                ButtonVariant::Primary
+            } else {
+                ButtonVariant::Ghost
+            })
+            .on_click(move |_, _, cx| {
+                project.update(cx, |grid, cx| {
+                    grid.selection.set_snapping(!grid.selection.snapping());
+                    cx.notify();
+                })
+            })
+    }
+
+    // cmd-scroll zooms around the pointer, cmd-= and cmd-- around the edit cursor
+    fn zoom_buttons(&self, cx: &mut App) -> Div {
+        let state = self.project.read(cx);
+        let has_selection = state.selection_span().is_some();
+        let (can_back, can_forward) = (state.viewport.can_zoom_back(), state.viewport.can_zoom_forward());
+        div()
+            .flex()
+            .flex_row()
+            .items_center()
+            .gap_1()
+            .child(self.zoom_button("grid-zoom-back", "Back", !can_back, |grid| grid.viewport.zoom_back()))
+            .child(self.zoom_button("grid-zoom-forward", "Forward", !can_forward, |grid| {
+                grid.viewport.zoom_forward()
+            }))
+            .child(self.zoom_button("grid-zoom-fit", "Fit", false, |grid| grid.viewport.zoom_to_fit()))
+            .child(self.zoom_button(
+                "grid-zoom-selection",
+                "Selection",
+                !has_selection,
+                GridState::zoom_to_selection,
+            ))
+    }
+
+    fn zoom_button(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        disabled: bool,
+        zoom: impl Fn(&GridState) + 'static,
+    ) -> impl IntoElement {
+        let project = self.project.clone();
+        Button::new(id)
+            .label(label)
+            .small()
+            .ghost()
+            .disabled(disabled)
+            .on_click(move |_, _, cx| {
+                project.update(cx, |grid, cx| {
+                    zoom(grid);
+                    cx.notify();
+                })
+            })
+    }
+
+    fn bounce_button(&self) -> impl IntoElement {
+        let project = self.project.clone();
+        Button::new("grid-bounce")
+            .label("Bounce")
+            .small()
+            .ghost()
+            .dropdown_menu(move |menu, _, cx| bounce_menu(menu, &project, cx))
+    }
+
     fn grid_header(&self, cx: &mut App) -> Div {
         let project = self.project.read(cx);
         let header_width = project.viewport.header_size().width;
 
+        let minimap = GridMinimap::new(project.tracks(), &self.project, &project.viewport);
+
         div()
             .flex()
             .flex_row()
             .child(self.tracks_head(cx).w(header_width))
-            .child(self.grid_ruler(cx).flex_1())
+            .child(
+                div()
+                    .flex_1()
+                    .flex()
+                    .flex_col()
+                    .child(minimap)
+                    .child(self.grid_ruler(cx)),
+            )
     }
 
     fn tracks_head(&self, cx: &mut App) -> Div {
+        let project = self.project.clone();
+        let menu_project = self.project.clone();
+        let format = self.project.read(cx).viewport.ruler_format();
         let theme = cx.theme();
         div()
+            .flex()
+            .flex_col()
+            .gap_1()
             .px_3()
             .py_2()
             .bg(self.head_bg)
@@ -75,29 +220,91 @@ impl GridProjectView {
             .border_b(px(1.0))
             .border_t(px(1.0))
             .border_color(theme.border)
-            .child("Track / Pass")
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .child("Track / Pass")
+                    .child(
+                        Button::new("grid-add-track")
+                            .label("Add track")
+                            .xsmall()
+                            .ghost()
+                            .on_click(move |_, _, cx| {
+                                project.update(cx, |grid, cx| {
+                                    grid.add_track(grid.tracks().len());
+                                    cx.notify();
+                                })
+                            }),
+                    ),
+            )
+            .child(
+                Button::new("grid-ruler-format")
+                    .label(format.title())
+                    .xsmall()
+                    .ghost()
+                    .dropdown_menu(move |menu, window, cx| ruler_menu(menu, &menu_project, window, cx)),
+            )
     }
 
     fn grid_ruler(&self, cx: &mut App) -> Div {
+        let grid = self.project.clone();
+        let menu_grid = self.project.clone();
         let project = self.project.read(cx);
+        let viewport = project.viewport.clone();
+        let offset_x = viewport.frame_to_scroll_offset(project.transport.position());
+        let visible = offset_x >= px(0.0) && offset_x <= viewport.viewport_size().width;
 
         div()
+            .relative()
             .flex()
             .flex_col()
             .child(GridTickLabelView::new(project.viewport.ticks()))
             .child(GridTickView::new(project.viewport.ticks()))
+            .child(
+                div()
+                    .id("grid-markers")
+                    .child(GridMarkerView::new(
+                        project.markers.markers(),
+                        project.markers.loop_range(),
+                        &project.viewport,
+                    ))
+                    .context_menu(move |menu, window, cx| marker_menu(menu, &menu_grid, window, cx)),
+            )
+            .child(
+                PlayheadView::new(visible.then_some(offset_x))
+                    .on_seek(move |x, _, cx| {
+                        let frame = viewport.scroll_offset_to_frame(x);
+                        grid.update(cx, |grid, cx| {
+                            grid.locate(frame);
+                            cx.notify();
+                        });
+                    })
+                    .absolute()
+                    .top_0()
+                    .left_0()
+                    .size_full(),
+            )
     }
 
     fn tracks(&self, _: &mut Window, cx: &mut App) -> Div {
+        let menu_project = self.project.clone();
+        let (drag_project, drop_project) = (self.project.clone(), self.project.clone());
         let project = self.project.read(cx);
+        let comparison = project
+            .comparison
+            .is_open()
+            .then(|| GridComparisonLane::new(&self.project, &project.comparison, &project.viewport));
 
-        div().flex_1().flex().flex_col().child(
+        div().flex_1().flex().flex_col().children(comparison).child(
             div()
                 .flex_1()
                 .flex()
                 .flex_row()
                 .child(
-                    GridHeaderList::new(project.tracks(), &project.viewport)
+                    GridHeaderList::new(project.tracks(), &project.viewport, &project.transport, &self.project)
                         .h_full()
                         .w(project.viewport.header_size().width),
                 )
@@ -108,9 +315,39 @@ impl GridProjectView {
                         .relative()
                         .bg(rgb(0x2E2E2E))
                         .child(
-                            GridTrackList::new(project.tracks(), &project.viewport)
+                            div()
+                                .id("grid-track-list")
                                 .absolute()
-                                .size_full(),
+                                .size_full()
+                                // files dropped from the file manager become regions under the pointer
+                                .drag_over::<ExternalPaths>(|style, _, _, _| style.bg(rgba(0x4FB3FF14)))
+                                .on_drag_move::<ExternalPaths>(move |event, _, cx| {
+                                    let viewport = drag_project.read(cx).viewport.clone();
+                                    let point = grid_point(&viewport, event.bounds, event.event.position);
+                                    drag_project.update(cx, |grid, _| grid.set_drop_point(point));
+                                })
+                                .on_drop(move |paths: &ExternalPaths, window, cx| {
+                                    let point = drop_project.read(cx).drop_point();
+                                    if let Some(point) = point {
+                                        drop_files(&drop_project, paths, point, window, cx);
+                                    }
+                                })
+                                .child(
+                                    div()
+                                        .size_full()
+                                        .child(
+                                            GridTrackList::new(
+                                                project.tracks(),
+                                                &project.viewport,
+                                                &project.selection,
+                                                &project.history,
+                                                &project.transport,
+                                                &project.markers,
+                                            )
+                                            .size_full(),
+                                        )
+                                        .context_menu(move |menu, _, cx| region_menu(menu, &menu_project, cx)),
+                                ),
                         )
                         .child(
                             Scrollbar::new(&project.viewport) //
@@ -124,6 +361,7 @@ impl GridProjectView {
 
 impl RenderOnce for GridProjectView {
     fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
+        sync_zoom_slider(&self.project, window, cx);
         div()
             .size_full()
             .flex()
@@ -133,3 +371,12 @@ impl RenderOnce for GridProjectView {
             .child(self.tracks(window, cx))
     }
 }
+
+// zooms from shortcuts, buttons, the wheel and the minimap bypass the slider, it follows the viewport instead
+fn sync_zoom_slider(project: &Entity<GridState>, window: &mut Window, cx: &mut App) {
+    let state = project.read(cx);
+    let (value, slider) = (state.viewport.scale_log() as f32, state.x_slider.clone());
+    if (slider.read(cx).value().start() - value).abs() > ZOOM_SLIDER_TOLERANCE {
+        slider.update(cx, |slider, cx| slider.set_value(value, window, cx));
+    }
+}
@@ -2,11 +2,13 @@ mod grid;
 mod info;
 mod input;
 mod listener;
+mod notify;
 mod panel;
 mod session;
 
 pub use grid::*;
 pub use info::*;
 pub use input::*;
+pub use notify::*;
 pub use panel::*;
 pub use session::*;
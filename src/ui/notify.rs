@@ -0,0 +1,34 @@
+use crate::components::waveform::WaveClip;
+use gpui::{App, SharedString, Window};
+use gpui_component::WindowExt;
+use gpui_component::notification::Notification;
+use log::warn;
+use std::fmt::Display;
+
+// deferred, so it also works while the root view is still being built
+fn push_notification(window: &mut Window, cx: &mut App, notification: Notification) {
+    window.defer(cx, move |window, cx| window.push_notification(notification, cx));
+}
+
+pub fn notify_error(window: &mut Window, cx: &mut App, title: impl Into<SharedString>, error: impl Display) {
+    let message = SharedString::from(error.to_string());
+    push_notification(window, cx, Notification::error(message).title(title));
+}
+
+pub fn notify_warning(window: &mut Window, cx: &mut App, title: impl Into<SharedString>, message: impl Display) {
+    let message = SharedString::from(message.to_string());
+    push_notification(window, cx, Notification::warning(message).title(title));
+}
+
+pub fn notify_success(window: &mut Window, cx: &mut App, title: impl Into<SharedString>, message: impl Display) {
+    let message = SharedString::from(message.to_string());
+    push_notification(window, cx, Notification::success(message).title(title));
+}
+
+/// Warns when only the valid prefix of a truncated file was loaded.
+pub fn notify_clip_truncation(window: &mut Window, cx: &mut App, clip: &WaveClip) {
+    if let Some(truncation) = clip.truncation() {
+        warn!("{truncation}");
+        notify_warning(window, cx, "Audio file is truncated", truncation);
+    }
+}
@@ -2,6 +2,7 @@ use crate::ui::listener::StateEventListener;
 use crate::ui::{SessionState, app_input_label, app_input_text, app_panel_text, app_panel_title};
 use gpui::{App, Entity, IntoElement, ParentElement, RenderOnce, StyleRefinement, Styled, Window, div, rgb};
 use gpui_component::button::{Button, ButtonCustomVariant, ButtonVariants};
+use gpui_component::checkbox::Checkbox;
 use gpui_component::input::Input;
 use gpui_component::select::Select;
 
@@ -43,6 +44,16 @@ impl RenderOnce for SessionPanel {
             )
             .child(app_input_label().child("Audio Device").mt_4())
             .child(Select::new(&current_state.select_device_state))
+            .child(app_input_label().child("Sample Rate Conversion").mt_3())
+            .child(Select::new(&current_state.select_resampling_state))
+            .child(
+                div().mt_2().child(
+                    Checkbox::new("resampling_convert_back")
+                        .label("Convert captures back to source rate")
+                        .checked(current_state.resampling.convert_back)
+                        .on_click(window.event_listener_for(&self.state, SessionState::toggle_convert_back)),
+                ),
+            )
             .child(app_input_label().child("Input WAV File").mt_3())
             .child(
                 div()
@@ -67,7 +78,7 @@ impl RenderOnce for SessionPanel {
                             .on_click(window.event_listener_for(&self.state, SessionState::select_source_file)),
                     ),
             )
-            .child(app_input_text().child("Formats: WAV PCM 16/24/32-bit, mono/stereo."))
+            .child(app_input_text().child("Formats: WAV/RF64/W64 PCM 16/24/32-bit, mono/stereo."))
             .child(app_input_label().child("Output Directory").mt_3())
             .child(
                 div()
@@ -1,8 +1,8 @@
-use crate::audio::{CoreAudioDevice, CoreAudioDriver, RecordSession};
+use crate::audio::{CoreAudioDevice, CoreAudioDriver, RecordSession, ResamplerQuality, SessionResampling};
 use crate::components::grid::GridState;
 use crate::components::track::Track;
 use crate::components::waveform::WaveClip;
-use crate::ui::{ClipInfoState, SessionStatus};
+use crate::ui::{ClipInfoState, SessionStatus, notify_clip_truncation, notify_error};
 use anyhow::{Result, anyhow};
 use async_std::channel::unbounded;
 use async_std::prelude::FutureExt;
@@ -16,6 +16,7 @@ use std::path::PathBuf;
 
 pub struct SessionState {
     pub(super) select_device_state: Entity<SelectState<Vec<DeviceSelectItem>>>,
+    pub(super) select_resampling_state: Entity<SelectState<Vec<ResamplingSelectItem>>>,
     pub(super) iteration_count_state: Entity<InputState>,
     pub(super) source_path_state: Entity<InputState>,
     pub(super) destination_path_state: Entity<InputState>,
@@ -26,6 +27,7 @@ pub struct SessionState {
     pub(super) current_source_path: Option<PathBuf>,
     pub(super) current_destination_path: Option<PathBuf>,
     current_iteration_count: Option<u32>,
+    pub(super) resampling: SessionResampling,
     pub(super) session_status: SessionStatus,
 
     _subscriptions: Vec<Subscription>,
@@ -37,14 +39,17 @@ impl SessionState {
         cx: &mut Context<Self>,
         grid_state: &Entity<GridState>,
         info_state: &Entity<ClipInfoState>,
-    ) -> Result<Self> {
+    ) -> Self {
         let driver = CoreAudioDriver;
 
-        let devices: Vec<DeviceSelectItem> = driver
-            .list_devices()?
-            .iter()
-            .map(|device| DeviceSelectItem::from(device))
-            .collect();
+        let devices: Vec<DeviceSelectItem> = match driver.list_devices() {
+            Ok(devices) => devices.iter().map(|device| DeviceSelectItem::from(device)).collect(),
+            Err(error) => {
+                error!("failed to list devices: {error}");
+                notify_error(window, cx, "Failed to list audio devices", error);
+                Vec::new()
+            }
+        };
 
         let default = devices.iter().enumerate().find_map(|(index, device)| {
             if device.is_stereo() {
@@ -60,6 +65,28 @@ impl SessionState {
             SelectEvent::Confirm(value) => {
                 info!("device selected: {:?}", value);
                 this.current_device_id = value.clone();
+                this.grid_state.update(cx, |grid, cx| {
+                    grid.transport.set_device(*value);
+                    cx.notify();
+                });
+                cx.notify();
+            }
+        });
+
+        let resampling = SessionResampling::default();
+        let resampling_items = ResamplingSelectItem::all();
+        let default_resampling = resampling_items
+            .iter()
+            .position(|item| item.quality == resampling.quality)
+            .map(IndexPath::new);
+        let select_resampling_state = cx.new(|cx| SelectState::new(resampling_items, default_resampling, window, cx));
+
+        let select_resampling_sub = cx.subscribe(&select_resampling_state, |this, _, event, cx| match event {
+            SelectEvent::Confirm(value) => {
+                info!("resampling selected: {:?}", value);
+                if let Some(quality) = value {
+                    this.resampling.quality = *quality;
+                }
                 cx.notify();
             }
         });
@@ -109,6 +136,8 @@ impl SessionState {
             },
         );
 
+        grid_state.update(cx, |grid, _| grid.transport.set_device(default.map(|p| p.1)));
+
         let source_file_state = cx.new(|cx| {
             InputState::new(window, cx) //
                 .placeholder("Select a source file")
@@ -119,8 +148,9 @@ impl SessionState {
                 .placeholder("Select a destination dir")
         });
 
-        Ok(Self {
+        Self {
             select_device_state,
+            select_resampling_state,
             iteration_count_state,
             source_path_state: source_file_state,
             destination_path_state: destination_dir_state,
@@ -130,9 +160,15 @@ impl SessionState {
             current_source_path: None,
             current_destination_path: None,
             current_iteration_count: Some(100),
+            resampling,
             session_status: SessionStatus::IDLE,
-            _subscriptions: vec![select_device_sub, iteration_count_input_sub, iteration_count_inc_sub],
-        })
+            _subscriptions: vec![
+                select_device_sub,
+                select_resampling_sub,
+                iteration_count_input_sub,
+                iteration_count_inc_sub,
+            ],
+        }
     }
 
     pub fn select_source_file(&mut self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
@@ -155,12 +191,13 @@ impl SessionState {
                 });
                 match WaveClip::open(source_file.clone()) {
                     Ok(clip) => {
+                        notify_clip_truncation(window, cx, &clip);
                         cx.update_entity(&state.info_state, |view, cx| {
                             view.set_clip(&clip);
                             cx.notify()
                         });
                         cx.update_entity(&state.grid_state, |grid, cx| {
-                            grid.update_tracks(|tracks| {
+                            grid.update_tracks("Open source", |tracks| {
                                 tracks.clear();
                                 let track = Track::new(clip.metadata().filename());
                                 track.add_clip(&clip, 0);
@@ -169,7 +206,10 @@ impl SessionState {
                             cx.notify();
                         });
                     }
-                    Err(error) => error!("failed to open source track: {}", error),
+                    Err(error) => {
+                        error!("failed to open source track: {}", error);
+                        notify_error(window, cx, "Failed to open source file", error);
+                    }
                 }
             })
         })
@@ -190,6 +230,14 @@ impl SessionState {
             let destination_dir = paths.first().ok_or(anyhow!("no dir"))?;
             view.update_in(window, |view, window, cx| {
                 view.current_destination_path = Some(destination_dir.clone());
+                let result = view.grid_state.update(cx, |grid, cx| {
+                    cx.notify();
+                    grid.open_markers(destination_dir)
+                });
+                if let Err(error) = result {
+                    error!("failed to open markers: {error}");
+                    notify_error(window, cx, "Failed to open markers", error);
+                }
                 cx.notify();
                 cx.update_entity(&view.destination_path_state, |view, cx| {
                     view.set_value(destination_dir.to_string_lossy().to_string(), window, cx);
@@ -199,6 +247,11 @@ impl SessionState {
         .detach();
     }
 
+    pub fn toggle_convert_back(&mut self, checked: &bool, _: &mut Window, cx: &mut Context<Self>) {
+        self.resampling.convert_back = *checked;
+        cx.notify();
+    }
+
     pub fn record(&mut self, _: &ClickEvent, window: &mut Window, cx: &mut Context<Self>) {
         match &self.session_status {
             SessionStatus::RUNNING(sender) => {
@@ -214,6 +267,7 @@ impl SessionState {
             && let Some(iteration_count) = self.current_iteration_count.clone()
             && self.session_status.is_stopped()
         {
+            let resampling = self.resampling;
             let (sender, receiver) = unbounded();
             self.session_status = SessionStatus::RUNNING(sender);
             cx.notify();
@@ -233,7 +287,20 @@ impl SessionState {
                     iteration += 1;
 
                     info!("new session");
-                    let mut session = RecordSession::new(device_id, source_path, destination_path.clone()).await?;
+                    let session =
+                        RecordSession::new(device_id, source_path, destination_path.clone(), resampling).await;
+                    let mut session = match session {
+                        Ok(session) => session,
+                        Err(error) => {
+                            error!("failed to start recording: {error}");
+                            entity.update_in(cx, |state, window, cx| {
+                                notify_error(window, cx, "Failed to start recording", error);
+                                state.session_status = SessionStatus::FAILED;
+                                cx.notify();
+                            })?;
+                            return Ok(());
+                        }
+                    };
 
                     info!("start recording");
                     session.start().await?;
@@ -250,11 +317,23 @@ impl SessionState {
                     match session.stop().await {
                         Ok(_) => {
                             info!("successfully finished recording");
-                            let clip = WaveClip::open(destination_path)?;
-                            entity.update(cx, |state, cx| {
+                            let clip = match WaveClip::open(destination_path) {
+                                Ok(clip) => clip,
+                                Err(error) => {
+                                    error!("failed to open recording: {error}");
+                                    entity.update_in(cx, |state, window, cx| {
+                                        notify_error(window, cx, "Failed to open recording", error);
+                                        state.session_status = SessionStatus::FAILED;
+                                        cx.notify();
+                                    })?;
+                                    return Ok(());
+                                }
+                            };
+                            entity.update_in(cx, |state, window, cx| {
+                                notify_clip_truncation(window, cx, &clip);
                                 info!("update grid_state");
                                 state.grid_state.update(cx, |grid, cx| {
-                                    grid.update_tracks(|tracks| {
+                                    grid.update_tracks("Add recording", |tracks| {
                                         let track = Track::new(clip.metadata().filename());
                                         track.add_clip(&clip, 0);
                                         tracks.push(track);
@@ -270,7 +349,8 @@ impl SessionState {
                         }
                         Err(error) => {
                             error!("failed to finish recording: {error}");
-                            entity.update(cx, |state, _| {
+                            entity.update_in(cx, |state, window, cx| {
+                                notify_error(window, cx, "Failed to finish recording", error);
                                 state.session_status = SessionStatus::FAILED;
                             })?;
                         }
@@ -326,3 +406,35 @@ impl SelectItem for DeviceSelectItem {
         &self.device_id
     }
 }
+
+#[derive(Clone)]
+pub struct ResamplingSelectItem {
+    quality: Option<ResamplerQuality>,
+    title: SharedString,
+}
+
+impl ResamplingSelectItem {
+    pub fn all() -> Vec<Self> {
+        let device = Self {
+            quality: None,
+            title: SharedString::new_static("Device (OS converter)"),
+        };
+        let builtin = ResamplerQuality::ALL.into_iter().map(|quality| Self {
+            quality: Some(quality),
+            title: SharedString::from(format!("Built-in, {}", quality.title())),
+        });
+        std::iter::once(device).chain(builtin).collect()
+    }
+}
+
+impl SelectItem for ResamplingSelectItem {
+    type Value = Option<ResamplerQuality>;
+
+    fn title(&self) -> SharedString {
+        self.title.clone()
+    }
+
+    fn value(&self) -> &Self::Value {
+        &self.quality
+    }
+}